@@ -0,0 +1,374 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use sxd_document::parser as DomParser;
+use sxd_document::dom::Element as DomElement;
+
+use parser::XML_NS_URI;
+use resolver::{SchemaResolver, resolve_location, to_path};
+
+static CATALOG_NS_URI: &str = "urn:oasis:names:tc:entity:xmlns:xml:catalog";
+
+// delegation and nextCatalog entries may form cycles, catalogs deeper than this are ignored
+const MAX_DEPTH: usize = 16;
+
+/// Raised when a catalog file can not be read
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum CatalogError {
+    Io(io::ErrorKind),
+    NotWellFormed,
+    // the document element is not the catalog element of the OASIS namespace
+    NoCatalogRoot,
+}
+
+/// The kinds of identifiers that a catalog maps
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+enum Space {
+    System,
+    Public,
+    Uri,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+enum Rule {
+    // system, public and uri entries
+    Map { identifier: String, uri: String },
+    // rewriteSystem and rewriteURI, the start of the identifier is replaced by the prefix
+    Rewrite { start: String, prefix: String },
+    // delegateSystem, delegatePublic and delegateURI, identifiers with the start are only
+    // resolved by the delegated catalogs
+    Delegate { start: String, catalog: String },
+}
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+struct Entry {
+    space: Space,
+    rule: Rule,
+}
+
+/// An OASIS XML catalog that maps the system identifiers and URIs of schema documents, e.g.
+/// schemaLocations and namespace names, to local copies. The URIs of entries are resolved
+/// against the location of the catalog or their xml:base, delegated and next catalogs are read
+/// once along with the catalog
+/// see https://www.oasis-open.org/committees/download.php/14809/xml-catalogs.html
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Catalog {
+    entries: Vec<Entry>,
+    next_catalogs: Vec<String>,
+    // the delegated and next catalogs that this catalog names, directly or through the ones it
+    // names, by their URI. Catalogs that can not be read are left out
+    catalogs: HashMap<String, Catalog>,
+}
+
+impl Catalog {
+    /// Reads a catalog file, relative URIs of its entries are resolved against its path
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Catalog, CatalogError> {
+        let mut catalog = Catalog::read(path.as_ref())?;
+        catalog.read_catalogs();
+        Ok(catalog)
+    }
+
+    /// Parses the text of a catalog, relative URIs of its entries are resolved against the base
+    pub fn from_source(source: &str, base: &str) -> Result<Catalog, CatalogError> {
+        let mut catalog = Catalog::parse(source, base)?;
+        catalog.read_catalogs();
+        Ok(catalog)
+    }
+
+    /// Reads a catalog file without the catalogs that it names
+    fn read(path: &Path) -> Result<Catalog, CatalogError> {
+        let source = fs::read_to_string(path).map_err(|error| CatalogError::Io(error.kind()))?;
+        Catalog::parse(&source, &path.to_string_lossy())
+    }
+
+    /// Parses the text of a catalog without reading the catalogs that it names
+    fn parse(source: &str, base: &str) -> Result<Catalog, CatalogError> {
+        let package = DomParser::parse(source).map_err(|_| CatalogError::NotWellFormed)?;
+        let document = package.as_document();
+        let root = document.root().children().into_iter()
+            .filter_map(|child| child.element())
+            .find(|element| is_catalog_element(element, "catalog"))
+            .ok_or(CatalogError::NoCatalogRoot)?;
+        let mut catalog = Catalog { entries: Vec::new(), next_catalogs: Vec::new(), catalogs: HashMap::new() };
+        catalog.parse_entries(root, &element_base(&root, base));
+        Ok(catalog)
+    }
+
+    /// The URIs of the delegated and next catalogs of the entries
+    fn named_catalogs(&self) -> Vec<String> {
+        self.entries.iter()
+            .filter_map(|entry| match entry.rule {
+                Rule::Delegate { ref catalog, .. } => Some(catalog.clone()),
+                _ => None,
+            })
+            .chain(self.next_catalogs.iter().cloned())
+            .collect()
+    }
+
+    /// Reads every catalog that is named by this one or by a catalog that it names once, so that
+    /// resolving identifiers does not read files again. Cycles end at catalogs that were read
+    fn read_catalogs(&mut self) {
+        let mut unreadable = HashSet::new();
+        let mut pending = self.named_catalogs();
+        while let Some(uri) = pending.pop() {
+            if self.catalogs.contains_key(&uri) || unreadable.contains(&uri) {
+                continue;
+            }
+            match Catalog::read(&to_path(&uri)) {
+                Ok(catalog) => {
+                    pending.extend(catalog.named_catalogs());
+                    self.catalogs.insert(uri, catalog);
+                }
+                Err(_) => {
+                    unreadable.insert(uri);
+                }
+            }
+        }
+    }
+
+    /// Reads the entries of a catalog or group element with its base URI, groups only change
+    /// the base URI
+    fn parse_entries(&mut self, parent: DomElement, base: &str) {
+        for element in parent.children().into_iter().filter_map(|child| child.element()) {
+            if element.name().namespace_uri() != Some(CATALOG_NS_URI) {
+                continue;
+            }
+            let base = element_base(&element, base);
+            let attribute = |name| element.attribute_value(name);
+            let uri = |name| attribute(name).map(|uri| resolve_location(&base, uri.trim()));
+            let entry = match element.name().local_part() {
+                "group" => {
+                    self.parse_entries(element, &base);
+                    continue;
+                }
+                "nextCatalog" => {
+                    self.next_catalogs.extend(uri("catalog"));
+                    continue;
+                }
+                "system" => map(Space::System, attribute("systemId"), uri("uri")),
+                "public" => map(Space::Public, attribute("publicId").map(normalize_public), uri("uri")),
+                "uri" => map(Space::Uri, attribute("name"), uri("uri")),
+                "rewriteSystem" => rewrite(Space::System, attribute("systemIdStartString"), uri("rewritePrefix")),
+                "rewriteURI" => rewrite(Space::Uri, attribute("uriStartString"), uri("rewritePrefix")),
+                "delegateSystem" => delegate(Space::System, attribute("systemIdStartString"), uri("catalog")),
+                "delegatePublic" =>
+                    delegate(Space::Public, attribute("publicIdStartString").map(normalize_public), uri("catalog")),
+                "delegateURI" => delegate(Space::Uri, attribute("uriStartString"), uri("catalog")),
+                _ => None,
+            };
+            self.entries.extend(entry);
+        }
+    }
+
+    /// Maps the system identifier of an external resource, e.g. a schemaLocation
+    /// see https://www.oasis-open.org/committees/download.php/14809/xml-catalogs.html#s.ext.resolv
+    pub fn resolve_system(&self, system_id: &str) -> Option<String> {
+        self.resolve(&self.catalogs, Space::System, system_id, 0)
+    }
+
+    pub fn resolve_public(&self, public_id: &str) -> Option<String> {
+        self.resolve(&self.catalogs, Space::Public, &normalize_public(public_id), 0)
+    }
+
+    /// Maps a URI which is not the identifier of an external resource, e.g. a namespace name
+    /// see https://www.oasis-open.org/committees/download.php/14809/xml-catalogs.html#s.uri.resolv
+    pub fn resolve_uri(&self, uri: &str) -> Option<String> {
+        self.resolve(&self.catalogs, Space::Uri, uri, 0)
+    }
+
+    /// Exact matches win over rewrites and rewrites over delegation, the longest start string
+    /// wins among rewrites and delegates. The next catalogs are only consulted if no entry of
+    /// this catalog matches. Delegated and next catalogs are looked up among the catalogs that
+    /// were read along with the first one
+    fn resolve(&self, catalogs: &HashMap<String, Catalog>, space: Space, identifier: &str, depth: usize)
+               -> Option<String> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let rules = || self.entries.iter().filter(|entry| entry.space == space).map(|entry| &entry.rule);
+        let exact = rules().find_map(|rule| match *rule {
+            Rule::Map { identifier: ref mapped, ref uri } if mapped == identifier => Some(uri.clone()),
+            _ => None,
+        });
+        if exact.is_some() {
+            return exact;
+        }
+        let rewrite = rules()
+            .filter_map(|rule| match *rule {
+                Rule::Rewrite { ref start, ref prefix } if identifier.starts_with(start.as_str()) => Some((start, prefix)),
+                _ => None,
+            })
+            .max_by_key(|&(start, _)| start.len());
+        if let Some((start, prefix)) = rewrite {
+            return Some(format!("{}{}", prefix, &identifier[start.len()..]));
+        }
+        let mut delegates: Vec<(&String, &String)> = rules()
+            .filter_map(|rule| match *rule {
+                Rule::Delegate { ref start, ref catalog } if identifier.starts_with(start.as_str()) => {
+                    Some((start, catalog))
+                }
+                _ => None,
+            })
+            .collect();
+        if !delegates.is_empty() {
+            delegates.sort_by_key(|&(start, _)| ::std::cmp::Reverse(start.len()));
+            return delegates.iter()
+                .filter_map(|&(_, catalog)| catalogs.get(catalog))
+                .find_map(|catalog| catalog.resolve(catalogs, space, identifier, depth + 1));
+        }
+        self.next_catalogs.iter()
+            .filter_map(|catalog| catalogs.get(catalog))
+            .find_map(|catalog| catalog.resolve(catalogs, space, identifier, depth + 1))
+    }
+}
+
+/// Looks up schema locations as system identifiers and then as URIs. Locations that are not
+/// mapped are read as they are if they are local files, so that nothing is fetched from the
+/// network, only locations that can not be read either are looked up by the namespace name
+impl SchemaResolver for Catalog {
+    fn resolve(&self, namespace: Option<&str>, location: &str) -> Option<String> {
+        if let Some(uri) = self.resolve_system(location).or_else(|| self.resolve_uri(location)) {
+            return read_local(&uri);
+        }
+        read_local(location).or_else(|| read_local(&self.resolve_uri(namespace?)?))
+    }
+}
+
+/// Reads a schema document if it is a local file
+fn read_local(uri: &str) -> Option<String> {
+    if uri.contains("://") && !uri.starts_with("file://") {
+        return None;
+    }
+    fs::read_to_string(to_path(uri)).ok()
+}
+
+fn is_catalog_element(element: &DomElement, local_name: &str) -> bool {
+    element.name().namespace_uri() == Some(CATALOG_NS_URI) && element.name().local_part() == local_name
+}
+
+/// The base URI of an element, an xml:base attribute is resolved against the base of its parent
+fn element_base(element: &DomElement, base: &str) -> String {
+    match element.attribute_value((XML_NS_URI, "base")) {
+        Some(element_base) => resolve_location(base, element_base.trim()),
+        None => base.to_string(),
+    }
+}
+
+/// Public identifiers are compared with their whitespace collapsed
+/// see https://www.oasis-open.org/committees/download.php/14809/xml-catalogs.html#s.pubid
+fn normalize_public(public_id: &str) -> String {
+    public_id.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn map<S: Into<String>>(space: Space, identifier: Option<S>, uri: Option<String>) -> Option<Entry> {
+    Some(Entry { space, rule: Rule::Map { identifier: identifier?.into(), uri: uri? } })
+}
+
+fn rewrite(space: Space, start: Option<&str>, prefix: Option<String>) -> Option<Entry> {
+    Some(Entry { space, rule: Rule::Rewrite { start: start?.to_string(), prefix: prefix? } })
+}
+
+fn delegate<S: Into<String>>(space: Space, start: Option<S>, catalog: Option<String>) -> Option<Entry> {
+    Some(Entry { space, rule: Rule::Delegate { start: start?.into(), catalog: catalog? } })
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use parser::SchemaError;
+    use parser::schema::Schema;
+    use schema_set::{SchemaSet, SchemaSetError};
+    use super::*;
+
+    static CATALOG: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/catalog/catalog.xml");
+
+    fn local(path: &str) -> String {
+        format!("{}/tests/catalog/{}", env!("CARGO_MANIFEST_DIR"), path)
+    }
+
+    #[test]
+    fn entries() {
+        let catalog = Catalog::from_file(CATALOG).expect("Failed to read catalog");
+        assert_eq!(Some(local("schemas/order.xsd")), catalog.resolve_system("http://example.com/order.xsd"));
+        assert_eq!(Some(local("schemas/order.xsd")), catalog.resolve_uri("urn:example:order"));
+        // the exact match wins over the rewrite and the longest rewrite wins
+        assert_eq!(Some(local("schemas/v2/types.xsd")), catalog.resolve_system("http://example.com/schemas/v2/types.xsd"));
+        assert_eq!(Some(local("schemas/common.xsd")), catalog.resolve_system("http://example.com/schemas/common.xsd"));
+        assert_eq!(Some(local("schemas/legacy/types.xsd")),
+                   catalog.resolve_system("http://example.com/schemas/v1/types.xsd"));
+        assert_eq!(Some(local("schemas/other.xsd")), catalog.resolve_uri("urn:example:other"));
+        assert_eq!(Some(local("schemas/other.xsd")), catalog.resolve_uri("http://example.com/xsd/other.xsd"));
+        assert_eq!(Some(local("schemas/partner.xsd")), catalog.resolve_system("http://partner.org/schemas/partner.xsd"));
+        assert_eq!(Some(local("schemas/partner.xsd")), catalog.resolve_uri("urn:partner:invoice"));
+        assert_eq!(Some(local("schemas/partner.xsd")), catalog.resolve_public("-//Partner//DTD   Invoice//EN"));
+        // delegated prefixes are only resolved by the delegated catalogs
+        assert_eq!(None, catalog.resolve_system("http://partner.org/schemas/unknown.xsd"));
+        // found in the next catalog
+        assert_eq!(Some(local("schemas/next.xsd")), catalog.resolve_system("http://example.org/next.xsd"));
+        assert_eq!(None, catalog.resolve_system("http://example.org/unknown.xsd"));
+
+        assert_eq!(Err(CatalogError::NoCatalogRoot), Catalog::from_source("<catalog/>", ""));
+        assert_eq!(Err(CatalogError::Io(io::ErrorKind::NotFound)), Catalog::from_file(local("missing.xml")));
+    }
+
+    #[test]
+    fn catalogs_read_once() {
+        let directory = ::std::env::temp_dir().join(format!("sxd-xsd-catalog-{}", ::std::process::id()));
+        fs::create_dir_all(directory.join("partner")).expect("Failed to create directory");
+        for path in &["catalog.xml", "next.xml", "partner/catalog.xml"] {
+            fs::copy(local(path), directory.join(path)).expect("Failed to copy catalog");
+        }
+        let catalog = Catalog::from_file(directory.join("catalog.xml")).expect("Failed to read catalog");
+        fs::remove_dir_all(&directory).expect("Failed to remove directory");
+
+        let path = |path: &str| Some(directory.join(path).to_string_lossy().into_owned());
+        assert_eq!(path("schemas/next.xsd"), catalog.resolve_system("http://example.org/next.xsd"));
+        assert_eq!(path("schemas/partner.xsd"), catalog.resolve_uri("urn:partner:invoice"));
+    }
+
+    #[test]
+    fn schema_locations() {
+        let catalog = Catalog::from_file(CATALOG).expect("Failed to read catalog");
+        let xsd = include_str!("../tests/catalog/schemas/order.xsd");
+        let schema_package = DomParser::parse(xsd).expect("Failed to parse");
+        let schema_document = schema_package.as_document();
+        let schema = Schema::from_document(&schema_document).expect("Failed to parse schema");
+        let validate = |hints: &str| {
+            let xml = format!("<order xmlns=\"urn:example:order\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+                               xsi:schemaLocation=\"{}\"><other xmlns=\"urn:example:other\">1</other></order>", hints);
+            let package = DomParser::parse(&xml).expect("Failed to parse");
+            schema.validate_with_resolver(&package.as_document(), &catalog).map(|_| ())
+        };
+
+        assert_eq!(Ok(()), validate("urn:example:other http://example.com/schemas/other.xsd"));
+        // the namespace is looked up if the location is not mapped
+        assert_eq!(Ok(()), validate("urn:example:other http://unknown.org/other.xsd"));
+    }
+
+    #[test]
+    fn includes_and_imports() {
+        let catalog = Catalog::from_file(CATALOG).expect("Failed to read catalog");
+        // the include is read from its location although the namespace of the including document is mapped
+        assert_eq!(Some(local("schemas/invoice.xsd")), catalog.resolve_uri("urn:example:invoice"));
+        let schema_set = SchemaSet::load(&local("schemas/invoice.xsd"), &catalog).expect("Failed to load schemas");
+        assert_eq!(&[Some("urn:example:invoice".to_string()), Some("urn:example:other".to_string())],
+                   schema_set.target_namespaces());
+        let validate = |total: &str| {
+            let xml = format!("<invoice:invoice xmlns:invoice=\"urn:example:invoice\"><total>{}</total>\
+                               <other xmlns=\"urn:example:other\">1</other></invoice:invoice>", total);
+            let package = DomParser::parse(&xml).expect("Failed to parse");
+            schema_set.validate(&package.as_document()).map(|_| ())
+        };
+        assert_eq!(Ok(()), validate("12.50"));
+        assert!(validate("-1").is_err());
+
+        let missing = local("schemas/missing.xsd");
+        assert_eq!(Err(SchemaSetError { document: Some(0), error: SchemaError::UnresolvedLocation(missing.clone()) }),
+                   SchemaSet::load(&missing, &catalog).map(|_| ()));
+    }
+}
@@ -0,0 +1,260 @@
+use std::sync::Arc;
+
+use sxd_document::parser as DomParser;
+use sxd_document::dom::Document;
+
+use components::{CacheError, Components, Reader, Writer, source_hash};
+use parser::SchemaError;
+use parser::schema::Schema;
+use resolver::SchemaResolver;
+use validator::{Validation, ValidationError, Validator, validate_with_resolver};
+
+// the first bytes of every cache
+static CACHE_MAGIC: &[u8] = b"SXDXSD";
+// increased whenever the layout of the cache changes
+const CACHE_VERSION: u32 = 1;
+
+/// A checked schema that owns its resolved components instead of borrowing from a parsed
+/// document, it can be stored, cached and shared between threads
+#[derive(Clone, Debug)]
+pub struct CompiledSchema {
+    source: String,
+    target_namespace: Option<String>,
+    components: Arc<Components>,
+}
+
+// the components are built from the source, so schemas with the same source are equal
+impl PartialEq for CompiledSchema {
+    fn eq(&self, other: &CompiledSchema) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for CompiledSchema {}
+
+/// Writes the header of a cache, which identifies the format and the texts of the schema
+/// documents that the cache was written for
+pub(crate) fn write_header(writer: &mut Writer, sources: &[&str]) {
+    writer.write_bytes(CACHE_MAGIC);
+    writer.write(&CACHE_VERSION);
+    writer.write(&source_hash(sources));
+}
+
+/// Checks that a cache was written by write_header for the same texts of schema documents
+pub(crate) fn read_header(reader: &mut Reader, sources: &[&str]) -> Result<(), CacheError> {
+    if reader.read_bytes(CACHE_MAGIC.len()) != Ok(CACHE_MAGIC) {
+        return Err(CacheError::NotACache);
+    }
+    let version = reader.read()?;
+    if version != CACHE_VERSION {
+        return Err(CacheError::UnsupportedVersion(version));
+    }
+    if reader.read::<u64>()? != source_hash(sources) {
+        return Err(CacheError::Stale);
+    }
+    Ok(())
+}
+
+impl CompiledSchema {
+    /// Parses and checks the text of a schema document, errors carry the positions of the
+    /// offending components like the ones of Schema::from_source
+    pub fn new(source: &str) -> Result<CompiledSchema, SchemaError> {
+        let package = DomParser::parse(source).map_err(|_| SchemaError::NotWellFormed)?;
+        let document = package.as_document();
+        let schema = Schema::from_source(&document, source)?;
+        Ok(CompiledSchema {
+            source: source.to_string(),
+            target_namespace: schema.target_namespace.map(str::to_string),
+            components: schema.components,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn target_namespace(&self) -> Option<&str> {
+        self.target_namespace.as_deref()
+    }
+
+    pub fn components(&self) -> &Components {
+        &self.components
+    }
+
+    /// Serializes the resolved components to a versioned binary cache that from_cache loads
+    /// without parsing XML. Regular expressions are stored as their patterns and compiled again
+    /// on loading, content models are matched on the particles directly and need no automata
+    pub fn to_cache(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        write_header(&mut writer, &[&self.source]);
+        writer.write(&self.target_namespace);
+        writer.write(&*self.components);
+        writer.into_bytes()
+    }
+
+    /// Loads a schema from a cache written by to_cache. The cache stores a hash of the source it
+    /// was written for, a cache for a different source is stale and has to be compiled again
+    pub fn from_cache(cache: &[u8], source: &str) -> Result<CompiledSchema, CacheError> {
+        let mut reader = Reader::new(cache);
+        read_header(&mut reader, &[source])?;
+        let target_namespace = reader.read()?;
+        let components = reader.read()?;
+        if !reader.is_empty() {
+            return Err(CacheError::Corrupt);
+        }
+        Ok(CompiledSchema {
+            source: source.to_string(),
+            target_namespace,
+            components: Arc::new(components),
+        })
+    }
+
+    /// A validator for the components, e.g. to limit the number of violations of validate_all
+    pub fn validator(&self) -> Validator {
+        Validator::from_components(self.components.clone())
+    }
+
+    /// Validates a document and stops at the first violation
+    pub fn validate<'d>(&self, document: &Document<'d>) -> Result<Validation<'d>, ValidationError> {
+        self.validator().validate(document)
+    }
+
+    /// Validates a whole document and collects all violations
+    pub fn validate_all<'d>(&self, document: &Document<'d>) -> Validation<'d> {
+        self.validator().validate_all(document)
+    }
+
+    /// Validates a document and loads the schemas referenced by its schemaLocation hints, the
+    /// components are merged with the ones of the loaded schemas
+    pub fn validate_with_resolver<'d, R>(&self, document: &Document<'d>, resolver: &R)
+                                         -> Result<Validation<'d>, ValidationError>
+        where R: SchemaResolver + ?Sized {
+        validate_with_resolver(&self.components, self.target_namespace(), document, resolver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use std::sync::Arc;
+    use std::thread;
+
+    use sxd_document::parser as DomParser;
+
+    use datatypes::facets::FacetError;
+    use parser::SchemaError;
+    use parser::positions::Position;
+    use super::*;
+
+    fn is_send_and_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn shared_between_threads() {
+        is_send_and_sync::<CompiledSchema>();
+        let schema = Arc::new(CompiledSchema::new(include_str!("../tests/parser/mod/purchase.xsd"))
+            .expect("Failed to compile schema"));
+        let threads: Vec<_> = (0..4)
+            .map(|index| {
+                let schema = schema.clone();
+                thread::spawn(move || {
+                    let xml = include_str!("../tests/validator/mod/purchase.xml");
+                    let xml = match index % 2 {
+                        0 => xml.to_string(),
+                        _ => xml.replace("<zip>95819</zip>", "<zip>x</zip>"),
+                    };
+                    let package = DomParser::parse(&xml).expect("Failed to parse");
+                    schema.validate(&package.as_document()).is_ok()
+                })
+            })
+            .collect();
+        let results: Vec<bool> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+        assert_eq!(vec![true, false, true, false], results);
+    }
+
+    #[test]
+    fn cache() {
+        let source = include_str!("../tests/parser/mod/purchase.xsd");
+        let schema = CompiledSchema::new(source).expect("Failed to compile schema");
+        let cache = schema.to_cache();
+        let loaded = CompiledSchema::from_cache(&cache, source).expect("Failed to load cache");
+        assert_eq!(schema, loaded);
+
+        let xml = include_str!("../tests/validator/mod/purchase.xml");
+        let package = DomParser::parse(xml).expect("Failed to parse");
+        assert!(loaded.validate(&package.as_document()).is_ok());
+        let package = DomParser::parse(&xml.replace("<zip>95819</zip>", "<zip>x</zip>")).expect("Failed to parse");
+        assert!(loaded.validate(&package.as_document()).is_err());
+
+        let changed = source.replace("maxExclusive value=\"100\"", "maxExclusive value=\"10\"");
+        assert_eq!(Err(CacheError::Stale), CompiledSchema::from_cache(&cache, &changed));
+        assert_eq!(Err(CacheError::NotACache), CompiledSchema::from_cache(b"<xsd:schema/>", source));
+        let mut outdated = cache.clone();
+        outdated[CACHE_MAGIC.len()] += 1;
+        assert_eq!(Err(CacheError::UnsupportedVersion(CACHE_VERSION + 1)), CompiledSchema::from_cache(&outdated, source));
+        assert_eq!(Err(CacheError::Corrupt), CompiledSchema::from_cache(&cache[..cache.len() - 1], source));
+    }
+
+    #[test]
+    fn resolver() {
+        let schema = CompiledSchema::new(include_str!("../tests/validator/xsi/order.xsd")).expect("Failed to compile schema");
+        let resolver = |namespace: Option<&str>, location: &str| match (namespace, location) {
+            (Some("urn:parts"), "parts.xsd") => Some(include_str!("../tests/validator/xsi/parts.xsd").to_string()),
+            _ => None,
+        };
+        let validate = |code: &str, quantity: &str| {
+            let xml = format!("<order xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+                               xsi:schemaLocation=\"urn:parts parts.xsd\" xmlns:p=\"urn:parts\">\
+                               <p:part code=\"{}\"/><quantity>{}</quantity></order>", code, quantity);
+            let package = DomParser::parse(&xml).expect("Failed to parse");
+            let document = package.as_document();
+            (schema.validate(&document).is_ok(), schema.validate_with_resolver(&document, &resolver).map(|_| ()))
+        };
+
+        assert_eq!((false, Ok(())), validate("abc", "12"));
+        assert_eq!((false, Err(ValidationError::Facet(FacetError::MaxExclusive))), validate("abc", "120"));
+        assert!(validate("abcd", "12").1.is_err());
+    }
+
+    #[test]
+    fn max_errors() {
+        let schema = CompiledSchema::new(include_str!("../tests/parser/mod/purchase.xsd")).expect("Failed to compile schema");
+        let package = DomParser::parse(include_str!("../tests/validator/report/purchase.xml")).expect("Failed to parse");
+        let document = package.as_document();
+        assert_eq!(5, schema.validate_all(&document).violations().len());
+        assert_eq!(3, schema.validator().with_max_errors(3).validate_all(&document).violations().len());
+    }
+
+    #[test]
+    fn cached_patterns() {
+        let source = "<xsd:schema xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\
+                      <xsd:element name=\"sku\"><xsd:simpleType><xsd:restriction base=\"xsd:string\">\
+                      <xsd:pattern value=\"\\d{3}-[A-Z]{2}\"/></xsd:restriction></xsd:simpleType></xsd:element>\
+                      </xsd:schema>";
+        let schema = CompiledSchema::new(source).expect("Failed to compile schema");
+        let loaded = CompiledSchema::from_cache(&schema.to_cache(), source).expect("Failed to load cache");
+        let validate = |xml: &str| {
+            let package = DomParser::parse(xml).expect("Failed to parse");
+            loaded.validate(&package.as_document()).map(|_| ())
+        };
+        assert_eq!(Ok(()), validate("<sku>926-AA</sku>"));
+        assert_eq!(Err(ValidationError::Pattern), validate("<sku>926-A</sku>"));
+    }
+
+    #[test]
+    fn invalid_schemas() {
+        assert_eq!(Err(SchemaError::NotWellFormed), CompiledSchema::new("<xsd:schema"));
+        let error = CompiledSchema::new("<xsd:schema xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\n\
+                                         <xsd:element name=\"order\" type=\"OrderType\"/></xsd:schema>")
+            .unwrap_err();
+        assert_eq!(Some(Position { line: 2, column: 1 }), error.component().and_then(|component| component.position));
+        // elements that can not be parsed are reported at their own position
+        let error = CompiledSchema::new("<xsd:schema xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\n\
+                                         <xsd:element name=\"order\"><xsd:complexType><xsd:sequence>\n  \
+                                         <xsd:element name=\"item\" maxOccurs=\"many\"/>\
+                                         </xsd:sequence></xsd:complexType></xsd:element></xsd:schema>")
+            .unwrap_err();
+        assert_eq!(Some("s4s-att-invalid-value"), error.code());
+        assert_eq!(Some(Position { line: 3, column: 3 }), error.component().and_then(|component| component.position));
+    }
+}
@@ -0,0 +1,575 @@
+use sxd_document::dom::Element as DomElement;
+
+use datatypes::patterns;
+use parser::{QName, ValueConstraint as ParsedValueConstraint};
+use parser::schema::{FormChoice, Schema};
+use parser::elements::Element;
+use parser::attributes::{Attribute, AttributeGroup, AttributeUse as ParsedAttributeUse};
+use parser::groups::{Group, ModelGroup as ParsedModelGroup, Particle as ParsedParticle};
+use parser::wildcards::{NamespaceConstraint as ParsedNamespaceConstraint, ProcessContents};
+use parser::types::{
+    AnySimpleType,
+    AnyType,
+    ComplexType as ParsedComplexType,
+    ComplexTypeContent,
+    RestrictionRule,
+    SimpleType as ParsedSimpleType,
+    SimpleTypeContent,
+    TopLevelType,
+};
+
+use components::{
+    AttributeDeclaration,
+    AttributeGroupDefinition,
+    AttributeGroupId,
+    AttributeId,
+    AttributeUse,
+    ComplexType,
+    ComplexTypeId,
+    ComponentName,
+    Components,
+    Compositor,
+    ContentType,
+    ElementDeclaration,
+    ElementId,
+    Facet,
+    GroupId,
+    ModelGroup,
+    ModelGroupDefinition,
+    NamespaceConstraint,
+    NotationDeclaration,
+    Particle,
+    Pattern,
+    Reference,
+    SimpleDerivation,
+    SimpleType,
+    SimpleTypeDefinition,
+    SimpleTypeId,
+    Term,
+    TypeDefinition,
+    ValueConstraint,
+    Wildcard,
+};
+
+/// The names of the top level components of all schemas in the order they are numbered in
+#[derive(Default)]
+struct Names {
+    elements: Vec<(ComponentName, ElementId)>,
+    attributes: Vec<(ComponentName, AttributeId)>,
+    types: Vec<(ComponentName, TypeDefinition)>,
+    groups: Vec<(ComponentName, GroupId)>,
+    attribute_groups: Vec<(ComponentName, AttributeGroupId)>,
+}
+
+/// Finds the first component with the name, a redefinition is skipped so that its reference to
+/// its own name finds the component that it redefines
+fn find<T: Copy + PartialEq>(names: &[(ComponentName, T)], name: &QName, redefinition: Option<T>) -> Reference<T> {
+    names.iter()
+        .find(|&&(ref component, id)| component.matches(name) && Some(id) != redefinition)
+        .map(|&(_, id)| id)
+        .ok_or_else(|| ComponentName::new(name.namespace_uri, name.local_name))
+}
+
+/// A type or group of a redefine that is being built
+#[derive(Copy, Clone)]
+enum Redefinition {
+    Type(TypeDefinition),
+    Group(GroupId),
+    AttributeGroup(AttributeGroupId),
+}
+
+fn value_constraint(value_constraint: ParsedValueConstraint) -> ValueConstraint {
+    match value_constraint {
+        ParsedValueConstraint::Default(value) => ValueConstraint::Default(value.to_string()),
+        ParsedValueConstraint::Fixed(value) => ValueConstraint::Fixed(value.to_string()),
+    }
+}
+
+fn wildcard(namespace: &ParsedNamespaceConstraint, process_contents: ProcessContents) -> Wildcard {
+    let namespace = match *namespace {
+        ParsedNamespaceConstraint::Any => NamespaceConstraint::Any,
+        ParsedNamespaceConstraint::Not(excluded) => NamespaceConstraint::Not(excluded.map(str::to_string)),
+        ParsedNamespaceConstraint::List(ref namespaces) =>
+            NamespaceConstraint::List(namespaces.iter().map(|namespace| namespace.map(str::to_string)).collect()),
+    };
+    Wildcard { namespace, process_contents }
+}
+
+fn facets(rules: &[RestrictionRule]) -> Vec<Facet> {
+    rules.iter()
+        .map(|rule| match *rule {
+            RestrictionRule::MinExclusive(ref facet) => Facet::MinExclusive(facet.value.to_string()),
+            RestrictionRule::MinInclusive(ref facet) => Facet::MinInclusive(facet.value.to_string()),
+            RestrictionRule::MaxExclusive(ref facet) => Facet::MaxExclusive(facet.value.to_string()),
+            RestrictionRule::MaxInclusive(ref facet) => Facet::MaxInclusive(facet.value.to_string()),
+            RestrictionRule::TotalDigits(ref facet) => Facet::TotalDigits(facet.value),
+            RestrictionRule::FractionDigits(ref facet) => Facet::FractionDigits(facet.value),
+            RestrictionRule::Length(ref facet) => Facet::Length(facet.value),
+            RestrictionRule::MinLength(ref facet) => Facet::MinLength(facet.value),
+            RestrictionRule::MaxLength(ref facet) => Facet::MaxLength(facet.value),
+            RestrictionRule::Enumeration(ref facet) => Facet::Enumeration(facet.value.to_string()),
+            RestrictionRule::WhiteSpace(ref facet) => Facet::WhiteSpace(facet.value),
+            RestrictionRule::Pattern(ref facet) => Facet::Pattern(Pattern {
+                value: facet.value.to_string(),
+                regex: patterns::compile(facet.value),
+            }),
+        })
+        .collect()
+}
+
+/// Builds the components of a set of schemas. Top level components get their ids before any
+/// component is built so that references can be resolved regardless of the order of definitions,
+/// their slots are filled as the schemas are walked and local components are appended
+struct Builder<'s, 'a: 's> {
+    schemas: &'s [&'s Schema<'a>],
+    // the schema whose components are built
+    schema: &'s Schema<'a>,
+    names: Names,
+    elements: Vec<Option<ElementDeclaration>>,
+    attributes: Vec<Option<AttributeDeclaration>>,
+    simple_types: Vec<Option<SimpleType>>,
+    complex_types: Vec<Option<ComplexType>>,
+    groups: Vec<ModelGroupDefinition>,
+    attribute_groups: Vec<AttributeGroupDefinition>,
+    // elements without a type which may take the type of their substitution group head
+    untyped_elements: Vec<ElementId>,
+    // the redefinition whose components are built
+    redefinition: Option<Redefinition>,
+}
+
+impl<'s, 'a: 's> Builder<'s, 'a> {
+    fn new(schemas: &'s [&'s Schema<'a>], schema: &'s Schema<'a>) -> Builder<'s, 'a> {
+        let mut builder = Builder {
+            schemas,
+            schema,
+            names: Names::default(),
+            elements: Vec::new(),
+            attributes: Vec::new(),
+            simple_types: Vec::new(),
+            complex_types: Vec::new(),
+            groups: Vec::new(),
+            attribute_groups: Vec::new(),
+            untyped_elements: Vec::new(),
+            redefinition: None,
+        };
+        // redefinitions come first so that all references find them instead of the originals
+        for &schema in schemas {
+            for redefine in &schema.redefines {
+                builder.declare_types(schema, &redefine.types);
+                builder.declare_groups(schema, &redefine.groups, &redefine.attribute_groups);
+            }
+        }
+        for &schema in schemas {
+            let name = |local_name| ComponentName::new(schema.target_namespace, local_name);
+            for element in &schema.elements {
+                builder.names.elements.push((name(element.name), ElementId(builder.elements.len())));
+                builder.elements.push(None);
+            }
+            for attribute in &schema.attributes {
+                builder.names.attributes.push((name(attribute.name), AttributeId(builder.attributes.len())));
+                builder.attributes.push(None);
+            }
+            builder.declare_types(schema, &schema.types);
+            builder.declare_groups(schema, &schema.groups, &schema.attribute_groups);
+        }
+        builder
+    }
+
+    fn declare_types(&mut self, schema: &Schema, types: &[TopLevelType]) {
+        for top_level_type in types {
+            let (type_name, definition) = match *top_level_type {
+                TopLevelType::SimpleType(ref simple_type) => {
+                    let id = SimpleTypeId(self.simple_types.len());
+                    self.simple_types.push(None);
+                    (simple_type.name, TypeDefinition::Simple(SimpleTypeDefinition::Defined(id)))
+                }
+                TopLevelType::ComplexType(ref complex_type) => {
+                    let id = ComplexTypeId(self.complex_types.len());
+                    self.complex_types.push(None);
+                    (complex_type.name, TypeDefinition::Complex(id))
+                }
+            };
+            let name = ComponentName::new(schema.target_namespace, type_name.expect("Type defined without name"));
+            self.names.types.push((name, definition));
+        }
+    }
+
+    fn declare_groups(&mut self, schema: &Schema, groups: &[Group], attribute_groups: &[AttributeGroup]) {
+        for group in groups {
+            let id = GroupId(self.names.groups.len());
+            self.names.groups.push((ComponentName::new(schema.target_namespace, group.name), id));
+        }
+        for group in attribute_groups {
+            let id = AttributeGroupId(self.names.attribute_groups.len());
+            self.names.attribute_groups.push((ComponentName::new(schema.target_namespace, group.name), id));
+        }
+    }
+
+    fn name(&self, local_name: &str) -> ComponentName {
+        ComponentName::new(self.schema.target_namespace, local_name)
+    }
+
+    /// The name of a local declaration, only qualified ones are in the target namespace
+    fn local_name(&self, form: FormChoice, local_name: &str) -> ComponentName {
+        match form {
+            FormChoice::Qualified => self.name(local_name),
+            FormChoice::Unqualified => ComponentName::new(None, local_name),
+        }
+    }
+
+    fn position(&self, source: DomElement) -> Option<::parser::positions::Position> {
+        self.schema.position(source)
+    }
+
+    fn find_type(&self, name: &QName) -> Reference<TypeDefinition> {
+        let redefinition = match self.redefinition {
+            Some(Redefinition::Type(definition)) => Some(definition),
+            _ => None,
+        };
+        find(&self.names.types, name, redefinition)
+    }
+
+    fn find_group(&self, name: &QName) -> Reference<GroupId> {
+        let redefinition = match self.redefinition {
+            Some(Redefinition::Group(id)) => Some(id),
+            _ => None,
+        };
+        find(&self.names.groups, name, redefinition)
+    }
+
+    fn find_attribute_group(&self, name: &QName) -> Reference<AttributeGroupId> {
+        let redefinition = match self.redefinition {
+            Some(Redefinition::AttributeGroup(id)) => Some(id),
+            _ => None,
+        };
+        find(&self.names.attribute_groups, name, redefinition)
+    }
+
+    fn any_type(&mut self, any_type: &AnyType) -> Reference<TypeDefinition> {
+        match *any_type {
+            AnyType::ComplexType(ref complex_type) => Ok(TypeDefinition::Complex(self.complex_type(complex_type, None))),
+            AnyType::AnySimpleType(ref simple_type) => self.any_simple_type(simple_type).map(TypeDefinition::Simple),
+            AnyType::Any => Ok(TypeDefinition::Any),
+            AnyType::Reference(ref name) => self.find_type(name),
+        }
+    }
+
+    /// Resolves a simple type, a reference to a complex type can not be resolved
+    fn any_simple_type(&mut self, simple_type: &AnySimpleType) -> Reference<SimpleTypeDefinition> {
+        match *simple_type {
+            AnySimpleType::BuiltIn(builtin) => Ok(SimpleTypeDefinition::BuiltIn(builtin)),
+            AnySimpleType::SimpleType(ref simple_type) =>
+                Ok(SimpleTypeDefinition::Defined(self.simple_type(simple_type, None))),
+            AnySimpleType::Reference(ref name) => match self.find_type(name)? {
+                TypeDefinition::Simple(simple_type) => Ok(simple_type),
+                _ => Err(ComponentName::new(name.namespace_uri, name.local_name)),
+            },
+        }
+    }
+
+    /// Builds an element declaration into the slot of a top level one or as a new local one
+    fn element(&mut self, element: &Element, slot: Option<ElementId>) -> ElementId {
+        let id = slot.unwrap_or_else(|| {
+            self.elements.push(None);
+            ElementId(self.elements.len() - 1)
+        });
+        if element.element_type == AnyType::Any && !element.substitution_groups.is_empty() {
+            self.untyped_elements.push(id);
+        }
+        let declaration = ElementDeclaration {
+            name: match slot {
+                Some(_) => self.name(element.name),
+                None => self.local_name(element.form, element.name),
+            },
+            is_global: slot.is_some(),
+            type_definition: self.any_type(&element.element_type),
+            is_nillable: element.is_nillable,
+            value_constraint: element.value_constraint.map(value_constraint),
+            is_abstract: element.is_abstract,
+            block_modes: element.block_modes.clone(),
+            final_modes: element.final_modes.clone(),
+            substitution_groups: element.substitution_groups.iter()
+                .map(|head| find(&self.names.elements, head, None))
+                .collect(),
+            position: self.position(element.source),
+        };
+        self.elements[id.0] = Some(declaration);
+        id
+    }
+
+    fn attribute(&mut self, attribute: &Attribute, slot: Option<AttributeId>) -> AttributeId {
+        let id = slot.unwrap_or_else(|| {
+            self.attributes.push(None);
+            AttributeId(self.attributes.len() - 1)
+        });
+        let declaration = AttributeDeclaration {
+            name: match slot {
+                Some(_) => self.name(attribute.name),
+                None => self.local_name(attribute.form, attribute.name),
+            },
+            is_global: slot.is_some(),
+            type_definition: self.any_simple_type(&attribute.attribute_type),
+            value_constraint: attribute.value_constraint.map(value_constraint),
+            position: self.position(attribute.source),
+        };
+        self.attributes[id.0] = Some(declaration);
+        id
+    }
+
+    fn simple_type(&mut self, simple_type: &ParsedSimpleType, slot: Option<SimpleTypeId>) -> SimpleTypeId {
+        let id = slot.unwrap_or_else(|| {
+            self.simple_types.push(None);
+            SimpleTypeId(self.simple_types.len() - 1)
+        });
+        let derivation = match *simple_type.content {
+            SimpleTypeContent::Restriction(ref restriction) => SimpleDerivation::Restriction {
+                base: self.any_simple_type(&restriction.restriction_type),
+                facets: facets(&restriction.rules),
+            },
+            SimpleTypeContent::List(ref list) => SimpleDerivation::List(self.any_simple_type(&list.item_type)),
+            SimpleTypeContent::Union(ref union) => SimpleDerivation::Union(union.member_types.iter()
+                .map(|member_type| self.any_simple_type(member_type))
+                .collect()),
+        };
+        let definition = SimpleType {
+            name: simple_type.name.map(|name| self.name(name)),
+            derivation,
+            final_modes: simple_type.final_modes.clone(),
+            position: self.position(simple_type.source),
+        };
+        self.simple_types[id.0] = Some(definition);
+        id
+    }
+
+    fn complex_type(&mut self, complex_type: &ParsedComplexType, slot: Option<ComplexTypeId>) -> ComplexTypeId {
+        let id = slot.unwrap_or_else(|| {
+            self.complex_types.push(None);
+            ComplexTypeId(self.complex_types.len() - 1)
+        });
+        let (derivation, content) = match *complex_type.content {
+            ComplexTypeContent::SimpleContent(ref derivation) => (derivation, ContentType::Simple {
+                simple_type: derivation.simple_type.as_ref().map(|simple_type| self.simple_type(simple_type, None)),
+                facets: facets(&derivation.rules),
+            }),
+            ComplexTypeContent::ComplexContent(ref derivation) =>
+                (derivation, ContentType::Complex(derivation.particle.as_ref().map(|particle| self.particle(particle)))),
+        };
+        let (attribute_uses, attribute_groups) = self.attribute_uses(&derivation.attributes);
+        let definition = ComplexType {
+            name: complex_type.name.map(|name| self.name(name)),
+            base: self.any_type(&derivation.base),
+            method: derivation.method,
+            is_abstract: complex_type.is_abstract,
+            is_mixed: complex_type.is_mixed,
+            block_modes: complex_type.block_modes.clone(),
+            final_modes: complex_type.final_modes.clone(),
+            content,
+            attribute_uses,
+            attribute_groups,
+            attribute_wildcard: derivation.any_attribute.as_ref()
+                .map(|any| wildcard(&any.namespace, any.process_contents)),
+            position: self.position(complex_type.source),
+        };
+        self.complex_types[id.0] = Some(definition);
+        id
+    }
+
+    fn particle(&mut self, particle: &ParsedParticle) -> Particle {
+        let term = match *particle {
+            ParsedParticle::Element(ref element) => Term::Element(Ok(self.element(element, None))),
+            ParsedParticle::ElementReference(ref reference) =>
+                Term::Element(find(&self.names.elements, &reference.reference, None)),
+            ParsedParticle::Sequence(ref group) => Term::ModelGroup(self.model_group(Compositor::Sequence, group)),
+            ParsedParticle::Choice(ref group) => Term::ModelGroup(self.model_group(Compositor::Choice, group)),
+            ParsedParticle::All(ref group) => Term::ModelGroup(self.model_group(Compositor::All, group)),
+            ParsedParticle::Group(ref reference) => Term::Group(self.find_group(&reference.reference)),
+            ParsedParticle::Any(ref any) => Term::Wildcard(wildcard(&any.namespace, any.process_contents)),
+        };
+        Particle {
+            min_occurs: particle.min_occurs(),
+            max_occurs: particle.max_occurs(),
+            term,
+        }
+    }
+
+    fn model_group(&mut self, compositor: Compositor, group: &ParsedModelGroup) -> ModelGroup {
+        ModelGroup {
+            compositor,
+            particles: group.particles.iter().map(|particle| self.particle(particle)).collect(),
+        }
+    }
+
+    /// Splits the attribute uses of a complex type or attribute group into the attribute uses
+    /// and the attribute groups it refers to
+    fn attribute_uses(&mut self, attribute_uses: &[ParsedAttributeUse])
+                      -> (Vec<AttributeUse>, Vec<Reference<AttributeGroupId>>) {
+        let mut uses = Vec::new();
+        let mut groups = Vec::new();
+        for attribute_use in attribute_uses {
+            match *attribute_use {
+                ParsedAttributeUse::Attribute(ref attribute) => uses.push(AttributeUse {
+                    declaration: Ok(self.attribute(attribute, None)),
+                    usage: attribute.usage,
+                    value_constraint: attribute.value_constraint.map(value_constraint),
+                }),
+                ParsedAttributeUse::Reference(ref reference) => {
+                    // the referenced declaration may not be built yet, so its value constraint is
+                    // taken from the schema that declares it
+                    let declared = self.schemas.iter()
+                        .find_map(|schema| schema.find_attribute(&reference.reference))
+                        .and_then(|attribute| attribute.value_constraint);
+                    uses.push(AttributeUse {
+                        declaration: find(&self.names.attributes, &reference.reference, None),
+                        usage: reference.usage,
+                        value_constraint: reference.value_constraint.or(declared).map(value_constraint),
+                    });
+                }
+                ParsedAttributeUse::Group(ref reference) =>
+                    groups.push(self.find_attribute_group(&reference.reference)),
+            }
+        }
+        (uses, groups)
+    }
+
+    /// Builds top level types into the next simple and complex type slots
+    fn top_level_types(&mut self, types: &[TopLevelType], slots: &mut (usize, usize), are_redefinitions: bool) {
+        for top_level_type in types {
+            match *top_level_type {
+                TopLevelType::SimpleType(ref simple_type) => {
+                    let id = SimpleTypeId(slots.0);
+                    if are_redefinitions {
+                        let definition = TypeDefinition::Simple(SimpleTypeDefinition::Defined(id));
+                        self.redefinition = Some(Redefinition::Type(definition));
+                    }
+                    self.simple_type(simple_type, Some(id));
+                    slots.0 += 1;
+                }
+                TopLevelType::ComplexType(ref complex_type) => {
+                    let id = ComplexTypeId(slots.1);
+                    if are_redefinitions {
+                        self.redefinition = Some(Redefinition::Type(TypeDefinition::Complex(id)));
+                    }
+                    self.complex_type(complex_type, Some(id));
+                    slots.1 += 1;
+                }
+            }
+        }
+    }
+
+    fn groups(&mut self, groups: &[Group], attribute_groups: &[AttributeGroup], are_redefinitions: bool) {
+        for group in groups {
+            if are_redefinitions {
+                self.redefinition = Some(Redefinition::Group(GroupId(self.groups.len())));
+            }
+            let definition = ModelGroupDefinition {
+                name: self.name(group.name),
+                particle: self.particle(&group.particle),
+                position: self.position(group.source),
+            };
+            self.groups.push(definition);
+        }
+        for group in attribute_groups {
+            if are_redefinitions {
+                self.redefinition = Some(Redefinition::AttributeGroup(AttributeGroupId(self.attribute_groups.len())));
+            }
+            let (attribute_uses, attribute_group_references) = self.attribute_uses(&group.attributes);
+            let definition = AttributeGroupDefinition {
+                name: self.name(group.name),
+                attribute_uses,
+                attribute_groups: attribute_group_references,
+                attribute_wildcard: group.any_attribute.as_ref()
+                    .map(|any| wildcard(&any.namespace, any.process_contents)),
+                position: self.position(group.source),
+            };
+            self.attribute_groups.push(definition);
+        }
+    }
+
+    /// Members of a substitution group without a type take the type of their first head, the
+    /// chain of heads stops at the first head that repeats in circular substitution groups
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-element
+    fn inherit_types(&mut self) {
+        let mut inherited = Vec::with_capacity(self.untyped_elements.len());
+        for &id in &self.untyped_elements {
+            let mut current = id;
+            let mut visited = vec![id];
+            while self.untyped_elements.contains(&current) {
+                let head = self.elements[current.0].as_ref()
+                    .and_then(|element| element.substitution_groups.first())
+                    .and_then(|head| head.as_ref().ok());
+                match head {
+                    Some(&head) if !visited.contains(&head) => {
+                        visited.push(head);
+                        current = head;
+                    }
+                    _ => break,
+                }
+            }
+            let type_definition = self.elements[current.0].as_ref()
+                .map(|element| element.type_definition.clone())
+                .expect("Element is not built");
+            inherited.push((id, type_definition));
+        }
+        for (id, type_definition) in inherited {
+            if let Some(ref mut element) = self.elements[id.0] {
+                element.type_definition = type_definition;
+            }
+        }
+    }
+}
+
+impl Components {
+    /// Builds the components of one or more schemas, references from one schema to the
+    /// components of another are resolved. If a name is defined twice the first one is used,
+    /// the types and groups of a redefine replace the ones they redefine
+    pub fn new(schemas: &[&Schema]) -> Components {
+        let first = match schemas.first() {
+            Some(&schema) => schema,
+            None => return Components::default(),
+        };
+        let mut builder = Builder::new(schemas, first);
+        let mut notations = Vec::new();
+        let mut type_slots = (0, 0);
+        for &schema in schemas {
+            builder.schema = schema;
+            for redefine in &schema.redefines {
+                builder.top_level_types(&redefine.types, &mut type_slots, true);
+                builder.groups(&redefine.groups, &redefine.attribute_groups, true);
+            }
+        }
+        builder.redefinition = None;
+        let (mut element_slot, mut attribute_slot) = (0, 0);
+        for &schema in schemas {
+            builder.schema = schema;
+            for element in &schema.elements {
+                builder.element(element, Some(ElementId(element_slot)));
+                element_slot += 1;
+            }
+            for attribute in &schema.attributes {
+                builder.attribute(attribute, Some(AttributeId(attribute_slot)));
+                attribute_slot += 1;
+            }
+            builder.top_level_types(&schema.types, &mut type_slots, false);
+            builder.groups(&schema.groups, &schema.attribute_groups, false);
+            notations.extend(schema.notations.iter().map(|notation| NotationDeclaration {
+                name: ComponentName::new(schema.target_namespace, notation.name),
+                public: notation.public.map(str::to_string),
+                system: notation.system.map(str::to_string),
+            }));
+        }
+        builder.inherit_types();
+        let built = "Top level component is not built";
+        let mut components = Components {
+            elements: builder.elements.into_iter().map(|element| element.expect(built)).collect(),
+            attributes: builder.attributes.into_iter().map(|attribute| attribute.expect(built)).collect(),
+            simple_types: builder.simple_types.into_iter().map(|simple_type| simple_type.expect(built)).collect(),
+            complex_types: builder.complex_types.into_iter().map(|complex_type| complex_type.expect(built)).collect(),
+            groups: builder.groups,
+            attribute_groups: builder.attribute_groups,
+            notations,
+            substitution_members: Vec::new(),
+        };
+        components.collect_substitution_members();
+        components
+    }
+}
@@ -0,0 +1,929 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use datatypes::patterns;
+use parser::attributes::Usage;
+use parser::elements::{ElementBlock, ElementFinal};
+use parser::groups::MaxOccurs;
+use parser::positions::Position;
+use parser::types::{BuiltIn, ComplexBlock, ComplexFinal, DerivationMethod, SimpleFinal, WhiteSpaceValue};
+use parser::wildcards::ProcessContents;
+
+use components::*;
+
+/// Raised when a cached schema can not be loaded
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum CacheError {
+    // the data does not start with the header of a schema cache
+    NotACache,
+    // the cache was written by a version of the crate that uses a different format
+    UnsupportedVersion(u32),
+    // the cache was written for a source that differs from the given one
+    Stale,
+    // the data ends early or contains values that are out of range
+    Corrupt,
+}
+
+// the types in the order of their declaration, a built in type is stored as its index
+static BUILT_INS: [BuiltIn; 45] = [
+    BuiltIn::AnySimpleType, BuiltIn::String, BuiltIn::NormalizedString, BuiltIn::Token, BuiltIn::Language,
+    BuiltIn::Name, BuiltIn::NcName, BuiltIn::Id, BuiltIn::IdRef, BuiltIn::IdRefs, BuiltIn::Entity,
+    BuiltIn::Entities, BuiltIn::NmToken, BuiltIn::NmTokens, BuiltIn::Boolean, BuiltIn::Decimal, BuiltIn::Integer,
+    BuiltIn::NonPositiveInteger, BuiltIn::NegativeInteger, BuiltIn::NonNegativeInteger, BuiltIn::PositiveInteger,
+    BuiltIn::Long, BuiltIn::UnsignedLong, BuiltIn::Int, BuiltIn::UnsignedInt, BuiltIn::Short,
+    BuiltIn::UnsignedShort, BuiltIn::Byte, BuiltIn::UnsignedByte, BuiltIn::Float, BuiltIn::Double,
+    BuiltIn::Duration, BuiltIn::DateTime, BuiltIn::Time, BuiltIn::Date, BuiltIn::GYearMonth, BuiltIn::GYear,
+    BuiltIn::GMonthDay, BuiltIn::GDay, BuiltIn::GMonth, BuiltIn::HexBinary, BuiltIn::Base64Binary,
+    BuiltIn::AnyUri, BuiltIn::QName, BuiltIn::Notation,
+];
+
+/// Hashes the text of schema documents with 64 bit FNV-1a, which unlike the hasher of the
+/// standard library is guaranteed to give the same result on every platform and Rust version
+pub fn source_hash(sources: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for source in sources {
+        // the length separates the documents, so moving text between them changes the hash
+        for byte in (source.len() as u64).to_le_bytes().iter().chain(source.as_bytes()) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// Appends values to a byte buffer, integers are stored in little endian byte order
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Writer {
+        Writer { bytes: Vec::new() }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn write<T: Encode + ?Sized>(&mut self, value: &T) {
+        value.encode(self);
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn tag(&mut self, tag: u8) {
+        self.bytes.push(tag);
+    }
+}
+
+/// Reads the values written by a Writer in the same order
+pub struct Reader<'b> {
+    bytes: &'b [u8],
+}
+
+impl<'b> Reader<'b> {
+    pub fn new(bytes: &'b [u8]) -> Reader<'b> {
+        Reader { bytes }
+    }
+
+    pub fn read<T: Decode>(&mut self) -> Result<T, CacheError> {
+        T::decode(self)
+    }
+
+    pub fn read_bytes(&mut self, length: usize) -> Result<&'b [u8], CacheError> {
+        if self.bytes.len() < length {
+            return Err(CacheError::Corrupt);
+        }
+        let (bytes, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn tag(&mut self) -> Result<u8, CacheError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+}
+
+pub trait Encode {
+    fn encode(&self, writer: &mut Writer);
+}
+
+pub trait Decode: Sized {
+    fn decode(reader: &mut Reader) -> Result<Self, CacheError>;
+}
+
+impl Encode for u32 {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write_bytes(&self.to_le_bytes());
+    }
+}
+
+impl Decode for u32 {
+    fn decode(reader: &mut Reader) -> Result<u32, CacheError> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(reader.read_bytes(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+}
+
+impl Encode for u64 {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write_bytes(&self.to_le_bytes());
+    }
+}
+
+impl Decode for u64 {
+    fn decode(reader: &mut Reader) -> Result<u64, CacheError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(reader.read_bytes(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+// stored with 64 bits so that caches can be shared between 32 and 64 bit platforms
+impl Encode for usize {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&(*self as u64));
+    }
+}
+
+impl Decode for usize {
+    fn decode(reader: &mut Reader) -> Result<usize, CacheError> {
+        let value: u64 = reader.read()?;
+        if value > usize::MAX as u64 {
+            return Err(CacheError::Corrupt);
+        }
+        Ok(value as usize)
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, writer: &mut Writer) {
+        writer.tag(*self as u8);
+    }
+}
+
+impl Decode for bool {
+    fn decode(reader: &mut Reader) -> Result<bool, CacheError> {
+        match reader.tag()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(CacheError::Corrupt),
+        }
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.len());
+        writer.write_bytes(self.as_bytes());
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(self.as_str());
+    }
+}
+
+impl Decode for String {
+    fn decode(reader: &mut Reader) -> Result<String, CacheError> {
+        let length = reader.read()?;
+        let bytes = reader.read_bytes(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| CacheError::Corrupt)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, writer: &mut Writer) {
+        match *self {
+            None => writer.tag(0),
+            Some(ref value) => {
+                writer.tag(1);
+                writer.write(value);
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(reader: &mut Reader) -> Result<Option<T>, CacheError> {
+        match reader.tag()? {
+            0 => Ok(None),
+            1 => Ok(Some(reader.read()?)),
+            _ => Err(CacheError::Corrupt),
+        }
+    }
+}
+
+// unresolved references keep the name that could not be resolved
+impl<T: Encode> Encode for Reference<T> {
+    fn encode(&self, writer: &mut Writer) {
+        match *self {
+            Ok(ref value) => {
+                writer.tag(0);
+                writer.write(value);
+            }
+            Err(ref name) => {
+                writer.tag(1);
+                writer.write(name);
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Reference<T> {
+    fn decode(reader: &mut Reader) -> Result<Reference<T>, CacheError> {
+        match reader.tag()? {
+            0 => Ok(Ok(reader.read()?)),
+            1 => Ok(Err(reader.read()?)),
+            _ => Err(CacheError::Corrupt),
+        }
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.len());
+        for value in self {
+            writer.write(value);
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(self.as_slice());
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(reader: &mut Reader) -> Result<Vec<T>, CacheError> {
+        let length: usize = reader.read()?;
+        // every value takes at least one byte, so a corrupt length can not exhaust the memory
+        if length > reader.bytes.len() {
+            return Err(CacheError::Corrupt);
+        }
+        (0..length).map(|_| reader.read()).collect()
+    }
+}
+
+impl<T: Encode> Encode for HashSet<T> {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.len());
+        for value in self {
+            writer.write(value);
+        }
+    }
+}
+
+impl<T: Decode + Eq + Hash> Decode for HashSet<T> {
+    fn decode(reader: &mut Reader) -> Result<HashSet<T>, CacheError> {
+        Ok(reader.read::<Vec<T>>()?.into_iter().collect())
+    }
+}
+
+/// Implements Encode and Decode for enums without fields by storing the index of the variant
+macro_rules! unit_enum {
+    ($name:ident { $($variant:ident),* }) => {
+        impl Encode for $name {
+            fn encode(&self, writer: &mut Writer) {
+                let variants = [$($name::$variant),*];
+                let index = variants.iter().position(|variant| variant == self).expect("Variant is listed");
+                writer.tag(index as u8);
+            }
+        }
+
+        impl Decode for $name {
+            fn decode(reader: &mut Reader) -> Result<$name, CacheError> {
+                let variants = [$($name::$variant),*];
+                variants.get(reader.tag()? as usize).cloned().ok_or(CacheError::Corrupt)
+            }
+        }
+    }
+}
+
+unit_enum!(Usage { Optional, Required, Prohibited });
+unit_enum!(ElementBlock { Extension, Restriction, Substitution });
+unit_enum!(ElementFinal { Extension, Restriction });
+unit_enum!(ComplexBlock { Extension, Restriction });
+unit_enum!(ComplexFinal { Extension, Restriction });
+unit_enum!(SimpleFinal { Extension, Restriction, List, Union });
+unit_enum!(DerivationMethod { Extension, Restriction });
+unit_enum!(WhiteSpaceValue { Collapse, Preserve, Replace });
+unit_enum!(ProcessContents { Strict, Lax, Skip });
+unit_enum!(Compositor { Sequence, Choice, All });
+
+impl Encode for BuiltIn {
+    fn encode(&self, writer: &mut Writer) {
+        writer.tag(*self as u8);
+    }
+}
+
+impl Decode for BuiltIn {
+    fn decode(reader: &mut Reader) -> Result<BuiltIn, CacheError> {
+        BUILT_INS.get(reader.tag()? as usize).cloned().ok_or(CacheError::Corrupt)
+    }
+}
+
+impl Encode for MaxOccurs {
+    fn encode(&self, writer: &mut Writer) {
+        let bounded = match *self {
+            MaxOccurs::Bounded(max) => Some(max),
+            MaxOccurs::Unbounded => None,
+        };
+        writer.write(&bounded);
+    }
+}
+
+impl Decode for MaxOccurs {
+    fn decode(reader: &mut Reader) -> Result<MaxOccurs, CacheError> {
+        Ok(reader.read::<Option<usize>>()?.map_or(MaxOccurs::Unbounded, MaxOccurs::Bounded))
+    }
+}
+
+impl Encode for Position {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.line);
+        writer.write(&self.column);
+    }
+}
+
+impl Decode for Position {
+    fn decode(reader: &mut Reader) -> Result<Position, CacheError> {
+        Ok(Position { line: reader.read()?, column: reader.read()? })
+    }
+}
+
+impl Encode for ComponentName {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.namespace_uri);
+        writer.write(&self.local_name);
+    }
+}
+
+impl Decode for ComponentName {
+    fn decode(reader: &mut Reader) -> Result<ComponentName, CacheError> {
+        Ok(ComponentName { namespace_uri: reader.read()?, local_name: reader.read()? })
+    }
+}
+
+/// Implements Encode and Decode for ids, which have to be checked against the number of
+/// components of their kind once the whole graph is read
+macro_rules! id {
+    ($name:ident) => {
+        impl Encode for $name {
+            fn encode(&self, writer: &mut Writer) {
+                writer.write(&self.0);
+            }
+        }
+
+        impl Decode for $name {
+            fn decode(reader: &mut Reader) -> Result<$name, CacheError> {
+                Ok($name(reader.read()?))
+            }
+        }
+    }
+}
+
+id!(ElementId);
+id!(AttributeId);
+id!(SimpleTypeId);
+id!(ComplexTypeId);
+id!(GroupId);
+id!(AttributeGroupId);
+
+impl Encode for SimpleTypeDefinition {
+    fn encode(&self, writer: &mut Writer) {
+        match *self {
+            SimpleTypeDefinition::BuiltIn(builtin) => {
+                writer.tag(0);
+                writer.write(&builtin);
+            }
+            SimpleTypeDefinition::Defined(id) => {
+                writer.tag(1);
+                writer.write(&id);
+            }
+        }
+    }
+}
+
+impl Decode for SimpleTypeDefinition {
+    fn decode(reader: &mut Reader) -> Result<SimpleTypeDefinition, CacheError> {
+        match reader.tag()? {
+            0 => Ok(SimpleTypeDefinition::BuiltIn(reader.read()?)),
+            1 => Ok(SimpleTypeDefinition::Defined(reader.read()?)),
+            _ => Err(CacheError::Corrupt),
+        }
+    }
+}
+
+impl Encode for TypeDefinition {
+    fn encode(&self, writer: &mut Writer) {
+        match *self {
+            TypeDefinition::Simple(simple_type) => {
+                writer.tag(0);
+                writer.write(&simple_type);
+            }
+            TypeDefinition::Complex(id) => {
+                writer.tag(1);
+                writer.write(&id);
+            }
+            TypeDefinition::Any => writer.tag(2),
+        }
+    }
+}
+
+impl Decode for TypeDefinition {
+    fn decode(reader: &mut Reader) -> Result<TypeDefinition, CacheError> {
+        match reader.tag()? {
+            0 => Ok(TypeDefinition::Simple(reader.read()?)),
+            1 => Ok(TypeDefinition::Complex(reader.read()?)),
+            2 => Ok(TypeDefinition::Any),
+            _ => Err(CacheError::Corrupt),
+        }
+    }
+}
+
+impl Encode for ValueConstraint {
+    fn encode(&self, writer: &mut Writer) {
+        writer.tag(match *self {
+            ValueConstraint::Default(_) => 0,
+            ValueConstraint::Fixed(_) => 1,
+        });
+        writer.write(self.value());
+    }
+}
+
+impl Decode for ValueConstraint {
+    fn decode(reader: &mut Reader) -> Result<ValueConstraint, CacheError> {
+        match reader.tag()? {
+            0 => Ok(ValueConstraint::Default(reader.read()?)),
+            1 => Ok(ValueConstraint::Fixed(reader.read()?)),
+            _ => Err(CacheError::Corrupt),
+        }
+    }
+}
+
+// compiled regular expressions can not be stored, the pattern is compiled again when it is read
+impl Encode for Pattern {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.value);
+    }
+}
+
+impl Decode for Pattern {
+    fn decode(reader: &mut Reader) -> Result<Pattern, CacheError> {
+        let value: String = reader.read()?;
+        let regex = patterns::compile(&value);
+        Ok(Pattern { value, regex })
+    }
+}
+
+impl Encode for Facet {
+    fn encode(&self, writer: &mut Writer) {
+        match *self {
+            Facet::MinExclusive(ref limit) => { writer.tag(0); writer.write(limit); }
+            Facet::MinInclusive(ref limit) => { writer.tag(1); writer.write(limit); }
+            Facet::MaxExclusive(ref limit) => { writer.tag(2); writer.write(limit); }
+            Facet::MaxInclusive(ref limit) => { writer.tag(3); writer.write(limit); }
+            Facet::TotalDigits(digits) => { writer.tag(4); writer.write(&digits); }
+            Facet::FractionDigits(digits) => { writer.tag(5); writer.write(&digits); }
+            Facet::Length(length) => { writer.tag(6); writer.write(&length); }
+            Facet::MinLength(length) => { writer.tag(7); writer.write(&length); }
+            Facet::MaxLength(length) => { writer.tag(8); writer.write(&length); }
+            Facet::Enumeration(ref value) => { writer.tag(9); writer.write(value); }
+            Facet::WhiteSpace(white_space) => { writer.tag(10); writer.write(&white_space); }
+            Facet::Pattern(ref pattern) => { writer.tag(11); writer.write(pattern); }
+        }
+    }
+}
+
+impl Decode for Facet {
+    fn decode(reader: &mut Reader) -> Result<Facet, CacheError> {
+        Ok(match reader.tag()? {
+            0 => Facet::MinExclusive(reader.read()?),
+            1 => Facet::MinInclusive(reader.read()?),
+            2 => Facet::MaxExclusive(reader.read()?),
+            3 => Facet::MaxInclusive(reader.read()?),
+            4 => Facet::TotalDigits(reader.read()?),
+            5 => Facet::FractionDigits(reader.read()?),
+            6 => Facet::Length(reader.read()?),
+            7 => Facet::MinLength(reader.read()?),
+            8 => Facet::MaxLength(reader.read()?),
+            9 => Facet::Enumeration(reader.read()?),
+            10 => Facet::WhiteSpace(reader.read()?),
+            11 => Facet::Pattern(reader.read()?),
+            _ => return Err(CacheError::Corrupt),
+        })
+    }
+}
+
+impl Encode for NamespaceConstraint {
+    fn encode(&self, writer: &mut Writer) {
+        match *self {
+            NamespaceConstraint::Any => writer.tag(0),
+            NamespaceConstraint::Not(ref namespace) => {
+                writer.tag(1);
+                writer.write(namespace);
+            }
+            NamespaceConstraint::List(ref namespaces) => {
+                writer.tag(2);
+                writer.write(namespaces);
+            }
+        }
+    }
+}
+
+impl Decode for NamespaceConstraint {
+    fn decode(reader: &mut Reader) -> Result<NamespaceConstraint, CacheError> {
+        match reader.tag()? {
+            0 => Ok(NamespaceConstraint::Any),
+            1 => Ok(NamespaceConstraint::Not(reader.read()?)),
+            2 => Ok(NamespaceConstraint::List(reader.read()?)),
+            _ => Err(CacheError::Corrupt),
+        }
+    }
+}
+
+impl Encode for Wildcard {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.namespace);
+        writer.write(&self.process_contents);
+    }
+}
+
+impl Decode for Wildcard {
+    fn decode(reader: &mut Reader) -> Result<Wildcard, CacheError> {
+        Ok(Wildcard { namespace: reader.read()?, process_contents: reader.read()? })
+    }
+}
+
+impl Encode for Particle {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.min_occurs);
+        writer.write(&self.max_occurs);
+        match self.term {
+            Term::Element(ref element) => {
+                writer.tag(0);
+                writer.write(element);
+            }
+            Term::ModelGroup(ref group) => {
+                writer.tag(1);
+                writer.write(&group.compositor);
+                writer.write(&group.particles);
+            }
+            Term::Group(ref group) => {
+                writer.tag(2);
+                writer.write(group);
+            }
+            Term::Wildcard(ref wildcard) => {
+                writer.tag(3);
+                writer.write(wildcard);
+            }
+        }
+    }
+}
+
+impl Decode for Particle {
+    fn decode(reader: &mut Reader) -> Result<Particle, CacheError> {
+        let min_occurs = reader.read()?;
+        let max_occurs = reader.read()?;
+        let term = match reader.tag()? {
+            0 => Term::Element(reader.read()?),
+            1 => Term::ModelGroup(ModelGroup { compositor: reader.read()?, particles: reader.read()? }),
+            2 => Term::Group(reader.read()?),
+            3 => Term::Wildcard(reader.read()?),
+            _ => return Err(CacheError::Corrupt),
+        };
+        Ok(Particle { min_occurs, max_occurs, term })
+    }
+}
+
+impl Encode for AttributeUse {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.declaration);
+        writer.write(&self.usage);
+        writer.write(&self.value_constraint);
+    }
+}
+
+impl Decode for AttributeUse {
+    fn decode(reader: &mut Reader) -> Result<AttributeUse, CacheError> {
+        Ok(AttributeUse { declaration: reader.read()?, usage: reader.read()?, value_constraint: reader.read()? })
+    }
+}
+
+impl Encode for ElementDeclaration {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.name);
+        writer.write(&self.is_global);
+        writer.write(&self.type_definition);
+        writer.write(&self.is_nillable);
+        writer.write(&self.value_constraint);
+        writer.write(&self.is_abstract);
+        writer.write(&self.block_modes);
+        writer.write(&self.final_modes);
+        writer.write(&self.substitution_groups);
+        writer.write(&self.position);
+    }
+}
+
+impl Decode for ElementDeclaration {
+    fn decode(reader: &mut Reader) -> Result<ElementDeclaration, CacheError> {
+        Ok(ElementDeclaration {
+            name: reader.read()?,
+            is_global: reader.read()?,
+            type_definition: reader.read()?,
+            is_nillable: reader.read()?,
+            value_constraint: reader.read()?,
+            is_abstract: reader.read()?,
+            block_modes: reader.read()?,
+            final_modes: reader.read()?,
+            substitution_groups: reader.read()?,
+            position: reader.read()?,
+        })
+    }
+}
+
+impl Encode for AttributeDeclaration {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.name);
+        writer.write(&self.is_global);
+        writer.write(&self.type_definition);
+        writer.write(&self.value_constraint);
+        writer.write(&self.position);
+    }
+}
+
+impl Decode for AttributeDeclaration {
+    fn decode(reader: &mut Reader) -> Result<AttributeDeclaration, CacheError> {
+        Ok(AttributeDeclaration {
+            name: reader.read()?,
+            is_global: reader.read()?,
+            type_definition: reader.read()?,
+            value_constraint: reader.read()?,
+            position: reader.read()?,
+        })
+    }
+}
+
+impl Encode for SimpleType {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.name);
+        match self.derivation {
+            SimpleDerivation::Restriction { ref base, ref facets } => {
+                writer.tag(0);
+                writer.write(base);
+                writer.write(facets);
+            }
+            SimpleDerivation::List(ref item_type) => {
+                writer.tag(1);
+                writer.write(item_type);
+            }
+            SimpleDerivation::Union(ref member_types) => {
+                writer.tag(2);
+                writer.write(member_types);
+            }
+        }
+        writer.write(&self.final_modes);
+        writer.write(&self.position);
+    }
+}
+
+impl Decode for SimpleType {
+    fn decode(reader: &mut Reader) -> Result<SimpleType, CacheError> {
+        let name = reader.read()?;
+        let derivation = match reader.tag()? {
+            0 => SimpleDerivation::Restriction { base: reader.read()?, facets: reader.read()? },
+            1 => SimpleDerivation::List(reader.read()?),
+            2 => SimpleDerivation::Union(reader.read()?),
+            _ => return Err(CacheError::Corrupt),
+        };
+        Ok(SimpleType { name, derivation, final_modes: reader.read()?, position: reader.read()? })
+    }
+}
+
+impl Encode for ComplexType {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.name);
+        writer.write(&self.base);
+        writer.write(&self.method);
+        writer.write(&self.is_abstract);
+        writer.write(&self.is_mixed);
+        writer.write(&self.block_modes);
+        writer.write(&self.final_modes);
+        match self.content {
+            ContentType::Simple { ref simple_type, ref facets } => {
+                writer.tag(0);
+                writer.write(simple_type);
+                writer.write(facets);
+            }
+            ContentType::Complex(ref particle) => {
+                writer.tag(1);
+                writer.write(particle);
+            }
+        }
+        writer.write(&self.attribute_uses);
+        writer.write(&self.attribute_groups);
+        writer.write(&self.attribute_wildcard);
+        writer.write(&self.position);
+    }
+}
+
+impl Decode for ComplexType {
+    fn decode(reader: &mut Reader) -> Result<ComplexType, CacheError> {
+        let name = reader.read()?;
+        let base = reader.read()?;
+        let method = reader.read()?;
+        let is_abstract = reader.read()?;
+        let is_mixed = reader.read()?;
+        let block_modes = reader.read()?;
+        let final_modes = reader.read()?;
+        let content = match reader.tag()? {
+            0 => ContentType::Simple { simple_type: reader.read()?, facets: reader.read()? },
+            1 => ContentType::Complex(reader.read()?),
+            _ => return Err(CacheError::Corrupt),
+        };
+        Ok(ComplexType {
+            name,
+            base,
+            method,
+            is_abstract,
+            is_mixed,
+            block_modes,
+            final_modes,
+            content,
+            attribute_uses: reader.read()?,
+            attribute_groups: reader.read()?,
+            attribute_wildcard: reader.read()?,
+            position: reader.read()?,
+        })
+    }
+}
+
+impl Encode for ModelGroupDefinition {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.name);
+        writer.write(&self.particle);
+        writer.write(&self.position);
+    }
+}
+
+impl Decode for ModelGroupDefinition {
+    fn decode(reader: &mut Reader) -> Result<ModelGroupDefinition, CacheError> {
+        Ok(ModelGroupDefinition { name: reader.read()?, particle: reader.read()?, position: reader.read()? })
+    }
+}
+
+impl Encode for AttributeGroupDefinition {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.name);
+        writer.write(&self.attribute_uses);
+        writer.write(&self.attribute_groups);
+        writer.write(&self.attribute_wildcard);
+        writer.write(&self.position);
+    }
+}
+
+impl Decode for AttributeGroupDefinition {
+    fn decode(reader: &mut Reader) -> Result<AttributeGroupDefinition, CacheError> {
+        Ok(AttributeGroupDefinition {
+            name: reader.read()?,
+            attribute_uses: reader.read()?,
+            attribute_groups: reader.read()?,
+            attribute_wildcard: reader.read()?,
+            position: reader.read()?,
+        })
+    }
+}
+
+impl Encode for NotationDeclaration {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.name);
+        writer.write(&self.public);
+        writer.write(&self.system);
+    }
+}
+
+impl Decode for NotationDeclaration {
+    fn decode(reader: &mut Reader) -> Result<NotationDeclaration, CacheError> {
+        Ok(NotationDeclaration { name: reader.read()?, public: reader.read()?, system: reader.read()? })
+    }
+}
+
+impl Encode for Components {
+    fn encode(&self, writer: &mut Writer) {
+        writer.write(&self.elements);
+        writer.write(&self.attributes);
+        writer.write(&self.simple_types);
+        writer.write(&self.complex_types);
+        writer.write(&self.groups);
+        writer.write(&self.attribute_groups);
+        writer.write(&self.notations);
+    }
+}
+
+impl Decode for Components {
+    fn decode(reader: &mut Reader) -> Result<Components, CacheError> {
+        let mut components = Components {
+            elements: reader.read()?,
+            attributes: reader.read()?,
+            simple_types: reader.read()?,
+            complex_types: reader.read()?,
+            groups: reader.read()?,
+            attribute_groups: reader.read()?,
+            notations: reader.read()?,
+            substitution_members: Vec::new(),
+        };
+        if components.has_valid_ids() {
+            components.collect_substitution_members();
+            Ok(components)
+        } else {
+            Err(CacheError::Corrupt)
+        }
+    }
+}
+
+impl Components {
+    /// Checks that every id refers to an existing component, so that a corrupt cache can not
+    /// make the validator index out of bounds
+    fn has_valid_ids(&self) -> bool {
+        let element = |reference: &Reference<ElementId>| reference.as_ref().map_or(true, |id| id.0 < self.elements.len());
+        let group = |reference: &Reference<GroupId>| reference.as_ref().map_or(true, |id| id.0 < self.groups.len());
+        let attribute_group = |reference: &Reference<AttributeGroupId>| reference.as_ref()
+            .map_or(true, |id| id.0 < self.attribute_groups.len());
+        let simple_type = |simple_type: &SimpleTypeDefinition| match *simple_type {
+            SimpleTypeDefinition::Defined(id) => id.0 < self.simple_types.len(),
+            SimpleTypeDefinition::BuiltIn(_) => true,
+        };
+        let simple_reference = |reference: &Reference<SimpleTypeDefinition>| reference.as_ref().map_or(true, simple_type);
+        let type_reference = |reference: &Reference<TypeDefinition>| match *reference {
+            Ok(TypeDefinition::Simple(ref definition)) => simple_type(definition),
+            Ok(TypeDefinition::Complex(id)) => id.0 < self.complex_types.len(),
+            Ok(TypeDefinition::Any) | Err(_) => true,
+        };
+        let attribute_uses = |attribute_uses: &[AttributeUse]| attribute_uses.iter()
+            .all(|attribute_use| attribute_use.declaration.as_ref().map_or(true, |id| id.0 < self.attributes.len()));
+        fn particle_ids<F, G>(particle: &Particle, element: &F, group: &G) -> bool
+            where F: Fn(&Reference<ElementId>) -> bool, G: Fn(&Reference<GroupId>) -> bool {
+            match particle.term {
+                Term::Element(ref reference) => element(reference),
+                Term::Group(ref reference) => group(reference),
+                Term::ModelGroup(ref model_group) =>
+                    model_group.particles.iter().all(|particle| particle_ids(particle, element, group)),
+                Term::Wildcard(_) => true,
+            }
+        }
+
+        self.elements.iter()
+            .all(|declaration| type_reference(&declaration.type_definition) &&
+                declaration.substitution_groups.iter().all(&element)) &&
+            self.attributes.iter().all(|declaration| simple_reference(&declaration.type_definition)) &&
+            self.simple_types.iter().all(|simple_type| match simple_type.derivation {
+                SimpleDerivation::Restriction { ref base, .. } | SimpleDerivation::List(ref base) =>
+                    simple_reference(base),
+                SimpleDerivation::Union(ref member_types) => member_types.iter().all(&simple_reference),
+            }) &&
+            self.complex_types.iter().all(|complex_type| type_reference(&complex_type.base) &&
+                attribute_uses(&complex_type.attribute_uses) &&
+                complex_type.attribute_groups.iter().all(&attribute_group) &&
+                match complex_type.content {
+                    ContentType::Simple { simple_type, .. } => simple_type.is_none_or(|id| id.0 < self.simple_types.len()),
+                    ContentType::Complex(ref particle) =>
+                        particle.as_ref().is_none_or(|particle| particle_ids(particle, &element, &group)),
+                }) &&
+            self.groups.iter().all(|definition| particle_ids(&definition.particle, &element, &group)) &&
+            self.attribute_groups.iter().all(|definition| attribute_uses(&definition.attribute_uses) &&
+                definition.attribute_groups.iter().all(&attribute_group))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_indices() {
+        for (index, &builtin) in BUILT_INS.iter().enumerate() {
+            assert_eq!(index, builtin as usize);
+        }
+    }
+
+    #[test]
+    fn corrupt_data() {
+        let mut writer = Writer::new();
+        writer.write(&vec![Some("a".to_string()), None]);
+        let bytes = writer.into_bytes();
+        assert_eq!(Ok(vec![Some("a".to_string()), None]), Reader::new(&bytes).read::<Vec<Option<String>>>());
+        assert_eq!(Err(CacheError::Corrupt), Reader::new(&bytes[..bytes.len() - 1]).read::<Vec<Option<String>>>());
+        assert_eq!(Err(CacheError::Corrupt), Reader::new(&[2]).read::<Option<String>>());
+        assert_eq!(Err(CacheError::Corrupt), Reader::new(&[0xff; 8]).read::<Vec<bool>>());
+    }
+}
@@ -0,0 +1,243 @@
+use parser::QName;
+
+use components::*;
+
+/// The indices that the components of one graph get in the merged graph, by kind
+struct Indices {
+    elements: Vec<usize>,
+    attributes: Vec<usize>,
+    simple_types: Vec<usize>,
+    complex_types: Vec<usize>,
+    groups: Vec<usize>,
+    attribute_groups: Vec<usize>,
+}
+
+impl Indices {
+    fn element(&self, id: ElementId) -> ElementId {
+        ElementId(self.elements[id.0])
+    }
+
+    fn attribute(&self, id: AttributeId) -> AttributeId {
+        AttributeId(self.attributes[id.0])
+    }
+
+    fn simple_type(&self, id: SimpleTypeId) -> SimpleTypeId {
+        SimpleTypeId(self.simple_types[id.0])
+    }
+
+    fn group(&self, id: GroupId) -> GroupId {
+        GroupId(self.groups[id.0])
+    }
+
+    fn attribute_group(&self, id: AttributeGroupId) -> AttributeGroupId {
+        AttributeGroupId(self.attribute_groups[id.0])
+    }
+
+    fn simple_type_definition(&self, definition: SimpleTypeDefinition) -> SimpleTypeDefinition {
+        match definition {
+            SimpleTypeDefinition::Defined(id) => SimpleTypeDefinition::Defined(self.simple_type(id)),
+            SimpleTypeDefinition::BuiltIn(_) => definition,
+        }
+    }
+
+    fn type_definition(&self, definition: TypeDefinition) -> TypeDefinition {
+        match definition {
+            TypeDefinition::Simple(simple_type) => TypeDefinition::Simple(self.simple_type_definition(simple_type)),
+            TypeDefinition::Complex(id) => TypeDefinition::Complex(ComplexTypeId(self.complex_types[id.0])),
+            TypeDefinition::Any => definition,
+        }
+    }
+}
+
+/// Numbers the components of one kind of both graphs, the top level ones come before the local
+/// ones and the ones of the first graph before the ones of the second
+fn number<T, F>(first: &[T], second: &[T], is_global: F) -> (Vec<usize>, Vec<usize>)
+    where F: Fn(&T) -> bool {
+    let mut indices = (vec![0; first.len()], vec![0; second.len()]);
+    let mut next = 0;
+    for &global in &[true, false] {
+        for (components, indices) in [(first, &mut indices.0), (second, &mut indices.1)] {
+            for (index, component) in components.iter().enumerate() {
+                if is_global(component) == global {
+                    indices[index] = next;
+                    next += 1;
+                }
+            }
+        }
+    }
+    indices
+}
+
+/// Moves the components of one kind of both graphs to their indices in the merged graph
+fn place<T, F>(first: Vec<T>, second: Vec<T>, indices: &[Indices; 2], kind: F) -> Vec<T>
+    where F: Fn(&Indices) -> &Vec<usize> {
+    let mut placed: Vec<(usize, T)> = kind(&indices[0]).iter().cloned().zip(first)
+        .chain(kind(&indices[1]).iter().cloned().zip(second))
+        .collect();
+    placed.sort_by_key(|&(index, _)| index);
+    placed.into_iter().map(|(_, component)| component).collect()
+}
+
+/// Two graphs that are merged and the indices of their components in the merged graph
+struct Merge<'c> {
+    graphs: [&'c Components; 2],
+    indices: [Indices; 2],
+}
+
+impl<'c> Merge<'c> {
+    /// Finds a top level component in the first graph and then in the second one
+    fn find<T, F>(&self, name: &ComponentName, find: F) -> Reference<T>
+        where F: Fn(&Components, &Indices, &QName) -> Option<T> {
+        let name_ref = QName { namespace_uri: name.namespace_uri.as_deref(), local_name: &name.local_name };
+        self.graphs.iter().zip(&self.indices)
+            .filter_map(|(graph, indices)| find(graph, indices, &name_ref))
+            .next()
+            .ok_or_else(|| name.clone())
+    }
+
+    /// A copy of one of the graphs with its references moved to the merged graph
+    fn remap(&self, graph: usize) -> Components {
+        let remap = Remap { merge: self, indices: &self.indices[graph] };
+        let mut components = self.graphs[graph].clone();
+        components.elements.iter_mut().for_each(|element| remap.element(element));
+        components.attributes.iter_mut().for_each(|attribute| remap.simple_reference(&mut attribute.type_definition));
+        components.simple_types.iter_mut().for_each(|simple_type| remap.simple_type(simple_type));
+        components.complex_types.iter_mut().for_each(|complex_type| remap.complex_type(complex_type));
+        components.groups.iter_mut().for_each(|group| remap.particle(&mut group.particle));
+        components.attribute_groups.iter_mut().for_each(|group| {
+            remap.attribute_uses(&mut group.attribute_uses);
+            group.attribute_groups.iter_mut().for_each(|reference| remap.attribute_group(reference));
+        });
+        components
+    }
+}
+
+/// Moves the references of the components of one graph to the merged graph, references that the
+/// graph could not resolve are resolved against both graphs
+struct Remap<'m, 'c: 'm> {
+    merge: &'m Merge<'c>,
+    indices: &'m Indices,
+}
+
+impl<'m, 'c: 'm> Remap<'m, 'c> {
+    fn resolve<T, F, G>(&self, reference: &mut Reference<T>, remap: F, find: G)
+        where T: Copy, F: Fn(&Indices, T) -> T, G: Fn(&Components, &QName) -> Option<T> {
+        let resolved = match *reference {
+            Ok(id) => Ok(remap(self.indices, id)),
+            Err(ref name) => self.merge.find(name, |graph, indices, name| find(graph, name).map(|id| remap(indices, id))),
+        };
+        *reference = resolved;
+    }
+
+    fn element_reference(&self, reference: &mut Reference<ElementId>) {
+        self.resolve(reference, Indices::element, Components::find_element)
+    }
+
+    fn attribute_group(&self, reference: &mut Reference<AttributeGroupId>) {
+        self.resolve(reference, Indices::attribute_group, Components::find_attribute_group)
+    }
+
+    fn type_reference(&self, reference: &mut Reference<TypeDefinition>) {
+        self.resolve(reference, Indices::type_definition, Components::find_type)
+    }
+
+    /// A reference to a complex type stays unresolved like it does when the graph is built
+    fn simple_reference(&self, reference: &mut Reference<SimpleTypeDefinition>) {
+        self.resolve(reference, Indices::simple_type_definition, |graph, name| match graph.find_type(name) {
+            Some(TypeDefinition::Simple(simple_type)) => Some(simple_type),
+            _ => None,
+        })
+    }
+
+    fn element(&self, element: &mut ElementDeclaration) {
+        self.type_reference(&mut element.type_definition);
+        element.substitution_groups.iter_mut().for_each(|head| self.element_reference(head));
+    }
+
+    fn simple_type(&self, simple_type: &mut SimpleType) {
+        match simple_type.derivation {
+            SimpleDerivation::Restriction { ref mut base, .. } | SimpleDerivation::List(ref mut base) =>
+                self.simple_reference(base),
+            SimpleDerivation::Union(ref mut member_types) =>
+                member_types.iter_mut().for_each(|member_type| self.simple_reference(member_type)),
+        }
+    }
+
+    fn complex_type(&self, complex_type: &mut ComplexType) {
+        self.type_reference(&mut complex_type.base);
+        self.attribute_uses(&mut complex_type.attribute_uses);
+        complex_type.attribute_groups.iter_mut().for_each(|reference| self.attribute_group(reference));
+        match complex_type.content {
+            ContentType::Simple { ref mut simple_type, .. } =>
+                *simple_type = simple_type.map(|id| self.indices.simple_type(id)),
+            ContentType::Complex(ref mut particle) =>
+                particle.iter_mut().for_each(|particle| self.particle(particle)),
+        }
+    }
+
+    fn attribute_uses(&self, attribute_uses: &mut [AttributeUse]) {
+        for attribute_use in attribute_uses {
+            self.resolve(&mut attribute_use.declaration, Indices::attribute, Components::find_attribute);
+        }
+    }
+
+    fn particle(&self, particle: &mut Particle) {
+        match particle.term {
+            Term::Element(ref mut reference) => self.element_reference(reference),
+            Term::Group(ref mut reference) => self.resolve(reference, Indices::group, Components::find_group),
+            Term::ModelGroup(ref mut model_group) =>
+                model_group.particles.iter_mut().for_each(|particle| self.particle(particle)),
+            Term::Wildcard(_) => {}
+        }
+    }
+}
+
+impl Components {
+    /// Combines this graph with another one, e.g. with the components of the schemas that the
+    /// schemaLocation hints of a document name. References that one graph could not resolve are
+    /// resolved against the other, a name that both graphs define refers to the component of this one
+    pub fn merge(&self, other: &Components) -> Components {
+        let elements = number(&self.elements, &other.elements, |element| element.is_global);
+        let attributes = number(&self.attributes, &other.attributes, |attribute| attribute.is_global);
+        let simple_types = number(&self.simple_types, &other.simple_types, |simple_type| simple_type.name.is_some());
+        let complex_types = number(&self.complex_types, &other.complex_types, |complex_type| complex_type.name.is_some());
+        let groups = number(&self.groups, &other.groups, |_| true);
+        let attribute_groups = number(&self.attribute_groups, &other.attribute_groups, |_| true);
+        let merge = Merge {
+            graphs: [self, other],
+            indices: [
+                Indices {
+                    elements: elements.0,
+                    attributes: attributes.0,
+                    simple_types: simple_types.0,
+                    complex_types: complex_types.0,
+                    groups: groups.0,
+                    attribute_groups: attribute_groups.0,
+                },
+                Indices {
+                    elements: elements.1,
+                    attributes: attributes.1,
+                    simple_types: simple_types.1,
+                    complex_types: complex_types.1,
+                    groups: groups.1,
+                    attribute_groups: attribute_groups.1,
+                },
+            ],
+        };
+        let (first, second) = (merge.remap(0), merge.remap(1));
+        let indices = &merge.indices;
+        let mut components = Components {
+            elements: place(first.elements, second.elements, indices, |indices| &indices.elements),
+            attributes: place(first.attributes, second.attributes, indices, |indices| &indices.attributes),
+            simple_types: place(first.simple_types, second.simple_types, indices, |indices| &indices.simple_types),
+            complex_types: place(first.complex_types, second.complex_types, indices, |indices| &indices.complex_types),
+            groups: place(first.groups, second.groups, indices, |indices| &indices.groups),
+            attribute_groups: place(first.attribute_groups, second.attribute_groups, indices,
+                                    |indices| &indices.attribute_groups),
+            notations: first.notations.into_iter().chain(second.notations).collect(),
+            substitution_members: Vec::new(),
+        };
+        components.collect_substitution_members();
+        components
+    }
+}
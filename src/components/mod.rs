@@ -0,0 +1,523 @@
+mod builder;
+mod cache;
+mod merge;
+
+use std::collections::HashSet;
+
+use regex::Regex;
+
+use datatypes::patterns::PatternError;
+use parser::QName;
+use parser::attributes::Usage;
+use parser::elements::{ElementBlock, ElementFinal};
+use parser::groups::MaxOccurs;
+use parser::positions::Position;
+use parser::types::{BuiltIn, ComplexBlock, ComplexFinal, DerivationMethod, SimpleFinal, WhiteSpaceValue};
+use parser::wildcards::ProcessContents;
+
+pub use self::cache::CacheError;
+pub(crate) use self::cache::{Reader, Writer, source_hash};
+
+/// The expanded name of a declaration or of a named type definition
+#[derive(Eq, PartialEq, Hash, Clone, Debug)]
+pub struct ComponentName {
+    pub namespace_uri: Option<String>,
+    pub local_name: String,
+}
+
+impl ComponentName {
+    pub fn new(namespace_uri: Option<&str>, local_name: &str) -> ComponentName {
+        ComponentName {
+            namespace_uri: namespace_uri.map(str::to_string),
+            local_name: local_name.to_string(),
+        }
+    }
+
+    /// Checks if a name of a document or a reference of a schema names the component
+    pub fn matches(&self, name: &QName) -> bool {
+        self.namespace_uri.as_deref() == name.namespace_uri && self.local_name == name.local_name
+    }
+}
+
+/// Identifies an element declaration of a component graph
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct ElementId(usize);
+
+/// Identifies an attribute declaration of a component graph
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct AttributeId(usize);
+
+/// Identifies a simple type definition of a component graph
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct SimpleTypeId(usize);
+
+/// Identifies a complex type definition of a component graph
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct ComplexTypeId(usize);
+
+/// Identifies a model group definition of a component graph
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct GroupId(usize);
+
+/// Identifies an attribute group definition of a component graph
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct AttributeGroupId(usize);
+
+/// A resolved reference to a top level component or the name of a component that none of the
+/// schemas defines. Unresolved references are only an error once validation reaches them
+pub type Reference<T> = Result<T, ComponentName>;
+
+/// A simple type definition with its reference resolved
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub enum SimpleTypeDefinition {
+    BuiltIn(BuiltIn),
+    Defined(SimpleTypeId),
+}
+
+/// A type definition with its reference resolved
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub enum TypeDefinition {
+    Simple(SimpleTypeDefinition),
+    Complex(ComplexTypeId),
+    // xsd:anyType
+    Any,
+}
+
+/// The default or fixed value of a declaration or attribute use
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#a-value_constraint
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum ValueConstraint {
+    Default(String),
+    Fixed(String),
+}
+
+impl ValueConstraint {
+    pub fn value(&self) -> &str {
+        match *self {
+            ValueConstraint::Default(ref value) | ValueConstraint::Fixed(ref value) => value,
+        }
+    }
+}
+
+/// A pattern facet with its regular expression compiled when the graph is built
+/// see https://www.w3.org/TR/xmlschema11-2/#rf-pattern
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub value: String,
+    pub regex: Result<Regex, PatternError>,
+}
+
+/// A constraining facet of a single restriction step
+/// see https://www.w3.org/TR/xmlschema11-2/#rf-facets
+#[derive(Clone, Debug)]
+pub enum Facet {
+    MinExclusive(String),
+    MinInclusive(String),
+    MaxExclusive(String),
+    MaxInclusive(String),
+    TotalDigits(usize),
+    FractionDigits(usize),
+    Length(usize),
+    MinLength(usize),
+    MaxLength(usize),
+    Enumeration(String),
+    WhiteSpace(WhiteSpaceValue),
+    Pattern(Pattern),
+}
+
+/// The namespaces that a wildcard allows
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum NamespaceConstraint {
+    Any,
+    // any namespace except the given one and no namespace, i.e. ##other
+    Not(Option<String>),
+    // None stands for no namespace, i.e. ##local
+    List(Vec<Option<String>>),
+}
+
+impl NamespaceConstraint {
+    pub fn allows(&self, namespace: Option<&str>) -> bool {
+        match *self {
+            NamespaceConstraint::Any => true,
+            NamespaceConstraint::Not(ref excluded) => namespace.is_some() && namespace != excluded.as_deref(),
+            NamespaceConstraint::List(ref namespaces) =>
+                namespaces.iter().any(|allowed| allowed.as_deref() == namespace),
+        }
+    }
+}
+
+/// An any or anyAttribute wildcard
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#Wildcards
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Wildcard {
+    pub namespace: NamespaceConstraint,
+    pub process_contents: ProcessContents,
+}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Compositor {
+    Sequence,
+    Choice,
+    All,
+}
+
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#Model_Groups
+#[derive(Clone, Debug)]
+pub struct ModelGroup {
+    pub compositor: Compositor,
+    pub particles: Vec<Particle>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Term {
+    // a local declaration or a reference to a top level one
+    Element(Reference<ElementId>),
+    ModelGroup(ModelGroup),
+    // a reference to a named model group
+    Group(Reference<GroupId>),
+    Wildcard(Wildcard),
+}
+
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cParticles
+#[derive(Clone, Debug)]
+pub struct Particle {
+    pub min_occurs: usize,
+    pub max_occurs: MaxOccurs,
+    pub term: Term,
+}
+
+/// An attribute declaration as it is used by a complex type or an attribute group
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#AU_details
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct AttributeUse {
+    pub declaration: Reference<AttributeId>,
+    pub usage: Usage,
+    // the value constraint of a reference replaces the one of the referenced declaration
+    pub value_constraint: Option<ValueConstraint>,
+}
+
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#Element_Declaration_details
+#[derive(Clone, Debug)]
+pub struct ElementDeclaration {
+    pub name: ComponentName,
+    // false for declarations nested inside of a complex type or group
+    pub is_global: bool,
+    // members of a substitution group which do not declare a type take the type of their first head
+    pub type_definition: Reference<TypeDefinition>,
+    pub is_nillable: bool,
+    pub value_constraint: Option<ValueConstraint>,
+    pub is_abstract: bool,
+    pub block_modes: HashSet<ElementBlock>,
+    pub final_modes: HashSet<ElementFinal>,
+    pub substitution_groups: Vec<Reference<ElementId>>,
+    // only known for schemas created by Schema::from_source
+    pub position: Option<Position>,
+}
+
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#Attribute_Declaration_details
+#[derive(Clone, Debug)]
+pub struct AttributeDeclaration {
+    pub name: ComponentName,
+    // false for declarations nested inside of a complex type or attribute group
+    pub is_global: bool,
+    pub type_definition: Reference<SimpleTypeDefinition>,
+    pub value_constraint: Option<ValueConstraint>,
+    pub position: Option<Position>,
+}
+
+/// How a simple type is derived from its base, lists and unions are restrictions of anySimpleType
+#[derive(Clone, Debug)]
+pub enum SimpleDerivation {
+    Restriction {
+        base: Reference<SimpleTypeDefinition>,
+        facets: Vec<Facet>,
+    },
+    List(Reference<SimpleTypeDefinition>),
+    Union(Vec<Reference<SimpleTypeDefinition>>),
+}
+
+/// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#dc-defn
+#[derive(Clone, Debug)]
+pub struct SimpleType {
+    // None for anonymous types
+    pub name: Option<ComponentName>,
+    pub derivation: SimpleDerivation,
+    pub final_modes: HashSet<SimpleFinal>,
+    pub position: Option<Position>,
+}
+
+/// The content a complex type declares itself, inherited content is not included
+#[derive(Clone, Debug)]
+pub enum ContentType {
+    // simpleContent, restrictions may restrict the base by a nested simple type and facets
+    Simple {
+        simple_type: Option<SimpleTypeId>,
+        facets: Vec<Facet>,
+    },
+    // complexContent or content defined directly inside of the complex type, empty without a particle
+    Complex(Option<Particle>),
+}
+
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#Complex_Type_Definition_details
+#[derive(Clone, Debug)]
+pub struct ComplexType {
+    // None for anonymous types
+    pub name: Option<ComponentName>,
+    pub base: Reference<TypeDefinition>,
+    pub method: DerivationMethod,
+    pub is_abstract: bool,
+    pub is_mixed: bool,
+    pub block_modes: HashSet<ComplexBlock>,
+    pub final_modes: HashSet<ComplexFinal>,
+    pub content: ContentType,
+    pub attribute_uses: Vec<AttributeUse>,
+    pub attribute_groups: Vec<Reference<AttributeGroupId>>,
+    pub attribute_wildcard: Option<Wildcard>,
+    pub position: Option<Position>,
+}
+
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#Model_Group_Definition_details
+#[derive(Clone, Debug)]
+pub struct ModelGroupDefinition {
+    pub name: ComponentName,
+    // one of sequence, choice or all
+    pub particle: Particle,
+    pub position: Option<Position>,
+}
+
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#Attribute_Group_Definition_details
+#[derive(Clone, Debug)]
+pub struct AttributeGroupDefinition {
+    pub name: ComponentName,
+    pub attribute_uses: Vec<AttributeUse>,
+    pub attribute_groups: Vec<Reference<AttributeGroupId>>,
+    pub attribute_wildcard: Option<Wildcard>,
+    pub position: Option<Position>,
+}
+
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#Notation_Declaration_details
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct NotationDeclaration {
+    pub name: ComponentName,
+    pub public: Option<String>,
+    pub system: Option<String>,
+}
+
+/// The components of one or more schema documents with the references between them resolved.
+/// Unlike a Schema it does not borrow from the documents it was built from, so it can be kept
+/// and shared between threads. Top level components come before the local ones of each kind
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#concepts-data-model
+#[derive(Default, Clone, Debug)]
+pub struct Components {
+    elements: Vec<ElementDeclaration>,
+    attributes: Vec<AttributeDeclaration>,
+    simple_types: Vec<SimpleType>,
+    complex_types: Vec<ComplexType>,
+    groups: Vec<ModelGroupDefinition>,
+    attribute_groups: Vec<AttributeGroupDefinition>,
+    notations: Vec<NotationDeclaration>,
+    // The transitive members of the substitution group of each element, indexed by element
+    substitution_members: Vec<Vec<ElementId>>,
+}
+
+impl Components {
+    pub fn element(&self, id: ElementId) -> &ElementDeclaration {
+        &self.elements[id.0]
+    }
+
+    pub fn attribute(&self, id: AttributeId) -> &AttributeDeclaration {
+        &self.attributes[id.0]
+    }
+
+    pub fn simple_type(&self, id: SimpleTypeId) -> &SimpleType {
+        &self.simple_types[id.0]
+    }
+
+    pub fn complex_type(&self, id: ComplexTypeId) -> &ComplexType {
+        &self.complex_types[id.0]
+    }
+
+    pub fn group(&self, id: GroupId) -> &ModelGroupDefinition {
+        &self.groups[id.0]
+    }
+
+    pub fn attribute_group(&self, id: AttributeGroupId) -> &AttributeGroupDefinition {
+        &self.attribute_groups[id.0]
+    }
+
+    /// All element declarations including the local ones
+    pub fn elements(&self) -> impl Iterator<Item = (ElementId, &ElementDeclaration)> {
+        self.elements.iter().enumerate().map(|(index, element)| (ElementId(index), element))
+    }
+
+    /// All attribute declarations including the local ones
+    pub fn attributes(&self) -> impl Iterator<Item = (AttributeId, &AttributeDeclaration)> {
+        self.attributes.iter().enumerate().map(|(index, attribute)| (AttributeId(index), attribute))
+    }
+
+    /// All simple type definitions including the anonymous ones
+    pub fn simple_types(&self) -> impl Iterator<Item = (SimpleTypeId, &SimpleType)> {
+        self.simple_types.iter().enumerate().map(|(index, simple_type)| (SimpleTypeId(index), simple_type))
+    }
+
+    /// All complex type definitions including the anonymous ones
+    pub fn complex_types(&self) -> impl Iterator<Item = (ComplexTypeId, &ComplexType)> {
+        self.complex_types.iter().enumerate().map(|(index, complex_type)| (ComplexTypeId(index), complex_type))
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = (GroupId, &ModelGroupDefinition)> {
+        self.groups.iter().enumerate().map(|(index, group)| (GroupId(index), group))
+    }
+
+    pub fn attribute_groups(&self) -> impl Iterator<Item = (AttributeGroupId, &AttributeGroupDefinition)> {
+        self.attribute_groups.iter().enumerate().map(|(index, group)| (AttributeGroupId(index), group))
+    }
+
+    pub fn notations(&self) -> &[NotationDeclaration] {
+        &self.notations
+    }
+
+    /// The top level element declarations
+    pub fn global_elements(&self) -> impl Iterator<Item = (ElementId, &ElementDeclaration)> {
+        self.elements().filter(|&(_, element)| element.is_global)
+    }
+
+    /// The members of the substitution group of a head element and their members, without the
+    /// head itself and without regard to blocking
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cos-equiv-class
+    pub fn substitution_members(&self, head: ElementId) -> &[ElementId] {
+        self.substitution_members.get(head.0).map_or(&[], Vec::as_slice)
+    }
+
+    /// Collects the members of every substitution group once, a circular substitution group
+    /// ends the walk instead of looping
+    fn collect_substitution_members(&mut self) {
+        let mut direct = vec![Vec::new(); self.elements.len()];
+        for (id, element) in self.global_elements() {
+            for head in element.substitution_groups.iter().filter_map(|head| head.as_ref().ok()) {
+                direct[head.0].push(id);
+            }
+        }
+        self.substitution_members = (0..self.elements.len()).map(|head| {
+            let mut members: Vec<ElementId> = Vec::new();
+            let mut pending = vec![head];
+            while let Some(current) = pending.pop() {
+                for &member in &direct[current] {
+                    if member.0 != head && !members.contains(&member) {
+                        members.push(member);
+                        pending.push(member.0);
+                    }
+                }
+            }
+            members
+        }).collect();
+    }
+
+    /// Finds a top level element declaration, the first one wins if a name is declared twice
+    pub fn find_element(&self, name: &QName) -> Option<ElementId> {
+        self.global_elements()
+            .find(|&(_, element)| element.name.matches(name))
+            .map(|(id, _)| id)
+    }
+
+    pub fn find_attribute(&self, name: &QName) -> Option<AttributeId> {
+        self.attributes()
+            .find(|&(_, attribute)| attribute.is_global && attribute.name.matches(name))
+            .map(|(id, _)| id)
+    }
+
+    /// Finds a named simple or complex type definition, built in types are not part of the graph
+    pub fn find_type(&self, name: &QName) -> Option<TypeDefinition> {
+        let is_named = |type_name: &Option<ComponentName>| type_name.as_ref()
+            .is_some_and(|type_name| type_name.matches(name));
+        self.simple_types()
+            .find(|&(_, simple_type)| is_named(&simple_type.name))
+            .map(|(id, _)| TypeDefinition::Simple(SimpleTypeDefinition::Defined(id)))
+            .or_else(|| self.complex_types()
+                .find(|&(_, complex_type)| is_named(&complex_type.name))
+                .map(|(id, _)| TypeDefinition::Complex(id)))
+    }
+
+    pub fn find_group(&self, name: &QName) -> Option<GroupId> {
+        self.groups()
+            .find(|&(_, group)| group.name.matches(name))
+            .map(|(id, _)| id)
+    }
+
+    pub fn find_attribute_group(&self, name: &QName) -> Option<AttributeGroupId> {
+        self.attribute_groups()
+            .find(|&(_, group)| group.name.matches(name))
+            .map(|(id, _)| id)
+    }
+
+    pub fn find_notation(&self, name: &QName) -> Option<&NotationDeclaration> {
+        self.notations.iter().find(|notation| notation.name.matches(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use parser::schema::Schema;
+    use super::*;
+
+    #[test]
+    fn resolved_components() {
+        let components = {
+            let package = DomParser::parse(include_str!("../../tests/parser/mod/purchase.xsd")).expect("Failed to parse");
+            let document = package.as_document();
+            let schema = Schema::from_document(&document).expect("Failed to parse schema");
+            schema.components.clone()
+        };
+        let order = components.find_element(&QName { namespace_uri: None, local_name: "purchaseOrder" })
+            .expect("Element not found");
+        let order_type = components.find_type(&QName { namespace_uri: None, local_name: "PurchaseOrderType" });
+        assert_eq!(Ok(order_type.expect("Type not found")), components.element(order).type_definition);
+        assert_eq!(2, components.global_elements().count());
+
+        let items = match components.find_type(&QName { namespace_uri: None, local_name: "Items" }) {
+            Some(TypeDefinition::Complex(id)) => components.complex_type(id),
+            _ => panic!("Type not found"),
+        };
+        let item = match items.content {
+            ContentType::Complex(Some(Particle { term: Term::ModelGroup(ref group), .. })) =>
+                match group.particles[0].term {
+                    Term::Element(Ok(item)) => components.element(item),
+                    _ => panic!("Unexpected particle"),
+                },
+            _ => panic!("Unexpected content"),
+        };
+        assert_eq!(ComponentName::new(None, "item"), item.name);
+        assert!(!item.is_global);
+        let item_type = match item.type_definition {
+            Ok(TypeDefinition::Complex(id)) => components.complex_type(id),
+            _ => panic!("Anonymous type expected"),
+        };
+        assert_eq!(None, item_type.name);
+        let part_number = item_type.attribute_uses[0].declaration.clone().expect("Unresolved attribute");
+        assert_eq!(Usage::Required, item_type.attribute_uses[0].usage);
+        assert_eq!("partNum", components.attribute(part_number).name.local_name);
+    }
+
+    #[test]
+    fn substitution_members() {
+        let package = DomParser::parse(include_str!("../../tests/validator/substitution/substitution.xsd"))
+            .expect("Failed to parse");
+        let document = package.as_document();
+        let schema = Schema::from_document(&document).expect("Failed to parse schema");
+        let components = &schema.components;
+        let find = |name| components.find_element(&QName { namespace_uri: None, local_name: name }).expect("Element not found");
+        let names = |head| components.substitution_members(find(head)).iter()
+            .map(|&id| components.element(id).name.local_name.as_str())
+            .collect::<Vec<_>>();
+        let mut party = names("Party");
+        party.sort();
+        assert_eq!(vec!["Agent", "Buyer", "Seller"], party);
+        assert_eq!(vec!["Seller"], names("Contact"));
+        assert_eq!(vec!["Variant"], names("Fixed"));
+        assert!(names("Agent").is_empty());
+    }
+}
@@ -0,0 +1,747 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use datatypes::ValueError;
+
+/// The built in types that share the date/time value space
+/// see https://www.w3.org/TR/xmlschema11-2/#theSevenPropertyModel
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
+pub enum DateTimeKind {
+    DateTime,
+    Date,
+    Time,
+    GYearMonth,
+    GYear,
+    GMonthDay,
+    GDay,
+    GMonth,
+}
+
+/// Seconds of a date/time value, the fraction is kept as its decimal digits without trailing
+/// zeros so no precision is lost and comparing the digits compares the values
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Hash)]
+struct Seconds {
+    whole: u8,
+    fraction: String,
+}
+
+/// A value of one of the date/time types, properties which the type does not have are None
+/// see https://www.w3.org/TR/xmlschema11-2/#dateTime
+#[derive(Debug, Clone)]
+pub struct DateTime {
+    kind: DateTimeKind,
+    year: Option<i64>,
+    month: Option<u8>,
+    day: Option<u8>,
+    hour: Option<u8>,
+    minute: Option<u8>,
+    second: Option<Seconds>,
+    // offset from UTC in minutes
+    timezone: Option<i16>,
+}
+
+/// A duration consisting of a number of months and a number of seconds with the same sign
+/// see https://www.w3.org/TR/xmlschema11-2/#duration
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
+pub struct Duration {
+    negative: bool,
+    months: u64,
+    seconds: u128,
+    fraction: String,
+}
+
+/// A point on the time line in seconds, the fraction has the same meaning as in Seconds
+type TimelinePoint = (i128, String);
+
+static MAX_TIMEZONE_MINUTES: i16 = 14 * 60;
+
+struct Cursor<'s> {
+    input: &'s [u8],
+    position: usize,
+}
+
+impl<'s> Cursor<'s> {
+    fn new(input: &'s str) -> Cursor<'s> {
+        Cursor {
+            input: input.as_bytes(),
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).cloned()
+    }
+
+    fn eat(&mut self, expected: u8) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), ValueError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(ValueError::InvalidDateTime)
+        }
+    }
+
+    /// Consumes all following digits and returns them
+    fn digits(&mut self) -> &'s str {
+        let start = self.position;
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.position += 1;
+        }
+        // only ascii digits were consumed so this is always valid utf-8
+        ::std::str::from_utf8(&self.input[start..self.position]).unwrap_or("")
+    }
+
+    fn fixed_digits(&mut self, count: usize) -> Result<u8, ValueError> {
+        let digits = self.digits();
+        if digits.len() != count {
+            return Err(ValueError::InvalidDateTime);
+        }
+        digits.parse().map_err(|_| ValueError::InvalidDateTime)
+    }
+
+    fn is_done(&self) -> bool {
+        self.position == self.input.len()
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year.rem_euclid(400) == 0 || (year.rem_euclid(4) == 0 && year.rem_euclid(100) != 0)
+}
+
+/// Without a year February is allowed to have 29 days, e.g. for gMonthDay
+fn days_in_month(year: Option<i64>, month: u8) -> u8 {
+    match month {
+        2 => match year {
+            Some(year) if !is_leap_year(year) => 28,
+            _ => 29,
+        },
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn trim_fraction(digits: &str) -> String {
+    digits.trim_end_matches('0').to_string()
+}
+
+/// Calculates 1 - 0.fraction
+fn complement_fraction(fraction: &str) -> String {
+    let length = fraction.len();
+    let complement: String = fraction.bytes()
+        .enumerate()
+        .map(|(index, digit)| {
+            let digit = digit - b'0';
+            let complement = if index == length - 1 { 10 - digit } else { 9 - digit };
+            (b'0' + complement) as char
+        })
+        .collect();
+    trim_fraction(&complement)
+}
+
+/// Years have at least four digits and no leading zeros if they are longer
+fn parse_year(cursor: &mut Cursor) -> Result<i64, ValueError> {
+    let negative = cursor.eat(b'-');
+    let digits = cursor.digits();
+    if digits.len() < 4 || (digits.len() > 4 && digits.starts_with('0')) {
+        return Err(ValueError::InvalidDateTime);
+    }
+    let year: i64 = digits.parse().map_err(|_| ValueError::InvalidDateTime)?;
+    Ok(if negative { -year } else { year })
+}
+
+fn parse_timezone(cursor: &mut Cursor) -> Result<Option<i16>, ValueError> {
+    if cursor.eat(b'Z') {
+        return Ok(Some(0));
+    }
+    let sign = if cursor.eat(b'+') {
+        1
+    } else if cursor.eat(b'-') {
+        -1
+    } else {
+        return Ok(None);
+    };
+    let hours = cursor.fixed_digits(2)? as i16;
+    cursor.expect(b':')?;
+    let minutes = cursor.fixed_digits(2)? as i16;
+    let offset = hours * 60 + minutes;
+    if minutes > 59 || offset > MAX_TIMEZONE_MINUTES {
+        return Err(ValueError::InvalidDateTime);
+    }
+    Ok(Some(sign * offset))
+}
+
+fn parse_seconds(cursor: &mut Cursor) -> Result<Seconds, ValueError> {
+    let whole = cursor.fixed_digits(2)?;
+    let fraction = if cursor.eat(b'.') {
+        let digits = cursor.digits();
+        if digits.is_empty() {
+            return Err(ValueError::InvalidDateTime);
+        }
+        trim_fraction(digits)
+    } else {
+        String::new()
+    };
+    Ok(Seconds {
+        whole,
+        fraction,
+    })
+}
+
+impl DateTime {
+    /// Parses the lexical representation of the given type, surrounding whitespace is not
+    /// allowed and needs to be collapsed beforehand
+    pub fn parse(kind: DateTimeKind, value: &str) -> Result<DateTime, ValueError> {
+        let mut cursor = Cursor::new(value);
+        let mut result = DateTime {
+            kind,
+            year: None,
+            month: None,
+            day: None,
+            hour: None,
+            minute: None,
+            second: None,
+            timezone: None,
+        };
+
+        match kind {
+            DateTimeKind::DateTime | DateTimeKind::Date | DateTimeKind::GYearMonth | DateTimeKind::GYear => {
+                result.year = Some(parse_year(&mut cursor)?);
+                if kind != DateTimeKind::GYear {
+                    cursor.expect(b'-')?;
+                    result.month = Some(cursor.fixed_digits(2)?);
+                }
+                if kind == DateTimeKind::DateTime || kind == DateTimeKind::Date {
+                    cursor.expect(b'-')?;
+                    result.day = Some(cursor.fixed_digits(2)?);
+                }
+                if kind == DateTimeKind::DateTime {
+                    cursor.expect(b'T')?;
+                }
+            }
+            DateTimeKind::GMonthDay | DateTimeKind::GMonth => {
+                cursor.expect(b'-')?;
+                cursor.expect(b'-')?;
+                result.month = Some(cursor.fixed_digits(2)?);
+                if kind == DateTimeKind::GMonthDay {
+                    cursor.expect(b'-')?;
+                    result.day = Some(cursor.fixed_digits(2)?);
+                }
+            }
+            DateTimeKind::GDay => {
+                cursor.expect(b'-')?;
+                cursor.expect(b'-')?;
+                cursor.expect(b'-')?;
+                result.day = Some(cursor.fixed_digits(2)?);
+            }
+            DateTimeKind::Time => {}
+        }
+
+        if kind == DateTimeKind::DateTime || kind == DateTimeKind::Time {
+            result.hour = Some(cursor.fixed_digits(2)?);
+            cursor.expect(b':')?;
+            result.minute = Some(cursor.fixed_digits(2)?);
+            cursor.expect(b':')?;
+            result.second = Some(parse_seconds(&mut cursor)?);
+        }
+
+        result.timezone = parse_timezone(&mut cursor)?;
+        if !cursor.is_done() {
+            return Err(ValueError::InvalidDateTime);
+        }
+
+        result.check_ranges()?;
+        result.normalize_end_of_day()?;
+        Ok(result)
+    }
+
+    fn check_ranges(&self) -> Result<(), ValueError> {
+        if let Some(month) = self.month {
+            if !(1..=12).contains(&month) {
+                return Err(ValueError::InvalidDateTime);
+            }
+        }
+        if let Some(day) = self.day {
+            let max_day = self.month.map_or(31, |month| days_in_month(self.year, month));
+            if day < 1 || day > max_day {
+                return Err(ValueError::InvalidDateTime);
+            }
+        }
+        if let (Some(hour), Some(minute), Some(ref second)) = (self.hour, self.minute, &self.second) {
+            let is_end_of_day = hour == 24 && minute == 0 && second.whole == 0 && second.fraction.is_empty();
+            if (hour > 23 && !is_end_of_day) || minute > 59 || second.whole > 59 {
+                return Err(ValueError::InvalidDateTime);
+            }
+        }
+        Ok(())
+    }
+
+    /// 24:00:00 is the first moment of the following day, which does not exist after the
+    /// largest year
+    fn normalize_end_of_day(&mut self) -> Result<(), ValueError> {
+        if self.hour != Some(24) {
+            return Ok(());
+        }
+        self.hour = Some(0);
+        if let (Some(year), Some(month), Some(day)) = (self.year, self.month, self.day) {
+            if day < days_in_month(Some(year), month) {
+                self.day = Some(day + 1);
+            } else if month < 12 {
+                self.month = Some(month + 1);
+                self.day = Some(1);
+            } else {
+                self.year = Some(year.checked_add(1).ok_or(ValueError::OutOfRange)?);
+                self.month = Some(1);
+                self.day = Some(1);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn kind(&self) -> DateTimeKind {
+        self.kind
+    }
+
+    pub fn year(&self) -> Option<i64> {
+        self.year
+    }
+
+    pub fn month(&self) -> Option<u8> {
+        self.month
+    }
+
+    pub fn day(&self) -> Option<u8> {
+        self.day
+    }
+
+    pub fn hour(&self) -> Option<u8> {
+        self.hour
+    }
+
+    pub fn minute(&self) -> Option<u8> {
+        self.minute
+    }
+
+    pub fn second(&self) -> Option<u8> {
+        self.second.as_ref().map(|second| second.whole)
+    }
+
+    /// Digits of the fractional seconds without trailing zeros
+    pub fn fractional_second(&self) -> Option<&str> {
+        self.second.as_ref().map(|second| second.fraction.as_str())
+    }
+
+    /// Offset from UTC in minutes
+    pub fn timezone(&self) -> Option<i16> {
+        self.timezone
+    }
+
+    fn with_timezone(&self, timezone: i16) -> DateTime {
+        let mut value = self.clone();
+        value.timezone = Some(timezone);
+        value
+    }
+
+    /// see https://www.w3.org/TR/xmlschema11-2/#vp-dt-timeOnTimeline
+    fn time_on_timeline(&self) -> TimelinePoint {
+        let year = self.year.unwrap_or(1972);
+        let month = self.month.unwrap_or(12);
+        let day = self.day.unwrap_or_else(|| days_in_month(Some(year), month)) as i128;
+        let mut minute = self.minute.unwrap_or(0) as i128;
+        if let Some(timezone) = self.timezone {
+            minute -= timezone as i128;
+        }
+        let mut seconds = seconds_until_year(year);
+        for previous_month in 1..month {
+            seconds += 86400 * days_in_month(Some(year), previous_month) as i128;
+        }
+        seconds += 86400 * (day - 1);
+        seconds += 3600 * self.hour.unwrap_or(0) as i128 + 60 * minute;
+        let fraction = match self.second {
+            Some(ref second) => {
+                seconds += second.whole as i128;
+                second.fraction.clone()
+            }
+            None => String::new(),
+        };
+        (seconds, fraction)
+    }
+}
+
+fn seconds_until_year(year: i64) -> i128 {
+    let year = year as i128 - 1;
+    31536000 * year + 86400 * (year.div_euclid(400) - year.div_euclid(100) + year.div_euclid(4))
+}
+
+/// A value without timezone can be anywhere between +14:00 and -14:00 so it is only
+/// ordered against a value with timezone if it is outside of that range
+fn compare_with_local(zoned: &DateTime, local: &DateTime) -> Option<Ordering> {
+    let point = zoned.time_on_timeline();
+    if point < local.with_timezone(MAX_TIMEZONE_MINUTES).time_on_timeline() {
+        Some(Ordering::Less)
+    } else if point > local.with_timezone(-MAX_TIMEZONE_MINUTES).time_on_timeline() {
+        Some(Ordering::Greater)
+    } else {
+        None
+    }
+}
+
+/// see https://www.w3.org/TR/xmlschema11-2/#dateTime-order
+impl PartialOrd for DateTime {
+    fn partial_cmp(&self, other: &DateTime) -> Option<Ordering> {
+        if self.kind != other.kind {
+            return None;
+        }
+        match (self.timezone, other.timezone) {
+            (Some(_), None) => compare_with_local(self, other),
+            (None, Some(_)) => compare_with_local(other, self).map(Ordering::reverse),
+            _ => Some(self.time_on_timeline().cmp(&other.time_on_timeline())),
+        }
+    }
+}
+
+impl PartialEq for DateTime {
+    fn eq(&self, other: &DateTime) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+fn write_timezone(formatter: &mut fmt::Formatter, timezone: Option<i16>) -> fmt::Result {
+    match timezone {
+        Some(0) => write!(formatter, "Z"),
+        Some(offset) => {
+            let sign = if offset < 0 { '-' } else { '+' };
+            let offset = offset.abs();
+            write!(formatter, "{}{:02}:{:02}", sign, offset / 60, offset % 60)
+        }
+        None => Ok(()),
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(year) = self.year {
+            if year < 0 {
+                write!(formatter, "-")?;
+            }
+            write!(formatter, "{:04}", year.abs())?;
+        } else if self.month.is_some() {
+            write!(formatter, "-")?;
+        } else if self.day.is_some() {
+            write!(formatter, "--")?;
+        }
+        if let Some(month) = self.month {
+            write!(formatter, "-{:02}", month)?;
+        }
+        if let Some(day) = self.day {
+            write!(formatter, "-{:02}", day)?;
+        }
+        if let (Some(hour), Some(minute), Some(ref second)) = (self.hour, self.minute, &self.second) {
+            if self.kind == DateTimeKind::DateTime {
+                write!(formatter, "T")?;
+            }
+            write!(formatter, "{:02}:{:02}:{:02}", hour, minute, second.whole)?;
+            if !second.fraction.is_empty() {
+                write!(formatter, ".{}", second.fraction)?;
+            }
+        }
+        write_timezone(formatter, self.timezone)
+    }
+}
+
+/// Durations are ordered by adding them to these dateTimes, if the results are not ordered
+/// the same way for all of them the durations are incomparable
+/// see https://www.w3.org/TR/xmlschema-2/#duration-order
+static DURATION_ORDER_REFERENCES: [(i64, u8); 4] = [(1696, 9), (1697, 2), (1903, 3), (1903, 7)];
+
+fn parse_duration_number<T: ::std::str::FromStr>(digits: Option<&str>) -> Result<T, ValueError> {
+    digits.unwrap_or("0").parse().map_err(|_| ValueError::InvalidDuration)
+}
+
+/// Consumes a number followed by the designator, e.g. 12Y, if it is present
+fn parse_duration_component<'s>(cursor: &mut Cursor<'s>, designator: u8) -> Option<&'s str> {
+    let start = cursor.position;
+    let digits = cursor.digits();
+    if !digits.is_empty() && cursor.eat(designator) {
+        Some(digits)
+    } else {
+        cursor.position = start;
+        None
+    }
+}
+
+/// Seconds are the only component that may have a fraction, e.g. 12.5S
+fn parse_duration_seconds<'s>(cursor: &mut Cursor<'s>) -> Result<Option<(&'s str, &'s str)>, ValueError> {
+    let start = cursor.position;
+    let digits = cursor.digits();
+    if digits.is_empty() {
+        return Ok(None);
+    }
+    let fraction = if cursor.eat(b'.') {
+        let fraction = cursor.digits();
+        if fraction.is_empty() {
+            return Err(ValueError::InvalidDuration);
+        }
+        fraction
+    } else {
+        ""
+    };
+    if cursor.eat(b'S') {
+        Ok(Some((digits, fraction)))
+    } else {
+        cursor.position = start;
+        Ok(None)
+    }
+}
+
+impl Duration {
+    /// Parses the lexical representation of a duration, e.g. -P1Y2M3DT10H30M12.3S
+    pub fn parse(value: &str) -> Result<Duration, ValueError> {
+        let mut cursor = Cursor::new(value);
+        let negative = cursor.eat(b'-');
+        if !cursor.eat(b'P') {
+            return Err(ValueError::InvalidDuration);
+        }
+
+        let years = parse_duration_component(&mut cursor, b'Y');
+        let months = parse_duration_component(&mut cursor, b'M');
+        let days = parse_duration_component(&mut cursor, b'D');
+        let (mut hours, mut minutes, mut seconds) = (None, None, None);
+        if cursor.eat(b'T') {
+            hours = parse_duration_component(&mut cursor, b'H');
+            minutes = parse_duration_component(&mut cursor, b'M');
+            seconds = parse_duration_seconds(&mut cursor)?;
+            if hours.is_none() && minutes.is_none() && seconds.is_none() {
+                return Err(ValueError::InvalidDuration);
+            }
+        }
+        let is_empty = years.is_none() && months.is_none() && days.is_none() && hours.is_none()
+            && minutes.is_none() && seconds.is_none();
+        if is_empty || !cursor.is_done() {
+            return Err(ValueError::InvalidDuration);
+        }
+
+        let total_months = parse_duration_number::<u64>(years)?.checked_mul(12)
+            .and_then(|years| years.checked_add(parse_duration_number(months).ok()?))
+            .ok_or(ValueError::InvalidDuration)?;
+        let (whole_seconds, fraction) = seconds.unwrap_or(("0", ""));
+        let total_seconds = [(days, 86400), (hours, 3600), (minutes, 60), (Some(whole_seconds), 1)].iter()
+            .try_fold(0u128, |total, &(digits, factor)| {
+                parse_duration_number::<u128>(digits)?.checked_mul(factor)
+                    .and_then(|seconds| total.checked_add(seconds))
+                    .ok_or(ValueError::InvalidDuration)
+            })?;
+        let fraction = trim_fraction(fraction);
+
+        let is_zero = total_months == 0 && total_seconds == 0 && fraction.is_empty();
+        Ok(Duration {
+            negative: negative && !is_zero,
+            months: total_months,
+            seconds: total_seconds,
+            fraction,
+        })
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Absolute number of months
+    pub fn months(&self) -> u64 {
+        self.months
+    }
+
+    /// Absolute number of whole seconds
+    pub fn seconds(&self) -> u128 {
+        self.seconds
+    }
+
+    /// Digits of the fractional seconds without trailing zeros
+    pub fn fractional_second(&self) -> &str {
+        &self.fraction
+    }
+
+    /// see https://www.w3.org/TR/xmlschema11-2/#vp-dt-dateTimePlusDuration
+    fn added_to(&self, year: i64, month: u8) -> TimelinePoint {
+        let sign = if self.negative { -1 } else { 1 };
+        let total_months = month as i128 - 1 + sign * self.months as i128;
+        let year = year as i128 + total_months.div_euclid(12);
+        let month = (total_months.rem_euclid(12) + 1) as u8;
+
+        let mut seconds = seconds_until_year(year as i64);
+        for previous_month in 1..month {
+            seconds += 86400 * days_in_month(Some(year as i64), previous_month) as i128;
+        }
+        if !self.negative {
+            (seconds + self.seconds as i128, self.fraction.clone())
+        } else if self.fraction.is_empty() {
+            (seconds - self.seconds as i128, String::new())
+        } else {
+            (seconds - self.seconds as i128 - 1, complement_fraction(&self.fraction))
+        }
+    }
+}
+
+/// see https://www.w3.org/TR/xmlschema-2/#duration-order
+impl PartialOrd for Duration {
+    fn partial_cmp(&self, other: &Duration) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        let mut orderings = DURATION_ORDER_REFERENCES.iter()
+            .map(|&(year, month)| self.added_to(year, month).cmp(&other.added_to(year, month)));
+        let first = orderings.next()?;
+        if first != Ordering::Equal && orderings.all(|ordering| ordering == first) {
+            Some(first)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.negative {
+            write!(formatter, "-")?;
+        }
+        write!(formatter, "P")?;
+        let (years, months) = (self.months / 12, self.months % 12);
+        let days = self.seconds / 86400;
+        let hours = self.seconds % 86400 / 3600;
+        let minutes = self.seconds % 3600 / 60;
+        let seconds = self.seconds % 60;
+        if years > 0 {
+            write!(formatter, "{}Y", years)?;
+        }
+        if months > 0 {
+            write!(formatter, "{}M", months)?;
+        }
+        if days > 0 {
+            write!(formatter, "{}D", days)?;
+        }
+        let has_seconds = seconds > 0 || !self.fraction.is_empty();
+        if hours > 0 || minutes > 0 || has_seconds || self.months == 0 && self.seconds == 0 {
+            write!(formatter, "T")?;
+        }
+        if hours > 0 {
+            write!(formatter, "{}H", hours)?;
+        }
+        if minutes > 0 {
+            write!(formatter, "{}M", minutes)?;
+        }
+        if has_seconds || self.months == 0 && self.seconds == 0 {
+            write!(formatter, "{}", seconds)?;
+            if !self.fraction.is_empty() {
+                write!(formatter, ".{}", self.fraction)?;
+            }
+            write!(formatter, "S")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::*;
+
+    fn date_time(value: &str) -> DateTime {
+        DateTime::parse(DateTimeKind::DateTime, value).expect("Failed to parse dateTime")
+    }
+
+    fn duration(value: &str) -> Duration {
+        Duration::parse(value).expect("Failed to parse duration")
+    }
+
+    #[test]
+    fn parse_lexical_forms() {
+        let value = date_time("2002-10-10T12:00:00.500-05:00");
+        assert_eq!(Some(2002), value.year());
+        assert_eq!(Some(10), value.month());
+        assert_eq!(Some("5"), value.fractional_second());
+        assert_eq!(Some(-300), value.timezone());
+
+        assert_eq!(Some(-12345), date_time("-12345-01-01T00:00:00").year());
+        assert!(DateTime::parse(DateTimeKind::GYear, "02002").is_err());
+        assert!(DateTime::parse(DateTimeKind::GYear, "202").is_err());
+        assert!(DateTime::parse(DateTimeKind::Date, "2001-02-29").is_err());
+        assert!(DateTime::parse(DateTimeKind::Date, "2000-02-29").is_ok());
+        assert!(DateTime::parse(DateTimeKind::GMonthDay, "--02-29").is_ok());
+        assert!(DateTime::parse(DateTimeKind::GDay, "---31Z").is_ok());
+        assert!(DateTime::parse(DateTimeKind::GMonth, "--13").is_err());
+        assert!(DateTime::parse(DateTimeKind::Time, "12:00:00+14:01").is_err());
+        assert!(DateTime::parse(DateTimeKind::Time, "24:00:01").is_err());
+        assert!(DateTime::parse(DateTimeKind::Date, " 2002-10-10").is_err());
+    }
+
+    #[test]
+    fn end_of_day() {
+        let value = date_time("1999-12-31T24:00:00Z");
+        assert_eq!("2000-01-01T00:00:00Z", value.to_string());
+        assert_eq!(date_time("2000-01-01T00:00:00Z"), value);
+
+        let time = DateTime::parse(DateTimeKind::Time, "24:00:00").unwrap();
+        assert_eq!("00:00:00", time.to_string());
+
+        assert_eq!(Err(ValueError::OutOfRange),
+                   DateTime::parse(DateTimeKind::DateTime, "9223372036854775807-12-31T24:00:00"));
+        assert!(DateTime::parse(DateTimeKind::DateTime, "9223372036854775807-12-30T24:00:00").is_ok());
+    }
+
+    #[test]
+    fn order_with_timezones() {
+        assert_eq!(date_time("2002-10-10T12:00:00-05:00"), date_time("2002-10-10T17:00:00Z"));
+        assert!(date_time("2000-01-15T00:00:00") < date_time("2000-02-15T00:00:00"));
+        assert!(date_time("2000-01-15T12:00:00") < date_time("2000-01-16T12:00:00Z"));
+        assert!(date_time("-0001-01-01T00:00:00Z") < date_time("0000-01-01T00:00:00Z"));
+        assert!(date_time("10000-01-01T00:00:00Z") > date_time("9999-12-31T23:59:59.9999Z"));
+
+        let local = date_time("2000-01-01T12:00:00");
+        let zoned = date_time("1999-12-31T23:00:00Z");
+        assert_eq!(None, local.partial_cmp(&zoned));
+        assert_ne!(local, zoned);
+    }
+
+    #[test]
+    fn order_of_partial_types() {
+        let parse = |kind, value| DateTime::parse(kind, value).unwrap();
+        assert!(parse(DateTimeKind::GYear, "2000") < parse(DateTimeKind::GYear, "2001"));
+        assert!(parse(DateTimeKind::Time, "12:00:00.1") > parse(DateTimeKind::Time, "12:00:00.09"));
+        assert_eq!(parse(DateTimeKind::GDay, "---02+10:00"), parse(DateTimeKind::GDay, "---01-14:00"));
+        assert_eq!(None, parse(DateTimeKind::GYear, "2000").partial_cmp(&parse(DateTimeKind::Date, "2000-01-01")));
+    }
+
+    #[test]
+    fn parse_durations() {
+        let value = duration("-P1Y2M3DT10H30M12.30S");
+        assert!(value.is_negative());
+        assert_eq!(14, value.months());
+        assert_eq!(3 * 86400 + 10 * 3600 + 30 * 60 + 12, value.seconds());
+        assert_eq!("3", value.fractional_second());
+        assert_eq!("-P1Y2M3DT10H30M12.3S", value.to_string());
+        assert_eq!("PT0S", duration("-P0D").to_string());
+
+        for invalid in &["P", "PT", "P1D T1H", "P1S", "PT1D", "P1M1Y", "P1.5Y", "PT1.S", "1Y", "P-1Y"] {
+            assert!(Duration::parse(invalid).is_err(), "{} should be invalid", invalid);
+        }
+    }
+
+    #[test]
+    fn order_durations() {
+        assert_eq!(duration("PT36H"), duration("P1DT12H"));
+        assert!(duration("P1Y") > duration("P364D"));
+        assert!(duration("P1Y") < duration("P367D"));
+        assert_eq!(None, duration("P1M").partial_cmp(&duration("P30D")));
+        assert_eq!(None, duration("P1Y").partial_cmp(&duration("P365D")));
+        assert!(duration("-PT1.5S") < duration("-PT1.25S"));
+        assert_eq!(Some(Ordering::Less), duration("-P1D").partial_cmp(&duration("PT0S")));
+    }
+}
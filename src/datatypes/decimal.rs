@@ -0,0 +1,196 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use datatypes::ValueError;
+
+/// An arbitrary precision decimal number which is used for xsd:decimal and all integer types.
+/// The digits are stored without leading zeros in the integer part and without trailing
+/// zeros in the fraction so equal values always have the same representation, e.g. 1.50 and 1.5
+/// see https://www.w3.org/TR/xmlschema11-2/#decimal
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
+pub struct Decimal {
+    negative: bool,
+    integer: String,
+    fraction: String,
+}
+
+fn is_digits(value: &str) -> bool {
+    value.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+impl Decimal {
+    fn new(negative: bool, integer: &str, fraction: &str) -> Decimal {
+        let integer = integer.trim_start_matches('0').to_string();
+        let fraction = fraction.trim_end_matches('0').to_string();
+        let is_zero = integer.is_empty() && fraction.is_empty();
+        Decimal {
+            negative: negative && !is_zero,
+            integer,
+            fraction,
+        }
+    }
+
+    /// Parses the lexical representation of xsd:decimal, e.g. -1.23, +.5 or 12.
+    pub fn parse(value: &str) -> Result<Decimal, ValueError> {
+        let (negative, unsigned) = split_sign(value);
+        let (integer, fraction) = match unsigned.find('.') {
+            Some(index) => (&unsigned[..index], &unsigned[index + 1..]),
+            None => (unsigned, ""),
+        };
+        if (integer.is_empty() && fraction.is_empty()) || !is_digits(integer) || !is_digits(fraction) {
+            return Err(ValueError::InvalidDecimal);
+        }
+        Ok(Decimal::new(negative, integer, fraction))
+    }
+
+    /// Parses the lexical representation of xsd:integer which does not allow a decimal point
+    pub fn parse_integer(value: &str) -> Result<Decimal, ValueError> {
+        let (negative, unsigned) = split_sign(value);
+        if unsigned.is_empty() || !is_digits(unsigned) {
+            return Err(ValueError::InvalidDecimal);
+        }
+        Ok(Decimal::new(negative, unsigned, ""))
+    }
+
+    pub fn zero() -> Decimal {
+        Decimal::new(false, "", "")
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.integer.is_empty() && self.fraction.is_empty()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.fraction.is_empty()
+    }
+
+    /// Number of significant digits as restricted by the totalDigits facet, leading zeros of
+    /// the fraction are counted because the value needs them to be expressed, e.g. 0.0012 has 4
+    /// see https://www.w3.org/TR/xmlschema11-2/#rf-totalDigits
+    pub fn total_digits(&self) -> usize {
+        ::std::cmp::max(1, self.integer.len() + self.fraction.len())
+    }
+
+    /// Number of digits after the decimal point as restricted by the fractionDigits facet
+    /// see https://www.w3.org/TR/xmlschema11-2/#rf-fractionDigits
+    pub fn fraction_digits(&self) -> usize {
+        self.fraction.len()
+    }
+
+    fn compare_magnitude(&self, other: &Decimal) -> Ordering {
+        self.integer.len().cmp(&other.integer.len())
+            .then_with(|| self.integer.cmp(&other.integer))
+            .then_with(|| self.fraction.cmp(&other.fraction))
+    }
+}
+
+fn split_sign(value: &str) -> (bool, &str) {
+    if let Some(unsigned) = value.strip_prefix('-') {
+        (true, unsigned)
+    } else if let Some(unsigned) = value.strip_prefix('+') {
+        (false, unsigned)
+    } else {
+        (false, value)
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.compare_magnitude(other),
+            (true, true) => other.compare_magnitude(self),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Decimal {
+        Decimal::new(value < 0, &value.unsigned_abs().to_string(), "")
+    }
+}
+
+impl From<u64> for Decimal {
+    fn from(value: u64) -> Decimal {
+        Decimal::new(false, &value.to_string(), "")
+    }
+}
+
+/// Writes the canonical representation, integers are written without a decimal point
+impl fmt::Display for Decimal {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.negative {
+            write!(formatter, "-")?;
+        }
+        if self.integer.is_empty() {
+            write!(formatter, "0")?;
+        } else {
+            write!(formatter, "{}", self.integer)?;
+        }
+        if !self.fraction.is_empty() {
+            write!(formatter, ".{}", self.fraction)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(value: &str) -> Decimal {
+        Decimal::parse(value).expect("Failed to parse decimal")
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("1.5", decimal("0001.50").to_string());
+        assert_eq!("-0.5", decimal("-.5").to_string());
+        assert_eq!("12", decimal("+12.").to_string());
+        assert_eq!("0", decimal("-0.000").to_string());
+        assert_eq!("123456789012345678901234567890.000000000000000000001",
+                   decimal("123456789012345678901234567890.000000000000000000001").to_string());
+
+        for invalid in &["", ".", "-", "1e5", "1.2.3", " 1", "--1", "INF"] {
+            assert!(Decimal::parse(invalid).is_err(), "{} should be invalid", invalid);
+        }
+        assert!(Decimal::parse_integer("1.0").is_err());
+        assert_eq!(Decimal::from(-42i64), Decimal::parse_integer("-0042").unwrap());
+    }
+
+    #[test]
+    fn equality_ignores_precision() {
+        assert_eq!(decimal("1.50"), decimal("1.5"));
+        assert_eq!(decimal("-0"), decimal("0.0"));
+        assert_ne!(decimal("1.5"), decimal("-1.5"));
+    }
+
+    #[test]
+    fn order() {
+        assert!(decimal("10") > decimal("9.999"));
+        assert!(decimal("-10") < decimal("-9.999"));
+        assert!(decimal("0.45") < decimal("0.5"));
+        assert!(decimal("-0.001") < decimal("0"));
+        assert!(decimal("99999999999999999999999") > Decimal::from(u64::MAX));
+    }
+
+    #[test]
+    fn digits() {
+        assert_eq!(3, decimal("1.50").total_digits() + decimal("1.50").fraction_digits());
+        assert_eq!(4, decimal("0.0012").total_digits());
+        assert_eq!(3, decimal("120").total_digits());
+        assert_eq!(1, decimal("0").total_digits());
+        assert_eq!(0, decimal("12.000").fraction_digits());
+    }
+}
@@ -0,0 +1,152 @@
+use std::cmp::Ordering;
+
+use components::Facet;
+
+use datatypes::Value;
+
+/// The constraining facet that rejected a value
+/// see https://www.w3.org/TR/xmlschema11-2/#rf-facets
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum FacetError {
+    MinExclusive,
+    MinInclusive,
+    MaxExclusive,
+    MaxInclusive,
+    TotalDigits,
+    FractionDigits,
+    Length,
+    MinLength,
+    MaxLength,
+    Enumeration,
+    // the value of the facet itself is not in the value space of the restricted type
+    InvalidFacetValue,
+}
+
+/// Checks a value against the value based facets of a single restriction step. Facet values are
+/// mapped into the value space of the restricted type with the given function so that e.g.
+/// 1.50 and 1.5 are the same enumeration value. whiteSpace and pattern apply to the lexical
+/// representation and are not checked here.
+/// Any error returned by the function means that the facet value itself is invalid
+pub fn check_facets<F, E>(value: &Value, facets: &[Facet], parse_value: F) -> Result<(), FacetError>
+    where F: Fn(&str) -> Result<Value, E> {
+    let parse_facet_value = |lexical: &str| parse_value(lexical.trim())
+        .map_err(|_| FacetError::InvalidFacetValue);
+    let compare = |lexical: &str| -> Result<Option<Ordering>, FacetError> {
+        Ok(value.partial_cmp(&parse_facet_value(lexical)?))
+    };
+
+    for facet in facets {
+        // incomparable values, e.g. dates with and without timezone, never satisfy range facets
+        let is_valid = match *facet {
+            Facet::MinExclusive(ref limit) => compare(limit)? == Some(Ordering::Greater),
+            Facet::MinInclusive(ref limit) => compare(limit)?.is_some_and(|ordering| ordering != Ordering::Less),
+            Facet::MaxExclusive(ref limit) => compare(limit)? == Some(Ordering::Less),
+            Facet::MaxInclusive(ref limit) => compare(limit)?.is_some_and(|ordering| ordering != Ordering::Greater),
+            Facet::TotalDigits(digits) => match *value {
+                Value::Decimal(ref decimal) => decimal.total_digits() <= digits,
+                _ => true,
+            },
+            Facet::FractionDigits(digits) => match *value {
+                Value::Decimal(ref decimal) => decimal.fraction_digits() <= digits,
+                _ => true,
+            },
+            Facet::Length(expected) => value.length().is_none_or(|length| length == expected),
+            Facet::MinLength(min) => value.length().is_none_or(|length| length >= min),
+            Facet::MaxLength(max) => value.length().is_none_or(|length| length <= max),
+            _ => true,
+        };
+        if !is_valid {
+            return Err(facet_error(facet));
+        }
+    }
+
+    let mut enumeration = facets.iter()
+        .filter_map(|facet| match *facet {
+            Facet::Enumeration(ref lexical) => Some(lexical),
+            _ => None,
+        })
+        .peekable();
+    if enumeration.peek().is_some() {
+        let mut is_enumerated = false;
+        for lexical in enumeration {
+            if parse_facet_value(lexical)? == *value {
+                is_enumerated = true;
+                break;
+            }
+        }
+        if !is_enumerated {
+            return Err(FacetError::Enumeration);
+        }
+    }
+    Ok(())
+}
+
+fn facet_error(facet: &Facet) -> FacetError {
+    match *facet {
+        Facet::MinExclusive(_) => FacetError::MinExclusive,
+        Facet::MinInclusive(_) => FacetError::MinInclusive,
+        Facet::MaxExclusive(_) => FacetError::MaxExclusive,
+        Facet::MaxInclusive(_) => FacetError::MaxInclusive,
+        Facet::TotalDigits(_) => FacetError::TotalDigits,
+        Facet::FractionDigits(_) => FacetError::FractionDigits,
+        Facet::Length(_) => FacetError::Length,
+        Facet::MinLength(_) => FacetError::MinLength,
+        Facet::MaxLength(_) => FacetError::MaxLength,
+        _ => FacetError::Enumeration,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use components::{SimpleDerivation, SimpleTypeDefinition, TypeDefinition};
+    use parser::QName;
+    use parser::schema::Schema;
+    use super::*;
+
+    fn check(schema: &Schema, type_name: &str, lexical: &str) -> Result<(), FacetError> {
+        let components = &schema.components;
+        let simple_type = match components.find_type(&QName { namespace_uri: None, local_name: type_name }) {
+            Some(TypeDefinition::Simple(SimpleTypeDefinition::Defined(id))) => components.simple_type(id),
+            _ => panic!("Type not found"),
+        };
+        let (builtin, facets) = match simple_type.derivation {
+            SimpleDerivation::Restriction { base: Ok(SimpleTypeDefinition::BuiltIn(builtin)), ref facets } =>
+                (builtin, facets),
+            _ => panic!("Type is not derived from a built in type"),
+        };
+        let value = Value::parse(builtin, lexical).expect("Value is invalid");
+        check_facets(&value, facets, |facet| Value::parse(builtin, facet))
+    }
+
+    #[test]
+    fn facets() {
+        let xml = include_str!("../../tests/datatypes/facets/facets.xsd");
+        let package = DomParser::parse(xml).expect("Failed to parse");
+        let document = package.as_document();
+        let schema = Schema::from_document(&document).expect("Failed to parse schema");
+
+        assert_eq!(Ok(()), check(&schema, "Amount", "12345.67"));
+        assert_eq!(Ok(()), check(&schema, "Amount", "0012345.670"));
+        assert_eq!(Err(FacetError::TotalDigits), check(&schema, "Amount", "123456.78"));
+        assert_eq!(Err(FacetError::FractionDigits), check(&schema, "Amount", "1.234"));
+        assert_eq!(Err(FacetError::MinInclusive), check(&schema, "Amount", "-0.01"));
+
+        assert_eq!(Ok(()), check(&schema, "Rate", "1.5"));
+        assert_eq!(Ok(()), check(&schema, "Rate", "+01.500"));
+        assert_eq!(Err(FacetError::Enumeration), check(&schema, "Rate", "1.25"));
+
+        assert_eq!(Ok(()), check(&schema, "DeliveryDate", "2020-01-01Z"));
+        assert_eq!(Err(FacetError::MaxExclusive), check(&schema, "DeliveryDate", "2021-01-01Z"));
+        // without a timezone the date could be before or after the lower bound
+        assert_eq!(Err(FacetError::MinInclusive), check(&schema, "DeliveryDate", "2020-01-01"));
+        assert_eq!(Ok(()), check(&schema, "DeliveryDate", "2020-12-31"));
+
+        assert_eq!(Ok(()), check(&schema, "Code", "ABC"));
+        assert_eq!(Err(FacetError::MinLength), check(&schema, "Code", "A"));
+        assert_eq!(Err(FacetError::MaxLength), check(&schema, "Code", "ABCDE"));
+    }
+}
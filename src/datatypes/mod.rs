@@ -0,0 +1,312 @@
+pub mod datetime;
+pub mod decimal;
+pub mod facets;
+pub mod names;
+pub mod patterns;
+pub mod whitespace;
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use parser::types::BuiltIn;
+
+use datatypes::datetime::{DateTime, DateTimeKind, Duration};
+use datatypes::decimal::Decimal;
+
+/// Raised when a lexical representation can not be mapped into the value space of its type
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum ValueError {
+    InvalidDateTime,
+    InvalidDuration,
+    InvalidDecimal,
+    InvalidFloat,
+    InvalidBoolean,
+    InvalidBinary,
+    InvalidName,
+    InvalidString,
+    OutOfRange,
+    // IDREFS, ENTITIES and NMTOKENS have a minLength of 1
+    EmptyList,
+}
+
+/// A value in the value space of a built in type, all integer types share the decimal value
+/// space and string based types share the string value space. IDs, references to them, entity
+/// names and notation names are kept apart because they refer to other parts of a document or schema
+/// see https://www.w3.org/TR/xmlschema11-2/#value-space
+#[derive(PartialEq, Debug, Clone)]
+pub enum Value {
+    String(String),
+    Id(String),
+    IdRef(String),
+    Entity(String),
+    // the lexical QName, its prefix is resolved in the context of the element it appears in
+    Notation(String),
+    Boolean(bool),
+    Decimal(Decimal),
+    Float(f32),
+    Double(f64),
+    Duration(Duration),
+    DateTime(DateTime),
+    Binary(Vec<u8>),
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// Maps a lexical representation to its value, the whitespace has to be normalized already
+    pub fn parse(builtin: BuiltIn, lexical: &str) -> Result<Value, ValueError> {
+        let value = match builtin {
+            BuiltIn::Boolean => Value::Boolean(parse_boolean(lexical)?),
+            BuiltIn::Decimal => Value::Decimal(Decimal::parse(lexical)?),
+            BuiltIn::Float => Value::Float(parse_float(lexical)?),
+            BuiltIn::Double => Value::Double(parse_float(lexical)?),
+            BuiltIn::Duration => Value::Duration(Duration::parse(lexical)?),
+            BuiltIn::DateTime => Value::DateTime(DateTime::parse(DateTimeKind::DateTime, lexical)?),
+            BuiltIn::Time => Value::DateTime(DateTime::parse(DateTimeKind::Time, lexical)?),
+            BuiltIn::Date => Value::DateTime(DateTime::parse(DateTimeKind::Date, lexical)?),
+            BuiltIn::GYearMonth => Value::DateTime(DateTime::parse(DateTimeKind::GYearMonth, lexical)?),
+            BuiltIn::GYear => Value::DateTime(DateTime::parse(DateTimeKind::GYear, lexical)?),
+            BuiltIn::GMonthDay => Value::DateTime(DateTime::parse(DateTimeKind::GMonthDay, lexical)?),
+            BuiltIn::GDay => Value::DateTime(DateTime::parse(DateTimeKind::GDay, lexical)?),
+            BuiltIn::GMonth => Value::DateTime(DateTime::parse(DateTimeKind::GMonth, lexical)?),
+            BuiltIn::HexBinary => Value::Binary(parse_hex_binary(lexical)?),
+            BuiltIn::Base64Binary => Value::Binary(parse_base64_binary(lexical)?),
+            BuiltIn::Id => Value::Id(parse_string(builtin, lexical)?),
+            BuiltIn::IdRef => Value::IdRef(parse_string(builtin, lexical)?),
+            BuiltIn::IdRefs => parse_list(BuiltIn::IdRef, lexical)?,
+            BuiltIn::Entity => Value::Entity(parse_string(builtin, lexical)?),
+            BuiltIn::Notation => Value::Notation(parse_string(builtin, lexical)?),
+            BuiltIn::Entities => parse_list(BuiltIn::Entity, lexical)?,
+            BuiltIn::NmTokens => parse_list(BuiltIn::NmToken, lexical)?,
+            _ => match integer_bounds(builtin) {
+                Some((min, max)) => Value::Decimal(parse_integer(lexical, min, max)?),
+                None => Value::String(parse_string(builtin, lexical)?),
+            },
+        };
+        Ok(value)
+    }
+
+    /// Length as restricted by the length facets: characters for strings, octets for binary
+    /// values and items for lists. Other types are not restricted by length facets
+    /// see https://www.w3.org/TR/xmlschema11-2/#rf-length
+    pub fn length(&self) -> Option<usize> {
+        match *self {
+            Value::String(ref value) | Value::Id(ref value) | Value::IdRef(ref value) |
+            Value::Entity(ref value) => Some(value.chars().count()),
+            Value::Binary(ref value) => Some(value.len()),
+            Value::List(ref value) => Some(value.len()),
+            _ => None,
+        }
+    }
+}
+
+/// Only numbers, dates and durations are ordered, values of different types are incomparable
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Decimal(value), Value::Decimal(other)) => value.partial_cmp(other),
+            (Value::Float(value), Value::Float(other)) => value.partial_cmp(other),
+            (Value::Double(value), Value::Double(other)) => value.partial_cmp(other),
+            (Value::Duration(value), Value::Duration(other)) => value.partial_cmp(other),
+            (Value::DateTime(value), Value::DateTime(other)) => value.partial_cmp(other),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the inclusive bounds of the integer types, None for types which are not integers
+fn integer_bounds(builtin: BuiltIn) -> Option<(Option<Decimal>, Option<Decimal>)> {
+    let bounds = match builtin {
+        BuiltIn::Integer => (None, None),
+        BuiltIn::NonPositiveInteger => (None, Some(Decimal::from(0i64))),
+        BuiltIn::NegativeInteger => (None, Some(Decimal::from(-1i64))),
+        BuiltIn::NonNegativeInteger => (Some(Decimal::from(0i64)), None),
+        BuiltIn::PositiveInteger => (Some(Decimal::from(1i64)), None),
+        BuiltIn::Long => (Some(Decimal::from(i64::MIN)), Some(Decimal::from(i64::MAX))),
+        BuiltIn::UnsignedLong => (Some(Decimal::from(0u64)), Some(Decimal::from(u64::MAX))),
+        BuiltIn::Int => (Some(Decimal::from(i32::MIN as i64)), Some(Decimal::from(i32::MAX as i64))),
+        BuiltIn::UnsignedInt => (Some(Decimal::from(0u64)), Some(Decimal::from(u32::MAX as u64))),
+        BuiltIn::Short => (Some(Decimal::from(i16::MIN as i64)), Some(Decimal::from(i16::MAX as i64))),
+        BuiltIn::UnsignedShort => (Some(Decimal::from(0u64)), Some(Decimal::from(u16::MAX as u64))),
+        BuiltIn::Byte => (Some(Decimal::from(i8::MIN as i64)), Some(Decimal::from(i8::MAX as i64))),
+        BuiltIn::UnsignedByte => (Some(Decimal::from(0u64)), Some(Decimal::from(u8::MAX as u64))),
+        _ => return None,
+    };
+    Some(bounds)
+}
+
+fn parse_integer(lexical: &str, min: Option<Decimal>, max: Option<Decimal>) -> Result<Decimal, ValueError> {
+    let value = Decimal::parse_integer(lexical)?;
+    let is_too_small = min.is_some_and(|min| value < min);
+    let is_too_big = max.is_some_and(|max| value > max);
+    if is_too_small || is_too_big {
+        Err(ValueError::OutOfRange)
+    } else {
+        Ok(value)
+    }
+}
+
+/// Checks the lexical space of the string based types
+fn parse_string(builtin: BuiltIn, lexical: &str) -> Result<String, ValueError> {
+    let is_valid = match builtin {
+        BuiltIn::NormalizedString => !lexical.contains(['\t', '\n', '\r']),
+        BuiltIn::Token => !lexical.contains(['\t', '\n', '\r']) && !lexical.contains("  ")
+            && !lexical.starts_with(' ') && !lexical.ends_with(' '),
+        BuiltIn::Language => names::is_language(lexical),
+        BuiltIn::Name => names::is_name(lexical),
+        BuiltIn::NcName | BuiltIn::Id | BuiltIn::IdRef | BuiltIn::Entity => names::is_ncname(lexical),
+        BuiltIn::NmToken => names::is_nmtoken(lexical),
+        BuiltIn::QName | BuiltIn::Notation => names::is_qname(lexical),
+        _ => true,
+    };
+    match (is_valid, builtin) {
+        (true, _) => Ok(lexical.to_string()),
+        (false, BuiltIn::NormalizedString) | (false, BuiltIn::Token) | (false, BuiltIn::Language) =>
+            Err(ValueError::InvalidString),
+        (false, _) => Err(ValueError::InvalidName),
+    }
+}
+
+fn parse_boolean(lexical: &str) -> Result<bool, ValueError> {
+    match lexical {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(ValueError::InvalidBoolean),
+    }
+}
+
+/// Parses float and double directly into their own precision, narrowing a double would round
+/// some values twice. Rust's own parser is more lenient so the lexical space is checked first,
+/// e.g. inf or 1_000 are not allowed
+/// see https://www.w3.org/TR/xmlschema11-2/#double
+fn parse_float<F: FromStr>(lexical: &str) -> Result<F, ValueError> {
+    let special = match lexical {
+        "INF" | "+INF" => Some("inf"),
+        "-INF" => Some("-inf"),
+        "NaN" => Some("NaN"),
+        _ => None,
+    };
+    if let Some(special) = special {
+        return special.parse().map_err(|_| ValueError::InvalidFloat);
+    }
+    let (mantissa, exponent) = match lexical.find(['e', 'E']) {
+        Some(index) => (&lexical[..index], Some(&lexical[index + 1..])),
+        None => (lexical, None),
+    };
+    let is_valid_exponent = exponent.is_none_or(|exponent| {
+        let digits = exponent.trim_start_matches(['+', '-']);
+        exponent.len() - digits.len() <= 1 && !digits.is_empty()
+            && digits.bytes().all(|byte| byte.is_ascii_digit())
+    });
+    if Decimal::parse(mantissa).is_err() || !is_valid_exponent {
+        return Err(ValueError::InvalidFloat);
+    }
+    lexical.parse().map_err(|_| ValueError::InvalidFloat)
+}
+
+fn parse_hex_binary(lexical: &str) -> Result<Vec<u8>, ValueError> {
+    if !lexical.len().is_multiple_of(2) {
+        return Err(ValueError::InvalidBinary);
+    }
+    (0..lexical.len())
+        .step_by(2)
+        .map(|index| {
+            lexical.get(index..index + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or(ValueError::InvalidBinary)
+        })
+        .collect()
+}
+
+fn base64_index(character: u8) -> Option<u32> {
+    match character {
+        b'A'..=b'Z' => Some((character - b'A') as u32),
+        b'a'..=b'z' => Some((character - b'a') as u32 + 26),
+        b'0'..=b'9' => Some((character - b'0') as u32 + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// Decodes base64 data, single spaces between characters are allowed by the lexical space
+/// see https://www.w3.org/TR/xmlschema11-2/#base64Binary
+fn parse_base64_binary(lexical: &str) -> Result<Vec<u8>, ValueError> {
+    let characters: Vec<u8> = lexical.bytes().filter(|&character| character != b' ').collect();
+    if !characters.len().is_multiple_of(4) || lexical.contains("  ") {
+        return Err(ValueError::InvalidBinary);
+    }
+    let padding = characters.iter().rev().take_while(|&&character| character == b'=').count();
+    if padding > 2 {
+        return Err(ValueError::InvalidBinary);
+    }
+    let mut bytes = Vec::with_capacity(characters.len() / 4 * 3);
+    for chunk in characters[..characters.len() - padding].chunks(4) {
+        let mut buffer = 0u32;
+        for &character in chunk {
+            buffer = (buffer << 6) | base64_index(character).ok_or(ValueError::InvalidBinary)?;
+        }
+        let bits = chunk.len() * 6;
+        buffer <<= 24 - bits;
+        let decoded = [(buffer >> 16) as u8, (buffer >> 8) as u8, buffer as u8];
+        let length = bits / 8;
+        // the bits which do not make up a whole octet have to be zero
+        if buffer & (0xFFFFFF >> (length * 8)) != 0 {
+            return Err(ValueError::InvalidBinary);
+        }
+        bytes.extend_from_slice(&decoded[..length]);
+    }
+    Ok(bytes)
+}
+
+fn parse_list(item_type: BuiltIn, lexical: &str) -> Result<Value, ValueError> {
+    let items = lexical.split_whitespace()
+        .map(|item| Value::parse(item_type, item))
+        .collect::<Result<Vec<Value>, ValueError>>()?;
+    if items.is_empty() {
+        return Err(ValueError::EmptyList);
+    }
+    Ok(Value::List(items))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_numbers() {
+        assert_eq!(Value::parse(BuiltIn::Decimal, "1.50"), Value::parse(BuiltIn::Decimal, "1.5"));
+        assert_eq!(Err(ValueError::OutOfRange), Value::parse(BuiltIn::PositiveInteger, "0"));
+        assert_eq!(Err(ValueError::OutOfRange), Value::parse(BuiltIn::Byte, "128"));
+        assert!(Value::parse(BuiltIn::UnsignedLong, "18446744073709551615").is_ok());
+        assert!(Value::parse(BuiltIn::Integer, "123456789012345678901234567890").is_ok());
+        assert_eq!(Err(ValueError::InvalidDecimal), Value::parse(BuiltIn::Int, "1.0"));
+
+        assert_eq!(Ok(Value::Double(f64::INFINITY)), Value::parse(BuiltIn::Double, "INF"));
+        assert_eq!(Ok(Value::Double(-1.5e-3)), Value::parse(BuiltIn::Double, "-1.5E-3"));
+        // the nearest float, not the float nearest to the nearest double
+        assert_eq!(Ok(Value::Float(1.0000001)), Value::parse(BuiltIn::Float, "1.00000005960464477539062501"));
+        assert_eq!(Ok(Value::Float(f32::NEG_INFINITY)), Value::parse(BuiltIn::Float, "-INF"));
+        for invalid in &["inf", "1e", "1e+-2", "e5", "1_0", "infinity"] {
+            assert!(Value::parse(BuiltIn::Float, invalid).is_err(), "{} should be invalid", invalid);
+        }
+    }
+
+    #[test]
+    fn parse_binary() {
+        assert_eq!(Ok(Value::Binary(vec![0x0F, 0xB7])), Value::parse(BuiltIn::HexBinary, "0fB7"));
+        assert!(Value::parse(BuiltIn::HexBinary, "0FB").is_err());
+        assert_eq!(Ok(Value::Binary(b"any carnal pleas".to_vec())),
+                   Value::parse(BuiltIn::Base64Binary, "YW55IGNhcm5hbCBwbGVhcw=="));
+        assert_eq!(Ok(Value::Binary(b"any".to_vec())), Value::parse(BuiltIn::Base64Binary, "YW 55"));
+        assert!(Value::parse(BuiltIn::Base64Binary, "YW5=").is_err());
+        assert!(Value::parse(BuiltIn::Base64Binary, "YW55===").is_err());
+    }
+
+    #[test]
+    fn length() {
+        assert_eq!(Some(3), Value::parse(BuiltIn::String, "äöü").unwrap().length());
+        assert_eq!(Some(2), Value::parse(BuiltIn::NmTokens, "a  b").unwrap().length());
+        assert_eq!(Err(ValueError::EmptyList), Value::parse(BuiltIn::IdRefs, ""));
+        assert_eq!(None, Value::parse(BuiltIn::Decimal, "12").unwrap().length());
+    }
+}
@@ -0,0 +1,75 @@
+/// see https://www.w3.org/TR/xml/#NT-NameStartChar
+pub fn is_name_start_char(character: char) -> bool {
+    matches!(character, ':' | '_' | 'A'..='Z' | 'a'..='z'
+        | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}' | '\u{370}'..='\u{37D}'
+        | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}' | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}')
+}
+
+/// see https://www.w3.org/TR/xml/#NT-NameChar
+pub fn is_name_char(character: char) -> bool {
+    match character {
+        '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}' => true,
+        _ => is_name_start_char(character),
+    }
+}
+
+/// see https://www.w3.org/TR/xml/#NT-Name
+pub fn is_name(value: &str) -> bool {
+    let mut characters = value.chars();
+    characters.next().is_some_and(is_name_start_char) && characters.all(is_name_char)
+}
+
+/// A name without colons
+/// see https://www.w3.org/TR/xml-names/#NT-NCName
+pub fn is_ncname(value: &str) -> bool {
+    is_name(value) && !value.contains(':')
+}
+
+/// see https://www.w3.org/TR/xml/#NT-Nmtoken
+pub fn is_nmtoken(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(is_name_char)
+}
+
+/// A prefixed or unprefixed name, the prefix is not resolved
+/// see https://www.w3.org/TR/xml-names/#NT-QName
+pub fn is_qname(value: &str) -> bool {
+    match value.find(':') {
+        Some(index) => is_ncname(&value[..index]) && is_ncname(&value[index + 1..]),
+        None => is_ncname(value),
+    }
+}
+
+/// see https://www.w3.org/TR/xmlschema11-2/#language
+pub fn is_language(value: &str) -> bool {
+    value.split('-')
+        .enumerate()
+        .all(|(index, part)| {
+            !part.is_empty() && part.len() <= 8 && part.chars().all(|character| {
+                character.is_ascii_alphabetic() || (index > 0 && character.is_ascii_digit())
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert!(is_name("xsd:element"));
+        assert!(is_ncname("_purchase.Order-1"));
+        assert!(!is_ncname("xsd:element"));
+        assert!(!is_name("1st"));
+        assert!(is_nmtoken("1st"));
+        assert!(!is_nmtoken(""));
+        assert!(is_qname("xsd:string"));
+        assert!(!is_qname("a:b:c"));
+        assert!(is_language("en-US"));
+        assert!(is_language("de-1996"));
+        assert!(!is_language("1996"));
+        assert!(!is_language("englishman"));
+        assert!(!is_language("en-"));
+    }
+}
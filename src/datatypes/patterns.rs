@@ -0,0 +1,254 @@
+use regex::Regex;
+
+/// Raised when a pattern facet is not a valid XSD regular expression or uses a block escape
+/// which is not supported
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum PatternError {
+    InvalidPattern,
+    UnsupportedBlock,
+}
+
+// contents of the character classes for \i and \c
+static NAME_START_CHARS: &str = ":A-Z_a-z\\x{C0}-\\x{D6}\\x{D8}-\\x{F6}\\x{F8}-\\x{2FF}\\x{370}-\\x{37D}\
+    \\x{37F}-\\x{1FFF}\\x{200C}-\\x{200D}\\x{2070}-\\x{218F}\\x{2C00}-\\x{2FEF}\\x{3001}-\\x{D7FF}\
+    \\x{F900}-\\x{FDCF}\\x{FDF0}-\\x{FFFD}\\x{10000}-\\x{EFFFF}";
+static NAME_CHARS: &str = "\\-.0-9\\x{B7}\\x{300}-\\x{36F}\\x{203F}-\\x{2040}";
+
+/// The Unicode blocks of XSD 1.0 which can be used with \p{IsBlock}, Rust's regex crate only
+/// supports scripts. Surrogates are no characters so their blocks match nothing
+/// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#regexs
+static BLOCKS: [(&str, &[(u32, u32)]); 96] = [
+    ("BasicLatin", &[(0x0000, 0x007F)]),
+    ("Latin-1Supplement", &[(0x0080, 0x00FF)]),
+    ("LatinExtended-A", &[(0x0100, 0x017F)]),
+    ("LatinExtended-B", &[(0x0180, 0x024F)]),
+    ("IPAExtensions", &[(0x0250, 0x02AF)]),
+    ("SpacingModifierLetters", &[(0x02B0, 0x02FF)]),
+    ("CombiningDiacriticalMarks", &[(0x0300, 0x036F)]),
+    ("Greek", &[(0x0370, 0x03FF)]),
+    ("Cyrillic", &[(0x0400, 0x04FF)]),
+    ("Armenian", &[(0x0530, 0x058F)]),
+    ("Hebrew", &[(0x0590, 0x05FF)]),
+    ("Arabic", &[(0x0600, 0x06FF)]),
+    ("Syriac", &[(0x0700, 0x074F)]),
+    ("Thaana", &[(0x0780, 0x07BF)]),
+    ("Devanagari", &[(0x0900, 0x097F)]),
+    ("Bengali", &[(0x0980, 0x09FF)]),
+    ("Gurmukhi", &[(0x0A00, 0x0A7F)]),
+    ("Gujarati", &[(0x0A80, 0x0AFF)]),
+    ("Oriya", &[(0x0B00, 0x0B7F)]),
+    ("Tamil", &[(0x0B80, 0x0BFF)]),
+    ("Telugu", &[(0x0C00, 0x0C7F)]),
+    ("Kannada", &[(0x0C80, 0x0CFF)]),
+    ("Malayalam", &[(0x0D00, 0x0D7F)]),
+    ("Sinhala", &[(0x0D80, 0x0DFF)]),
+    ("Thai", &[(0x0E00, 0x0E7F)]),
+    ("Lao", &[(0x0E80, 0x0EFF)]),
+    ("Tibetan", &[(0x0F00, 0x0FFF)]),
+    ("Myanmar", &[(0x1000, 0x109F)]),
+    ("Georgian", &[(0x10A0, 0x10FF)]),
+    ("HangulJamo", &[(0x1100, 0x11FF)]),
+    ("Ethiopic", &[(0x1200, 0x137F)]),
+    ("Cherokee", &[(0x13A0, 0x13FF)]),
+    ("UnifiedCanadianAboriginalSyllabics", &[(0x1400, 0x167F)]),
+    ("Ogham", &[(0x1680, 0x169F)]),
+    ("Runic", &[(0x16A0, 0x16FF)]),
+    ("Khmer", &[(0x1780, 0x17FF)]),
+    ("Mongolian", &[(0x1800, 0x18AF)]),
+    ("LatinExtendedAdditional", &[(0x1E00, 0x1EFF)]),
+    ("GreekExtended", &[(0x1F00, 0x1FFF)]),
+    ("GeneralPunctuation", &[(0x2000, 0x206F)]),
+    ("SuperscriptsandSubscripts", &[(0x2070, 0x209F)]),
+    ("CurrencySymbols", &[(0x20A0, 0x20CF)]),
+    ("CombiningMarksforSymbols", &[(0x20D0, 0x20FF)]),
+    ("LetterlikeSymbols", &[(0x2100, 0x214F)]),
+    ("NumberForms", &[(0x2150, 0x218F)]),
+    ("Arrows", &[(0x2190, 0x21FF)]),
+    ("MathematicalOperators", &[(0x2200, 0x22FF)]),
+    ("MiscellaneousTechnical", &[(0x2300, 0x23FF)]),
+    ("ControlPictures", &[(0x2400, 0x243F)]),
+    ("OpticalCharacterRecognition", &[(0x2440, 0x245F)]),
+    ("EnclosedAlphanumerics", &[(0x2460, 0x24FF)]),
+    ("BoxDrawing", &[(0x2500, 0x257F)]),
+    ("BlockElements", &[(0x2580, 0x259F)]),
+    ("GeometricShapes", &[(0x25A0, 0x25FF)]),
+    ("MiscellaneousSymbols", &[(0x2600, 0x26FF)]),
+    ("Dingbats", &[(0x2700, 0x27BF)]),
+    ("BraillePatterns", &[(0x2800, 0x28FF)]),
+    ("CJKRadicalsSupplement", &[(0x2E80, 0x2EFF)]),
+    ("KangxiRadicals", &[(0x2F00, 0x2FDF)]),
+    ("IdeographicDescriptionCharacters", &[(0x2FF0, 0x2FFF)]),
+    ("CJKSymbolsandPunctuation", &[(0x3000, 0x303F)]),
+    ("Hiragana", &[(0x3040, 0x309F)]),
+    ("Katakana", &[(0x30A0, 0x30FF)]),
+    ("Bopomofo", &[(0x3100, 0x312F)]),
+    ("HangulCompatibilityJamo", &[(0x3130, 0x318F)]),
+    ("Kanbun", &[(0x3190, 0x319F)]),
+    ("BopomofoExtended", &[(0x31A0, 0x31BF)]),
+    ("EnclosedCJKLettersandMonths", &[(0x3200, 0x32FF)]),
+    ("CJKCompatibility", &[(0x3300, 0x33FF)]),
+    ("CJKUnifiedIdeographsExtensionA", &[(0x3400, 0x4DB5)]),
+    ("CJKUnifiedIdeographs", &[(0x4E00, 0x9FFF)]),
+    ("YiSyllables", &[(0xA000, 0xA48F)]),
+    ("YiRadicals", &[(0xA490, 0xA4CF)]),
+    ("HangulSyllables", &[(0xAC00, 0xD7A3)]),
+    ("HighSurrogates", &[]),
+    ("HighPrivateUseSurrogates", &[]),
+    ("LowSurrogates", &[]),
+    ("PrivateUse", &[(0xE000, 0xF8FF), (0xF0000, 0xFFFFD), (0x100000, 0x10FFFD)]),
+    ("CJKCompatibilityIdeographs", &[(0xF900, 0xFAFF)]),
+    ("AlphabeticPresentationForms", &[(0xFB00, 0xFB4F)]),
+    ("ArabicPresentationForms-A", &[(0xFB50, 0xFDFF)]),
+    ("CombiningHalfMarks", &[(0xFE20, 0xFE2F)]),
+    ("CJKCompatibilityForms", &[(0xFE30, 0xFE4F)]),
+    ("SmallFormVariants", &[(0xFE50, 0xFE6F)]),
+    ("ArabicPresentationForms-B", &[(0xFE70, 0xFEFE)]),
+    ("Specials", &[(0xFEFF, 0xFEFF), (0xFFF0, 0xFFFD)]),
+    ("HalfwidthandFullwidthForms", &[(0xFF00, 0xFFEF)]),
+    ("OldItalic", &[(0x10300, 0x1032F)]),
+    ("Gothic", &[(0x10330, 0x1034F)]),
+    ("Deseret", &[(0x10400, 0x1044F)]),
+    ("ByzantineMusicalSymbols", &[(0x1D000, 0x1D0FF)]),
+    ("MusicalSymbols", &[(0x1D100, 0x1D1FF)]),
+    ("MathematicalAlphanumericSymbols", &[(0x1D400, 0x1D7FF)]),
+    ("CJKUnifiedIdeographsExtensionB", &[(0x20000, 0x2A6D6)]),
+    ("CJKCompatibilityIdeographsSupplement", &[(0x2F800, 0x2FA1F)]),
+    ("Tags", &[(0xE0000, 0xE007F)]),
+];
+
+/// Compiles a pattern facet, patterns always have to match the whole value
+/// see https://www.w3.org/TR/xmlschema11-2/#regexs
+pub fn compile(pattern: &str) -> Result<Regex, PatternError> {
+    let translated = translate(pattern)?;
+    Regex::new(&format!("^(?:{})$", translated)).map_err(|_| PatternError::InvalidPattern)
+}
+
+/// Translates an escape into a Rust character class or escaped character
+fn translate_escape(characters: &mut ::std::iter::Peekable<::std::str::Chars>) -> Result<String, PatternError> {
+    let escaped = characters.next().ok_or(PatternError::InvalidPattern)?;
+    let translated = match escaped {
+        'i' => format!("[{}]", NAME_START_CHARS),
+        'I' => format!("[^{}]", NAME_START_CHARS),
+        'c' => format!("[{}{}]", NAME_START_CHARS, NAME_CHARS),
+        'C' => format!("[^{}{}]", NAME_START_CHARS, NAME_CHARS),
+        's' => "[\\x20\\t\\n\\r]".to_string(),
+        'S' => "[^\\x20\\t\\n\\r]".to_string(),
+        'w' => "[^\\p{P}\\p{Z}\\p{C}]".to_string(),
+        'W' => "[\\p{P}\\p{Z}\\p{C}]".to_string(),
+        'd' | 'D' | 'n' | 'r' | 't' | '\\' | '|' | '.' | '?' | '*' | '+' | '(' | ')' | '{' | '}'
+        | '-' | '[' | ']' | '^' => format!("\\{}", escaped),
+        'p' | 'P' => {
+            if characters.next() != Some('{') {
+                return Err(PatternError::InvalidPattern);
+            }
+            let name: String = characters.by_ref().take_while(|&character| character != '}').collect();
+            let negation = if escaped == 'P' { "^" } else { "" };
+            if let Some(block) = name.strip_prefix("Is") {
+                let &(_, ranges) = BLOCKS.iter()
+                    .find(|&&(candidate, _)| candidate == block)
+                    .ok_or(PatternError::UnsupportedBlock)?;
+                if ranges.is_empty() {
+                    // a class of no characters, its complement is a class of all characters
+                    let negation = if escaped == 'P' { "" } else { "^" };
+                    return Ok(format!("[{}\\x{{0}}-\\x{{10FFFF}}]", negation));
+                }
+                let ranges: String = ranges.iter()
+                    .map(|&(start, end)| format!("\\x{{{:X}}}-\\x{{{:X}}}", start, end))
+                    .collect();
+                format!("[{}{}]", negation, ranges)
+            } else {
+                format!("\\{}{{{}}}", escaped, name)
+            }
+        }
+        _ => return Err(PatternError::InvalidPattern),
+    };
+    Ok(translated)
+}
+
+/// XSD patterns are anchored, treat ^ and $ as normal characters, only know single character
+/// escapes and use -[...] for subtraction inside of character classes
+fn translate(pattern: &str) -> Result<String, PatternError> {
+    let mut translated = String::with_capacity(pattern.len());
+    let mut characters = pattern.chars().peekable();
+    let mut class_depth = 0;
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => translated.push_str(&translate_escape(&mut characters)?),
+            '[' => {
+                class_depth += 1;
+                translated.push('[');
+                if characters.peek() == Some(&'^') {
+                    characters.next();
+                    translated.push('^');
+                }
+            }
+            ']' if class_depth > 0 => {
+                class_depth -= 1;
+                translated.push(']');
+            }
+            '-' if class_depth > 0 && characters.peek() == Some(&'[') => {
+                // class subtraction, e.g. [a-z-[aeiou]]
+                translated.push_str("--");
+            }
+            '.' if class_depth == 0 => translated.push_str("[^\\n\\r]"),
+            '^' | '$' | '&' | '~' | '#' | ' ' => {
+                translated.push('\\');
+                translated.push(character);
+            }
+            _ => translated.push(character),
+        }
+    }
+    if class_depth != 0 {
+        return Err(PatternError::InvalidPattern);
+    }
+    Ok(translated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, value: &str) -> bool {
+        compile(pattern).expect("Failed to compile pattern").is_match(value)
+    }
+
+    #[test]
+    fn anchored() {
+        assert!(matches("\\d{3}-[A-Z]{2}", "926-AA"));
+        assert!(!matches("\\d{3}-[A-Z]{2}", "926-AA-926"));
+        assert!(!matches("[A-Z]", "ZZ"));
+        assert!(matches("a|b", "b"));
+        assert!(!matches("a|b", "ab"));
+    }
+
+    #[test]
+    fn literal_characters() {
+        assert!(matches("^\\d+$", "^42$"));
+        assert!(matches("$ \\d+", "$ 42"));
+        assert!(matches("\\d+ &amp", "42 &amp"));
+        assert!(!matches(".", "\n"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(matches("[a-z-[aeiou]]+", "bcd"));
+        assert!(!matches("[a-z-[aeiou]]+", "bad"));
+        assert!(matches("\\i\\c*", "xsd:element"));
+        assert!(!matches("\\i\\c*", "1st"));
+        assert!(matches("\\s\\S", " a"));
+        assert!(matches("\\p{Lu}\\p{IsBasicLatin}", "Äa"));
+        assert!(!matches("\\P{IsBasicLatin}", "a"));
+        assert!(matches("[&~]{2}", "&~"));
+        assert!(matches("\\p{IsLatinExtendedAdditional}+", "\u{1E9E}\u{1EF9}"));
+        assert!(matches("\\p{IsSpecials}\\p{IsOldItalic}", "\u{FFFD}\u{10300}"));
+        assert!(!matches("\\p{IsHighSurrogates}", "a"));
+        assert!(matches("\\P{IsLowSurrogates}", "a"));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        assert_eq!(Err(PatternError::InvalidPattern), compile("[a-z").map(|_| ()));
+        assert_eq!(Err(PatternError::InvalidPattern), compile("\\q").map(|_| ()));
+        assert_eq!(Err(PatternError::UnsupportedBlock), compile("\\p{IsKlingon}").map(|_| ()));
+    }
+}
@@ -0,0 +1,43 @@
+use parser::types::{BuiltIn, WhiteSpaceValue};
+
+fn is_white_space(character: char) -> bool {
+    character == ' ' || character == '\t' || character == '\n' || character == '\r'
+}
+
+/// Normalizes a text or attribute value according to the whiteSpace facet
+/// see https://www.w3.org/TR/xmlschema11-2/#rf-whiteSpace
+pub fn normalize(value: &str, white_space: WhiteSpaceValue) -> String {
+    match white_space {
+        WhiteSpaceValue::Preserve => value.to_string(),
+        WhiteSpaceValue::Replace => value.chars()
+            .map(|character| if is_white_space(character) { ' ' } else { character })
+            .collect(),
+        WhiteSpaceValue::Collapse => value.split(is_white_space)
+            .filter(|token| !token.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" "),
+    }
+}
+
+/// The whiteSpace facet of the built in types, only string and normalizedString do not collapse
+pub fn builtin_white_space(builtin: BuiltIn) -> WhiteSpaceValue {
+    match builtin {
+        BuiltIn::String | BuiltIn::AnySimpleType => WhiteSpaceValue::Preserve,
+        BuiltIn::NormalizedString => WhiteSpaceValue::Replace,
+        _ => WhiteSpaceValue::Collapse,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_white_space() {
+        let value = "\t Math \r\n  &  others ";
+        assert_eq!(value, normalize(value, WhiteSpaceValue::Preserve));
+        assert_eq!("  Math     &  others ", normalize(value, WhiteSpaceValue::Replace));
+        assert_eq!("Math & others", normalize(value, WhiteSpaceValue::Collapse));
+        assert_eq!("", normalize(" \n ", WhiteSpaceValue::Collapse));
+    }
+}
@@ -1,4 +1,19 @@
-mod parser;
+pub mod parser;
+pub mod components;
+pub mod datatypes;
+pub mod validator;
+pub mod resolver;
+pub mod catalog;
+pub mod compiled;
+pub mod schema_set;
+#[cfg(test)]
+mod testing;
+
+pub use parser::schema::Schema;
+pub use compiled::CompiledSchema;
+pub use schema_set::SchemaSet;
+pub use catalog::Catalog;
 
 extern crate sxd_document;
 extern crate sxd_xpath;
+extern crate regex;
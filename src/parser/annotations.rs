@@ -11,6 +11,8 @@ use parser::{
     parse_additional_attributes,
 };
 use parser::Language;
+use parser::positions::Position;
+use parser::schema::Schema;
 
 /// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#anyURI
 #[derive(Eq, PartialEq, Debug)]
@@ -41,32 +43,42 @@ pub struct Documentation<'a> {
 pub struct Annotation<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub app_info: Vec<AppInfo<'a>>,
     pub documentation: Vec<Documentation<'a>>,
 }
 
+impl<'a> Annotation<'a> {
+    /// The line and column of the annotation element, only known for schemas created by
+    /// Schema::from_source
+    pub fn position(&self, schema: &Schema) -> Option<Position> {
+        schema.position(self.source)
+    }
+}
 
 
 pub fn parse_annotation<'a>(element: &DomElement<'a>) -> Option<Annotation<'a>> {
-    parse_child(&element,
+    parse_child(element,
                 |&el| is_of_element(&el, "annotation"),
                 |el| {
                     Annotation {
                         id: parse_id(&el),
                         additional_attributes: parse_additional_attributes(&el),
+                        source: el,
                         app_info: Vec::new(),
                         documentation: Vec::new(),
                     }
                 })
 }
 
-pub fn parse_annotations<'a>(elements: &Vec<DomElement<'a>>) -> Vec<Annotation<'a>> {
+pub fn parse_annotations<'a>(elements: &[DomElement<'a>]) -> Vec<Annotation<'a>> {
     elements.iter()
-        .filter(|&el| is_of_element(&el, "annotation"))
+        .filter(|&el| is_of_element(el, "annotation"))
         .map(|el| {
             Annotation {
-                id: parse_id(&el),
-                additional_attributes: parse_additional_attributes(&el),
+                id: parse_id(el),
+                additional_attributes: parse_additional_attributes(el),
+                source: *el,
                 app_info: Vec::new(),
                 documentation: Vec::new(),
             }
@@ -81,30 +93,18 @@ mod tests {
     use sxd_document::parser as DomParser;
 
     use parser::schema::*;
-    use parser::*;
-    use parser::versions::*;
-    use parser::types::TopLevelType;
-    use parser::types::SimpleType;
-    use parser::types::SimpleTypeContent;
-    use parser::types::Restriction;
-    use parser::types::AnySimpleType;
-    use parser::types::BuiltIn;
-    use parser::types::RestrictionRule;
-    use parser::types::Pattern;
-    use std::collections::HashSet;
-    use super::*;
 
     #[test]
     fn annotation() {
         let xml = include_str!("../../tests/parser/annotations/annotations.xsd");
-        let package = DomParser::parse(&xml).expect("Failed to parse");
+        let package = DomParser::parse(xml).expect("Failed to parse");
         let document = package.as_document();
         let schema = Schema::from_document(&document).expect("Failed to parse schema");
 
         let annotations = schema.annotations;
         assert_eq!(2, annotations.len());
 
-        let annotation1 = annotations.get(0).unwrap();
+        let annotation1 = annotations.first().unwrap();
         assert_eq!("annotation1", annotation1.id.as_ref().unwrap().id);
         assert_eq!(1, annotation1.additional_attributes.len());
         //assert_eq!(1, annotation1.app_info.len());
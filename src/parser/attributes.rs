@@ -0,0 +1,183 @@
+use sxd_document::dom::{
+    Element as DomElement,
+    Attribute as DomAttribute,
+};
+
+use parser::{
+    XSD_NS_URI,
+    parse_children,
+    parse_child,
+    parse_id,
+    parse_additional_attributes,
+    parse_form,
+    parse_qname_attribute,
+    parse_value_constraint,
+    required_attribute,
+    is_of_element,
+    ParseError,
+    QName,
+    ValueConstraint,
+};
+use parser::types::{Id, AnySimpleType, BuiltIn, parse_type_name, parse_simple_type};
+use parser::annotations::{Annotation, parse_annotation};
+use parser::schema::FormChoice;
+use parser::wildcards::{AnyAttribute, parse_any_attribute};
+
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum Usage {
+    Optional,
+    Required,
+    Prohibited,
+}
+
+/// A top level or local attribute declaration
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-attribute
+#[derive(Eq, PartialEq, Debug)]
+pub struct Attribute<'a> {
+    pub id: Option<Id<'a>>,
+    pub name: &'a str,
+    // taken from either type or a nested simpleType, defaults to anySimpleType
+    pub attribute_type: AnySimpleType<'a>,
+    // only allowed on local declarations, defaults to optional
+    pub usage: Usage,
+    // only applies to local declarations, taken from the form attribute or the
+    // attributeFormDefault of the schema
+    pub form: FormChoice,
+    pub value_constraint: Option<ValueConstraint<'a>>,
+    pub annotation: Option<Annotation<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
+}
+
+/// A reference to a top level attribute declaration
+#[derive(Eq, PartialEq, Debug)]
+pub struct AttributeReference<'a> {
+    pub id: Option<Id<'a>>,
+    pub reference: QName<'a>,
+    pub usage: Usage,
+    // replaces the value constraint of the referenced declaration
+    pub value_constraint: Option<ValueConstraint<'a>>,
+    pub annotation: Option<Annotation<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+}
+
+/// A reference to a named attribute group
+#[derive(Eq, PartialEq, Debug)]
+pub struct AttributeGroupReference<'a> {
+    pub id: Option<Id<'a>>,
+    pub reference: QName<'a>,
+    pub annotation: Option<Annotation<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+}
+
+/// The attribute related content of complex types and attribute groups
+#[derive(Eq, PartialEq, Debug)]
+pub enum AttributeUse<'a> {
+    Attribute(Box<Attribute<'a>>),
+    Reference(AttributeReference<'a>),
+    Group(AttributeGroupReference<'a>),
+}
+
+/// A top level named attribute group
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-attributeGroup
+#[derive(Eq, PartialEq, Debug)]
+pub struct AttributeGroup<'a> {
+    pub id: Option<Id<'a>>,
+    pub name: &'a str,
+    pub attributes: Vec<AttributeUse<'a>>,
+    pub any_attribute: Option<AnyAttribute<'a>>,
+    pub annotation: Option<Annotation<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
+}
+
+fn parse_usage(element: &DomElement) -> Usage {
+    match element.attribute_value("use").map(str::trim) {
+        Some("required") => Usage::Required,
+        Some("prohibited") => Usage::Prohibited,
+        _ => Usage::Optional,
+    }
+}
+
+pub fn parse_attribute(element: DomElement) -> Result<Attribute, ParseError> {
+    let attribute_type = match element.attribute_value("type") {
+        Some(name) => parse_type_name(&element, name, "type")?,
+        None => parse_child(&element, |el| is_of_element(el, "simpleType"),
+                            |el| parse_simple_type(el).map(|simple_type| AnySimpleType::SimpleType(Box::new(simple_type))))
+            .transpose()?
+            .unwrap_or(AnySimpleType::BuiltIn(BuiltIn::AnySimpleType)),
+    };
+    Ok(Attribute {
+        id: parse_id(&element),
+        name: required_attribute(&element, "name")?,
+        attribute_type,
+        usage: parse_usage(&element),
+        form: parse_form(&element, "form", "attributeFormDefault"),
+        value_constraint: parse_value_constraint(&element),
+        annotation: parse_annotation(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        source: element,
+    })
+}
+
+fn parse_attribute_use(element: DomElement) -> Result<AttributeUse, ParseError> {
+    let reference = element.attribute_value("ref")
+        .map(|reference| parse_qname_attribute(&element, reference, "ref"))
+        .transpose()?;
+    Ok(match (element.name().local_part(), reference) {
+        ("attributeGroup", Some(reference)) => AttributeUse::Group(AttributeGroupReference {
+            id: parse_id(&element),
+            reference,
+            annotation: parse_annotation(&element),
+            additional_attributes: parse_additional_attributes(&element),
+        }),
+        (_, Some(reference)) => AttributeUse::Reference(AttributeReference {
+            id: parse_id(&element),
+            reference,
+            usage: parse_usage(&element),
+            value_constraint: parse_value_constraint(&element),
+            annotation: parse_annotation(&element),
+            additional_attributes: parse_additional_attributes(&element),
+        }),
+        ("attributeGroup", None) => return Err(ParseError::new("s4s-att-must-appear", "ref", element)),
+        _ => AttributeUse::Attribute(Box::new(parse_attribute(element)?)),
+    })
+}
+
+fn is_attribute_use(element: &DomElement) -> bool {
+    element.name().namespace_uri() == Some(XSD_NS_URI)
+        && (element.name().local_part() == "attribute" || element.name().local_part() == "attributeGroup")
+}
+
+/// Parses the attribute, attributeGroup and anyAttribute children of an element
+pub fn parse_attribute_uses<'a>(element: &DomElement<'a>)
+                                -> Result<(Vec<AttributeUse<'a>>, Option<AnyAttribute<'a>>), ParseError<'a>> {
+    let attributes = parse_children(element, is_attribute_use, parse_attribute_use).into_iter().collect::<Result<_, _>>()?;
+    let any_attribute = parse_child(element, |el| is_of_element(el, "anyAttribute"), parse_any_attribute);
+    Ok((attributes, any_attribute))
+}
+
+pub fn parse_attributes<'a>(elements: &[DomElement<'a>]) -> Result<Vec<Attribute<'a>>, ParseError<'a>> {
+    elements.iter()
+        .filter(|&element| is_of_element(element, "attribute"))
+        .map(|&element| parse_attribute(element))
+        .collect()
+}
+
+pub fn parse_attribute_groups<'a>(elements: &[DomElement<'a>]) -> Result<Vec<AttributeGroup<'a>>, ParseError<'a>> {
+    elements.iter()
+        .filter(|&element| is_of_element(element, "attributeGroup"))
+        .map(|&element| {
+            let (attributes, any_attribute) = parse_attribute_uses(&element)?;
+            Ok(AttributeGroup {
+                id: parse_id(&element),
+                name: required_attribute(&element, "name")?,
+                attributes,
+                any_attribute,
+                annotation: parse_annotation(&element),
+                additional_attributes: parse_additional_attributes(&element),
+                source: element,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,91 @@
+use sxd_document::dom::{Document, Element as DomElement};
+
+use parser::{find_root_schema, is_of_element, required_attribute, ParseError};
+use parser::attributes::{parse_attribute_groups, AttributeGroup};
+use parser::groups::{parse_groups, Group};
+use parser::types::{parse_types, TopLevelType};
+
+/// Adds the components of a schema document with the same or no target namespace
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-include
+#[derive(Eq, PartialEq, Debug)]
+pub struct Include<'a> {
+    pub schema_location: &'a str,
+    pub source: DomElement<'a>,
+}
+
+/// Allows references to the components of another namespace, the location is only a hint
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-import
+#[derive(Eq, PartialEq, Debug)]
+pub struct Import<'a> {
+    // None imports the components without a namespace
+    pub namespace: Option<&'a str>,
+    pub schema_location: Option<&'a str>,
+    pub source: DomElement<'a>,
+}
+
+/// Includes a schema document like include but replaces some of its types and groups with new
+/// definitions, which refer to the replaced definitions by their own name
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#modify-schema
+#[derive(Eq, PartialEq, Debug)]
+pub struct Redefine<'a> {
+    pub schema_location: &'a str,
+    pub types: Vec<TopLevelType<'a>>,
+    pub groups: Vec<Group<'a>>,
+    pub attribute_groups: Vec<AttributeGroup<'a>>,
+    pub source: DomElement<'a>,
+}
+
+pub fn parse_includes<'a>(elements: &[DomElement<'a>]) -> Result<Vec<Include<'a>>, ParseError<'a>> {
+    elements.iter()
+        .filter(|&element| is_of_element(element, "include"))
+        .map(|&element| Ok(Include {
+            schema_location: required_attribute(&element, "schemaLocation")?,
+            source: element,
+        }))
+        .collect()
+}
+
+pub fn parse_imports<'a>(elements: &[DomElement<'a>]) -> Vec<Import<'a>> {
+    elements.iter()
+        .filter(|&element| is_of_element(element, "import"))
+        .map(|&element| Import {
+            namespace: element.attribute_value("namespace"),
+            schema_location: element.attribute_value("schemaLocation"),
+            source: element,
+        })
+        .collect()
+}
+
+pub fn parse_redefines<'a>(elements: &[DomElement<'a>]) -> Result<Vec<Redefine<'a>>, ParseError<'a>> {
+    elements.iter()
+        .filter(|&element| is_of_element(element, "redefine"))
+        .map(|&element| {
+            let children: Vec<DomElement> = element.children().into_iter()
+                .filter_map(|child| child.element())
+                .collect();
+            Ok(Redefine {
+                schema_location: required_attribute(&element, "schemaLocation")?,
+                types: parse_types(&children)?,
+                groups: parse_groups(&children)?,
+                attribute_groups: parse_attribute_groups(&children)?,
+                source: element,
+            })
+        })
+        .collect()
+}
+
+/// Gives a schema document without a target namespace the namespace of the schema that includes
+/// it, references without a prefix then resolve to the components of that namespace. Documents
+/// with a target namespace are left as they are and false is returned
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#src-include
+pub fn include_into(document: &Document, namespace: &str) -> bool {
+    let schema = match find_root_schema(document.root()) {
+        Some(schema) if schema.attribute_value("targetNamespace").is_none() => schema,
+        _ => return false,
+    };
+    schema.set_attribute_value("targetNamespace", namespace);
+    if schema.recursive_default_namespace_uri().is_none() {
+        schema.set_default_namespace_uri(Some(namespace));
+    }
+    true
+}
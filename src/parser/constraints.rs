@@ -0,0 +1,1129 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use sxd_document::dom::Element as DomElement;
+
+use components::{
+    ComponentName,
+    Components,
+    ContentType,
+    ElementId,
+    Facet,
+    GroupId,
+    Particle as ComponentParticle,
+    SimpleDerivation,
+    SimpleTypeDefinition,
+    Term,
+    TypeDefinition,
+};
+
+use parser::{XSD_NS_URI, ComponentError, ComponentKind, QName, SchemaError};
+use parser::positions::Position;
+use parser::schema::{FormChoice, Schema};
+use parser::elements::Element;
+use parser::groups::{GroupReference, MaxOccurs, Particle};
+use parser::attributes::{Attribute, AttributeGroup, AttributeUse, Usage};
+use parser::types::{
+    AnySimpleType,
+    AnyType,
+    BuiltIn,
+    ComplexFinal,
+    ComplexType,
+    ComplexTypeContent,
+    DerivationMethod,
+    RestrictionRule,
+    SimpleFinal,
+    SimpleType,
+    SimpleTypeContent,
+    TopLevelType,
+};
+use validator::{check_substitution_groups, check_value_constraints};
+
+/// A named or anonymous type definition or a global or local declaration anywhere inside of a schema
+#[derive(Copy, Clone)]
+pub enum Component<'s, 'a: 's> {
+    Simple(&'s SimpleType<'a>),
+    Complex(&'s ComplexType<'a>),
+    Element(&'s Element<'a>),
+    Attribute(&'s Attribute<'a>),
+}
+
+/// Calls the visitor for every type definition and declaration of a schema including the
+/// anonymous types and local declarations nested inside of them
+pub fn visit_components<'s, 'a, F>(schema: &'s Schema<'a>, visit: &mut F)
+    where F: FnMut(Component<'s, 'a>) {
+    for top_level_type in &schema.types {
+        match *top_level_type {
+            TopLevelType::SimpleType(ref simple_type) => visit_simple_type(simple_type, visit),
+            TopLevelType::ComplexType(ref complex_type) => visit_complex_type(complex_type, visit),
+        }
+    }
+    for element in &schema.elements {
+        visit_element(element, visit);
+    }
+    for attribute in &schema.attributes {
+        visit_attribute(attribute, visit);
+    }
+    for group in &schema.groups {
+        visit_particle(&group.particle, visit);
+    }
+    for group in &schema.attribute_groups {
+        visit_attribute_uses(&group.attributes, visit);
+    }
+    for redefine in &schema.redefines {
+        for top_level_type in &redefine.types {
+            match *top_level_type {
+                TopLevelType::SimpleType(ref simple_type) => visit_simple_type(simple_type, visit),
+                TopLevelType::ComplexType(ref complex_type) => visit_complex_type(complex_type, visit),
+            }
+        }
+        for group in &redefine.groups {
+            visit_particle(&group.particle, visit);
+        }
+        for group in &redefine.attribute_groups {
+            visit_attribute_uses(&group.attributes, visit);
+        }
+    }
+}
+
+fn visit_simple_type<'s, 'a, F>(simple_type: &'s SimpleType<'a>, visit: &mut F)
+    where F: FnMut(Component<'s, 'a>) {
+    visit(Component::Simple(simple_type));
+    match *simple_type.content {
+        SimpleTypeContent::Restriction(ref restriction) => visit_any_simple_type(&restriction.restriction_type, visit),
+        SimpleTypeContent::List(ref list) => visit_any_simple_type(&list.item_type, visit),
+        SimpleTypeContent::Union(ref union) => for member_type in &union.member_types {
+            visit_any_simple_type(member_type, visit);
+        },
+    }
+}
+
+fn visit_any_simple_type<'s, 'a, F>(simple_type: &'s AnySimpleType<'a>, visit: &mut F)
+    where F: FnMut(Component<'s, 'a>) {
+    if let AnySimpleType::SimpleType(ref simple_type) = *simple_type {
+        visit_simple_type(simple_type, visit);
+    }
+}
+
+fn visit_complex_type<'s, 'a, F>(complex_type: &'s ComplexType<'a>, visit: &mut F)
+    where F: FnMut(Component<'s, 'a>) {
+    visit(Component::Complex(complex_type));
+    let derivation = match *complex_type.content {
+        ComplexTypeContent::SimpleContent(ref derivation) |
+        ComplexTypeContent::ComplexContent(ref derivation) => derivation,
+    };
+    if let Some(ref simple_type) = derivation.simple_type {
+        visit_simple_type(simple_type, visit);
+    }
+    if let Some(ref particle) = derivation.particle {
+        visit_particle(particle, visit);
+    }
+    visit_attribute_uses(&derivation.attributes, visit);
+}
+
+fn visit_element<'s, 'a, F>(element: &'s Element<'a>, visit: &mut F)
+    where F: FnMut(Component<'s, 'a>) {
+    visit(Component::Element(element));
+    match element.element_type {
+        AnyType::ComplexType(ref complex_type) => visit_complex_type(complex_type, visit),
+        AnyType::AnySimpleType(ref simple_type) => visit_any_simple_type(simple_type, visit),
+        AnyType::Any | AnyType::Reference(_) => {}
+    }
+}
+
+fn visit_particle<'s, 'a, F>(particle: &'s Particle<'a>, visit: &mut F)
+    where F: FnMut(Component<'s, 'a>) {
+    match *particle {
+        Particle::Element(ref element) => visit_element(element, visit),
+        Particle::Sequence(ref group) | Particle::Choice(ref group) | Particle::All(ref group) =>
+            for particle in &group.particles {
+                visit_particle(particle, visit);
+            },
+        Particle::ElementReference(_) | Particle::Group(_) | Particle::Any(_) => {}
+    }
+}
+
+fn visit_attribute_uses<'s, 'a, F>(attributes: &'s [AttributeUse<'a>], visit: &mut F)
+    where F: FnMut(Component<'s, 'a>) {
+    for attribute in attributes {
+        if let AttributeUse::Attribute(ref attribute) = *attribute {
+            visit_attribute(attribute, visit);
+        }
+    }
+}
+
+fn visit_attribute<'s, 'a, F>(attribute: &'s Attribute<'a>, visit: &mut F)
+    where F: FnMut(Component<'s, 'a>) {
+    visit(Component::Attribute(attribute));
+    visit_any_simple_type(&attribute.attribute_type, visit);
+}
+
+impl<'s, 'a: 's> Component<'s, 'a> {
+    /// Describes the component for an error about the constraint with the given code
+    pub fn error(self, schema: &Schema, code: &'static str, attribute: &'static str) -> ComponentError {
+        ComponentError {
+            code,
+            kind: self.kind(),
+            name: self.name().map(str::to_string),
+            attribute,
+            position: schema.position(self.source()),
+        }
+    }
+
+    fn kind(self) -> ComponentKind {
+        match self {
+            Component::Simple(_) => ComponentKind::SimpleType,
+            Component::Complex(_) => ComponentKind::ComplexType,
+            Component::Element(_) => ComponentKind::Element,
+            Component::Attribute(_) => ComponentKind::Attribute,
+        }
+    }
+
+    fn name(self) -> Option<&'a str> {
+        match self {
+            Component::Simple(simple_type) => simple_type.name,
+            Component::Complex(complex_type) => complex_type.name,
+            Component::Element(element) => Some(element.name),
+            Component::Attribute(attribute) => Some(attribute.name),
+        }
+    }
+
+    /// The element of the schema document that the component was parsed from
+    pub fn source(self) -> DomElement<'a> {
+        match self {
+            Component::Simple(simple_type) => simple_type.source,
+            Component::Complex(complex_type) => complex_type.source,
+            Component::Element(element) => element.source,
+            Component::Attribute(attribute) => attribute.source,
+        }
+    }
+}
+
+/// Finds a named type in any of the schemas
+fn find_type<'s, 'a>(schemas: &[&'s Schema<'a>], name: &QName) -> Option<&'s TopLevelType<'a>> {
+    schemas.iter().filter_map(|schema| schema.find_type(name)).next()
+}
+
+/// Resolves a simple type definition, built in types are never final
+fn find_simple_type<'s, 'a>(schemas: &[&'s Schema<'a>], simple_type: &'s AnySimpleType<'a>)
+                            -> Option<&'s SimpleType<'a>> {
+    match *simple_type {
+        AnySimpleType::SimpleType(ref simple_type) => Some(simple_type),
+        AnySimpleType::Reference(ref name) => match find_type(schemas, name) {
+            Some(TopLevelType::SimpleType(simple_type)) => Some(simple_type),
+            _ => None,
+        },
+        AnySimpleType::BuiltIn(_) => None,
+    }
+}
+
+/// Checks that a type is not derived from a base type whose final set forbids the derivation,
+/// the base may be defined by any of the schemas. Bases which can not be resolved are left to
+/// the validator
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#ct-props-correct
+/// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#st-props-correct
+fn check_final(schema: &Schema, schemas: &[&Schema], component: Component) -> Result<(), SchemaError> {
+    let is_final = |simple_type: &AnySimpleType, method: SimpleFinal| {
+        find_simple_type(schemas, simple_type)
+            .is_some_and(|simple_type| simple_type.final_modes.contains(&method))
+    };
+    let violation = match component {
+        Component::Complex(complex_type) => {
+            let derivation = match *complex_type.content {
+                ComplexTypeContent::SimpleContent(ref derivation) |
+                ComplexTypeContent::ComplexContent(ref derivation) => derivation,
+            };
+            let is_final = match derivation.base {
+                AnyType::Reference(ref name) => match (find_type(schemas, name), derivation.method) {
+                    (Some(TopLevelType::ComplexType(base)), DerivationMethod::Extension) =>
+                        base.final_modes.contains(&ComplexFinal::Extension),
+                    (Some(TopLevelType::ComplexType(base)), DerivationMethod::Restriction) =>
+                        base.final_modes.contains(&ComplexFinal::Restriction),
+                    (Some(TopLevelType::SimpleType(base)), DerivationMethod::Extension) =>
+                        base.final_modes.contains(&SimpleFinal::Extension),
+                    _ => false,
+                },
+                _ => false,
+            };
+            match derivation.method {
+                DerivationMethod::Extension if is_final => Some(("cos-ct-extends.1.1", "base")),
+                DerivationMethod::Restriction if is_final => Some(("derivation-ok-restriction.1", "base")),
+                _ => None,
+            }
+        }
+        Component::Simple(simple_type) => match *simple_type.content {
+            SimpleTypeContent::Restriction(ref restriction)
+                if is_final(&restriction.restriction_type, SimpleFinal::Restriction) =>
+                Some(("st-props-correct.3", "base")),
+            SimpleTypeContent::List(ref list) if is_final(&list.item_type, SimpleFinal::List) =>
+                Some(("cos-st-restricts.2.3.1.1", "itemType")),
+            SimpleTypeContent::Union(ref union) if union.member_types.iter()
+                .any(|member_type| is_final(member_type, SimpleFinal::Union)) =>
+                Some(("cos-st-restricts.3.3.1.1", "memberTypes")),
+            _ => None,
+        },
+        Component::Element(_) | Component::Attribute(_) => None,
+    };
+    match violation {
+        Some((code, attribute)) => Err(SchemaError::DerivedFromFinal(component.error(schema, code, attribute))),
+        None => Ok(()),
+    }
+}
+
+/// NOTATION can not be used as the type of a declaration directly, only restrictions of it
+/// with an enumeration of the allowed notations
+/// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#enumeration-required-notation
+fn check_notation(schema: &Schema, component: Component) -> Result<(), SchemaError> {
+    let is_notation = |simple_type: &AnySimpleType| *simple_type == AnySimpleType::BuiltIn(BuiltIn::Notation);
+    let violation = match component {
+        Component::Element(element) if matches!(element.element_type, AnyType::AnySimpleType(ref simple_type)
+                                                if is_notation(simple_type)) => Some("type"),
+        Component::Attribute(attribute) if is_notation(&attribute.attribute_type) => Some("type"),
+        Component::Simple(simple_type) => match *simple_type.content {
+            SimpleTypeContent::Restriction(ref restriction) if is_notation(&restriction.restriction_type) &&
+                !restriction.rules.iter().any(|rule| matches!(*rule, RestrictionRule::Enumeration(_))) => Some("base"),
+            _ => None,
+        },
+        _ => None,
+    };
+    match violation {
+        Some(attribute) => Err(SchemaError::NotationWithoutEnumeration(
+            component.error(schema, "enumeration-required-notation", attribute))),
+        None => Ok(()),
+    }
+}
+
+/// Checks if a name is the one of a built in simple type or, where complex types are allowed, of
+/// anyType. Attributes and simple types can not reference anyType
+fn is_built_in(name: &QName, allows_any_type: bool) -> bool {
+    name.namespace_uri == Some(XSD_NS_URI) &&
+        (BuiltIn::from_name(name.local_name).is_some() || allows_any_type && name.local_name == "anyType")
+}
+
+/// References into the target namespace of one of the schemas have to name a component of
+/// one of them and references into the XSD namespace a built in type, references into other
+/// namespaces are left to the validator which may load the schemas for them
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#src-resolve
+struct References<'s, 'a: 's> {
+    // the schema whose references are checked
+    schema: &'s Schema<'a>,
+    // all schemas whose components the references may name, including the checked one
+    schemas: &'s [&'s Schema<'a>],
+}
+
+impl<'s, 'a: 's> References<'s, 'a> {
+    /// Describes a reference to a top level component, the position is the one of the type or
+    /// group that contains the reference
+    fn reference_error(&self, kind: ComponentKind, reference: &QName, source: DomElement) -> SchemaError {
+        SchemaError::UnresolvedReference(ComponentError {
+            code: "src-resolve",
+            kind,
+            name: Some(reference.local_name.to_string()),
+            attribute: "ref",
+            position: self.schema.position(source),
+        })
+    }
+
+    fn is_missing(&self, name: &QName, find: fn(&'s Schema<'a>, &QName) -> bool) -> bool {
+        let is_checked = name.namespace_uri == Some(XSD_NS_URI) ||
+            self.schemas.iter().any(|schema| schema.target_namespace == name.namespace_uri);
+        is_checked && !self.schemas.iter().any(|&schema| find(schema, name))
+    }
+
+    fn check_type(&self, any_type: &AnyType, component: Component, attribute: &'static str) -> Result<(), SchemaError> {
+        let find = |schema: &Schema, name: &QName| is_built_in(name, true) || schema.find_type(name).is_some();
+        match *any_type {
+            AnyType::Reference(ref name) if self.is_missing(name, find) =>
+                Err(SchemaError::UnresolvedReference(component.error(self.schema, "src-resolve", attribute))),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_simple_type(&self, simple_type: &AnySimpleType, component: Component, attribute: &'static str)
+                         -> Result<(), SchemaError> {
+        let find = |schema: &Schema, name: &QName| is_built_in(name, false) || schema.find_type(name).is_some();
+        match *simple_type {
+            AnySimpleType::Reference(ref name) if self.is_missing(name, find) =>
+                Err(SchemaError::UnresolvedReference(component.error(self.schema, "src-resolve", attribute))),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_component(&self, component: Component) -> Result<(), SchemaError> {
+        match component {
+            Component::Element(element) => {
+                self.check_type(&element.element_type, component, "type")?;
+                let is_missing = element.substitution_groups.iter()
+                    .any(|head| self.is_missing(head, |schema, name| schema.find_element(name).is_some()));
+                if is_missing {
+                    let error = component.error(self.schema, "src-resolve", "substitutionGroup");
+                    return Err(SchemaError::UnresolvedReference(error));
+                }
+                Ok(())
+            }
+            Component::Attribute(attribute) => self.check_simple_type(&attribute.attribute_type, component, "type"),
+            Component::Simple(simple_type) => match *simple_type.content {
+                SimpleTypeContent::Restriction(ref restriction) =>
+                    self.check_simple_type(&restriction.restriction_type, component, "base"),
+                SimpleTypeContent::List(ref list) => self.check_simple_type(&list.item_type, component, "itemType"),
+                SimpleTypeContent::Union(ref union) => union.member_types.iter()
+                    .try_for_each(|member_type| self.check_simple_type(member_type, component, "memberTypes")),
+            },
+            Component::Complex(complex_type) => {
+                let derivation = match *complex_type.content {
+                    ComplexTypeContent::SimpleContent(ref derivation) |
+                    ComplexTypeContent::ComplexContent(ref derivation) => derivation,
+                };
+                self.check_type(&derivation.base, component, "base")?;
+                if let Some(ref particle) = derivation.particle {
+                    self.check_particle(particle, complex_type.source)?;
+                }
+                self.check_attribute_uses(&derivation.attributes, complex_type.source)
+            }
+        }
+    }
+
+    /// Checks the references inside of a content model, errors point to the given source of the
+    /// type or group that contains the content model
+    fn check_particle(&self, particle: &Particle, source: DomElement) -> Result<(), SchemaError> {
+        match *particle {
+            Particle::ElementReference(ref element)
+                if self.is_missing(&element.reference, |schema, name| schema.find_element(name).is_some()) =>
+                Err(self.reference_error(ComponentKind::Element, &element.reference, source)),
+            Particle::Group(ref group)
+                if self.is_missing(&group.reference, |schema, name| schema.find_group(name).is_some()) =>
+                Err(self.reference_error(ComponentKind::Group, &group.reference, source)),
+            Particle::Sequence(ref group) | Particle::Choice(ref group) | Particle::All(ref group) =>
+                group.particles.iter().try_for_each(|particle| self.check_particle(particle, source)),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_attribute_uses(&self, attributes: &[AttributeUse], source: DomElement) -> Result<(), SchemaError> {
+        attributes.iter().try_for_each(|attribute| match *attribute {
+            AttributeUse::Reference(ref attribute)
+                if self.is_missing(&attribute.reference, |schema, name| schema.find_attribute(name).is_some()) =>
+                Err(self.reference_error(ComponentKind::Attribute, &attribute.reference, source)),
+            AttributeUse::Group(ref group)
+                if self.is_missing(&group.reference, |schema, name| schema.find_attribute_group(name).is_some()) =>
+                Err(self.reference_error(ComponentKind::AttributeGroup, &group.reference, source)),
+            _ => Ok(()),
+        })
+    }
+}
+
+/// Checks the constraints on schema components which can not be expressed by the schema for
+/// schemas. References may name the components of any of the given schemas
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#outcome-src
+pub fn check_schema<'s, 'a>(schema: &'s Schema<'a>, schemas: &'s [&'s Schema<'a>]) -> Result<(), SchemaError> {
+    let references = References { schema, schemas };
+    let mut result = Ok(());
+    visit_components(schema, &mut |component| {
+        if result.is_ok() {
+            result = references.check_component(component)
+                .and_then(|_| check_final(schema, schemas, component))
+                .and_then(|_| check_notation(schema, component));
+        }
+    });
+    result?;
+    let redefined_groups = schema.redefines.iter().flat_map(|redefine| &redefine.groups);
+    for group in schema.groups.iter().chain(redefined_groups) {
+        references.check_particle(&group.particle, group.source)?;
+    }
+    let redefined_attribute_groups = schema.redefines.iter().flat_map(|redefine| &redefine.attribute_groups);
+    for group in schema.attribute_groups.iter().chain(redefined_attribute_groups) {
+        references.check_attribute_uses(&group.attributes, group.source)?;
+    }
+    check_redefines(schema, schemas)
+}
+
+/// The references of a content model to model groups, nested model groups included
+fn group_references<'s, 'a>(particle: &'s Particle<'a>, references: &mut Vec<&'s GroupReference<'a>>) {
+    match *particle {
+        Particle::Group(ref group) => references.push(group),
+        Particle::Sequence(ref group) | Particle::Choice(ref group) | Particle::All(ref group) =>
+            for particle in &group.particles {
+                group_references(particle, references);
+            },
+        _ => {}
+    }
+}
+
+/// The name of the declaration of an attribute use, local declarations are only in the target
+/// namespace if they are qualified
+fn attribute_use_name<'a>(attribute: &AttributeUse<'a>, target_namespace: Option<&'a str>)
+                          -> Option<(Option<&'a str>, &'a str)> {
+    match *attribute {
+        AttributeUse::Attribute(ref attribute) if attribute.usage != Usage::Prohibited => match attribute.form {
+            FormChoice::Qualified => Some((target_namespace, attribute.name)),
+            FormChoice::Unqualified => Some((None, attribute.name)),
+        },
+        AttributeUse::Reference(ref attribute) if attribute.usage != Usage::Prohibited =>
+            Some((attribute.reference.namespace_uri, attribute.reference.local_name)),
+        _ => None,
+    }
+}
+
+/// Checks that an attribute group that does not refer to the group it redefines only allows
+/// attributes that the redefined group allows, prohibited attributes are always allowed
+fn is_attribute_group_restriction<'a>(schema: &Schema<'a>, group: &AttributeGroup<'a>, original: &AttributeGroup<'a>)
+                                      -> bool {
+    let name = |attribute: &AttributeUse<'a>| attribute_use_name(attribute, schema.target_namespace);
+    let allows = |attribute: &AttributeUse<'a>| match *attribute {
+        AttributeUse::Group(ref reference) => original.attributes.iter()
+            .any(|allowed| matches!(*allowed, AttributeUse::Group(ref allowed) if allowed.reference == reference.reference)),
+        _ => match name(attribute) {
+            Some(attribute) => original.attributes.iter().any(|allowed| name(allowed) == Some(attribute)),
+            None => true,
+        },
+    };
+    group.attributes.iter().all(allows) && (group.any_attribute.is_none() || original.any_attribute.is_some())
+}
+
+/// The types of a redefine have to be derived from the types they redefine. Groups may refer to
+/// the group they redefine once, attribute groups either refer to the group they redefine once
+/// and extend it or only allow attributes that it allows. Redefinitions whose originals are not
+/// part of the schemas are left to the reference checks
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#src-redefine
+fn check_redefines(schema: &Schema, schemas: &[&Schema]) -> Result<(), SchemaError> {
+    let is_own = |name: &QName, local_name| name.namespace_uri == schema.target_namespace && name.local_name == local_name;
+    let error = |code, kind, name: &str, source| SchemaError::InvalidRedefinition(ComponentError {
+        code,
+        kind,
+        name: Some(name.to_string()),
+        attribute: "ref",
+        position: schema.position(source),
+    });
+    for redefine in &schema.redefines {
+        for top_level_type in &redefine.types {
+            let (component, base) = match *top_level_type {
+                TopLevelType::SimpleType(ref simple_type) => (Component::Simple(simple_type), match *simple_type.content {
+                    SimpleTypeContent::Restriction(ref restriction) => match restriction.restriction_type {
+                        AnySimpleType::Reference(ref base) => Some(base),
+                        _ => None,
+                    },
+                    _ => None,
+                }),
+                TopLevelType::ComplexType(ref complex_type) => {
+                    let derivation = match *complex_type.content {
+                        ComplexTypeContent::SimpleContent(ref derivation) |
+                        ComplexTypeContent::ComplexContent(ref derivation) => derivation,
+                    };
+                    (Component::Complex(complex_type), match derivation.base {
+                        AnyType::Reference(ref base) => Some(base),
+                        _ => None,
+                    })
+                }
+            };
+            let name = component.name().unwrap_or_default();
+            if !base.is_some_and(|base| is_own(base, name)) {
+                return Err(SchemaError::InvalidRedefinition(component.error(schema, "src-redefine.5", "base")));
+            }
+        }
+        for group in &redefine.groups {
+            let mut references = Vec::new();
+            group_references(&group.particle, &mut references);
+            let own: Vec<&GroupReference> = references.into_iter()
+                .filter(|reference| is_own(&reference.reference, group.name))
+                .collect();
+            let code = match own.len() {
+                0 => continue,
+                1 if own[0].min_occurs == 1 && own[0].max_occurs == MaxOccurs::Bounded(1) => continue,
+                1 => "src-redefine.6.1.2",
+                _ => "src-redefine.6.1.1",
+            };
+            return Err(error(code, ComponentKind::Group, group.name, group.source));
+        }
+        for group in &redefine.attribute_groups {
+            let own = group.attributes.iter()
+                .filter(|attribute| matches!(**attribute, AttributeUse::Group(ref reference)
+                                             if is_own(&reference.reference, group.name)))
+                .count();
+            let name = QName { namespace_uri: schema.target_namespace, local_name: group.name };
+            let original = schemas.iter().find_map(|schema| schema.find_attribute_group(&name));
+            let code = match (own, original) {
+                (1, _) | (0, None) => continue,
+                (0, Some(original)) if is_attribute_group_restriction(schema, group, original) => continue,
+                (0, Some(_)) => "src-redefine.7.2.2",
+                _ => "src-redefine.7.1",
+            };
+            return Err(error(code, ComponentKind::AttributeGroup, group.name, group.source));
+        }
+    }
+    Ok(())
+}
+
+/// Finds the first top level component whose name is already used by another component of the
+/// same symbol space in one of the schemas, simple and complex types share a symbol space and so
+/// do the identity constraints of all element declarations, including the local ones.
+/// Returns the index of the schema that declares the duplicate
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#sch-props-correct
+pub fn find_duplicate(schemas: &[&Schema]) -> Option<(usize, ComponentError)> {
+    let mut names = HashSet::new();
+    for (index, schema) in schemas.iter().enumerate() {
+        let types = schema.types.iter().map(|top_level_type| match *top_level_type {
+            TopLevelType::SimpleType(ref simple_type) =>
+                (ComponentKind::SimpleType, simple_type.name.unwrap_or_default(), simple_type.source),
+            TopLevelType::ComplexType(ref complex_type) =>
+                (ComponentKind::ComplexType, complex_type.name.unwrap_or_default(), complex_type.source),
+        });
+        let mut identity_constraints = Vec::new();
+        visit_components(schema, &mut |component| if let Component::Element(element) = component {
+            identity_constraints.extend(element.identity_constraints.iter()
+                .map(|constraint| (ComponentKind::IdentityConstraint, constraint.name, constraint.source)));
+        });
+        // the symbol space of each component
+        let components = schema.elements.iter()
+            .map(|element| (0, (ComponentKind::Element, element.name, element.source)))
+            .chain(schema.attributes.iter()
+                .map(|attribute| (1, (ComponentKind::Attribute, attribute.name, attribute.source))))
+            .chain(types.map(|top_level_type| (2, top_level_type)))
+            .chain(schema.groups.iter().map(|group| (3, (ComponentKind::Group, group.name, group.source))))
+            .chain(schema.attribute_groups.iter()
+                .map(|group| (4, (ComponentKind::AttributeGroup, group.name, group.source))))
+            .chain(schema.notations.iter().map(|notation| (5, (ComponentKind::Notation, notation.name, notation.source))))
+            .chain(identity_constraints.into_iter().map(|constraint| (6, constraint)));
+        for (symbol_space, (kind, name, source)) in components {
+            if !names.insert((symbol_space, schema.target_namespace, name)) {
+                return Some((index, ComponentError {
+                    code: "sch-props-correct.2",
+                    kind,
+                    name: Some(name.to_string()),
+                    attribute: "name",
+                    position: schema.position(source),
+                }));
+            }
+        }
+    }
+    None
+}
+
+/// Follows the successors of a node depth first and returns the nodes from the first one that is
+/// reached again to the one that leads back to it
+fn follow<T, F>(node: T, successors: &F, path: &mut Vec<T>, finished: &mut HashSet<T>) -> Option<Vec<T>>
+    where T: Copy + Eq + Hash, F: Fn(T) -> Vec<T> {
+    if let Some(start) = path.iter().position(|&visited| visited == node) {
+        return Some(path[start..].to_vec());
+    }
+    if finished.contains(&node) {
+        return None;
+    }
+    path.push(node);
+    for successor in successors(node) {
+        if let Some(cycle) = follow(successor, successors, path, finished) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    finished.insert(node);
+    None
+}
+
+/// Finds the first cycle of a graph given by its nodes and the successors of each node
+fn find_cycle<T, F>(nodes: Vec<T>, successors: F) -> Option<Vec<T>>
+    where T: Copy + Eq + Hash, F: Fn(T) -> Vec<T> {
+    let mut finished = HashSet::new();
+    nodes.into_iter().find_map(|node| follow(node, &successors, &mut Vec::new(), &mut finished))
+}
+
+/// The model groups that a content model refers to outside of element declarations, references
+/// inside of element types are recursive content which is allowed
+fn referenced_groups(particle: &ComponentParticle, groups: &mut Vec<GroupId>) {
+    match particle.term {
+        Term::Group(Ok(group)) => groups.push(group),
+        Term::ModelGroup(ref group) => for particle in &group.particles {
+            referenced_groups(particle, groups);
+        },
+        _ => {}
+    }
+}
+
+/// Describes a cycle of named components, the error names the first of them
+fn circular_definition(code: &'static str, kind: ComponentKind, attribute: &'static str,
+                       cycle: Vec<(Option<&ComponentName>, Option<Position>)>) -> SchemaError {
+    let first = cycle.iter().position(|&(name, _)| name.is_some()).unwrap_or(0);
+    let (name, position) = cycle[first];
+    let names = cycle[first..].iter().chain(&cycle[..first])
+        .filter_map(|&(name, _)| name.cloned())
+        .collect();
+    SchemaError::CircularDefinition(ComponentError {
+        code,
+        kind,
+        name: name.map(|name| name.local_name.clone()),
+        attribute,
+        position,
+    }, names)
+}
+
+/// Types may not be derived from themselves, model groups and attribute groups may not contain
+/// themselves and elements may not be members of their own substitution groups. Elements whose
+/// type contains the elements themselves are not circular definitions
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#ct-props-correct
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#mg-props-correct
+fn check_cycles(components: &Components) -> Result<(), SchemaError> {
+    let simple_types = components.simple_types()
+        .map(|(id, _)| TypeDefinition::Simple(SimpleTypeDefinition::Defined(id)));
+    let types = simple_types.chain(components.complex_types().map(|(id, _)| TypeDefinition::Complex(id))).collect();
+    let defined = |simple_type: &Result<SimpleTypeDefinition, ComponentName>| match *simple_type {
+        Ok(simple_type @ SimpleTypeDefinition::Defined(_)) => Some(TypeDefinition::Simple(simple_type)),
+        _ => None,
+    };
+    let bases = |type_definition| match type_definition {
+        TypeDefinition::Simple(SimpleTypeDefinition::Defined(id)) => match components.simple_type(id).derivation {
+            SimpleDerivation::Restriction { ref base, .. } => defined(base).into_iter().collect(),
+            SimpleDerivation::List(ref item_type) => defined(item_type).into_iter().collect(),
+            SimpleDerivation::Union(ref member_types) => member_types.iter().filter_map(defined).collect(),
+        },
+        TypeDefinition::Complex(id) => {
+            let complex_type = components.complex_type(id);
+            let base = match complex_type.base {
+                Ok(TypeDefinition::Any) | Err(_) => None,
+                Ok(base) => Some(base),
+            };
+            let nested = match complex_type.content {
+                ContentType::Simple { simple_type: Some(id), .. } =>
+                    Some(TypeDefinition::Simple(SimpleTypeDefinition::Defined(id))),
+                _ => None,
+            };
+            base.into_iter().chain(nested).collect()
+        }
+        _ => Vec::new(),
+    };
+    if let Some(cycle) = find_cycle(types, bases) {
+        let is_complex = cycle.iter().any(|type_definition| matches!(*type_definition, TypeDefinition::Complex(_)));
+        let (code, kind) = if is_complex {
+            ("ct-props-correct.3", ComponentKind::ComplexType)
+        } else {
+            ("st-props-correct.2", ComponentKind::SimpleType)
+        };
+        let cycle = cycle.into_iter()
+            .map(|type_definition| match type_definition {
+                TypeDefinition::Simple(SimpleTypeDefinition::Defined(id)) => {
+                    let simple_type = components.simple_type(id);
+                    (simple_type.name.as_ref(), simple_type.position)
+                }
+                TypeDefinition::Complex(id) => {
+                    let complex_type = components.complex_type(id);
+                    (complex_type.name.as_ref(), complex_type.position)
+                }
+                _ => (None, None),
+            })
+            .collect();
+        return Err(circular_definition(code, kind, "base", cycle));
+    }
+
+    let groups = components.groups().map(|(id, _)| id).collect();
+    let contained = |id| {
+        let mut groups = Vec::new();
+        referenced_groups(&components.group(id).particle, &mut groups);
+        groups
+    };
+    if let Some(cycle) = find_cycle(groups, contained) {
+        let cycle = cycle.into_iter()
+            .map(|id| (Some(&components.group(id).name), components.group(id).position))
+            .collect();
+        return Err(circular_definition("mg-props-correct.2", ComponentKind::Group, "ref", cycle));
+    }
+
+    let attribute_groups = components.attribute_groups().map(|(id, _)| id).collect();
+    let contained = |id| components.attribute_group(id).attribute_groups.iter()
+        .filter_map(|group| group.as_ref().ok().cloned())
+        .collect();
+    if let Some(cycle) = find_cycle(attribute_groups, contained) {
+        let cycle = cycle.into_iter()
+            .map(|id| (Some(&components.attribute_group(id).name), components.attribute_group(id).position))
+            .collect();
+        return Err(circular_definition("src-attribute_group.3", ComponentKind::AttributeGroup, "ref", cycle));
+    }
+
+    let elements = components.global_elements().map(|(id, _)| id).collect();
+    let heads = |id| components.element(id).substitution_groups.iter()
+        .filter_map(|head| head.as_ref().ok().cloned())
+        .collect();
+    if let Some(cycle) = find_cycle(elements, heads) {
+        let cycle = cycle.into_iter()
+            .map(|id| (Some(&components.element(id).name), components.element(id).position))
+            .collect();
+        return Err(circular_definition("e-props-correct.6", ComponentKind::Element, "substitutionGroup", cycle));
+    }
+    Ok(())
+}
+
+/// The element declarations of a content model, including the ones of nested and referenced
+/// model groups
+fn element_particles(components: &Components, particle: &ComponentParticle, elements: &mut Vec<ElementId>) {
+    match particle.term {
+        Term::Element(Ok(element)) => elements.push(element),
+        Term::ModelGroup(ref group) => for particle in &group.particles {
+            element_particles(components, particle, elements);
+        },
+        Term::Group(Ok(group)) => element_particles(components, &components.group(group).particle, elements),
+        _ => {}
+    }
+}
+
+/// Element particles of the same name in a content model must have the same type, the content
+/// model of an extension includes the particles of its base
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cos-element-consistent
+fn check_consistent_elements(components: &Components) -> Result<(), SchemaError> {
+    for (id, _) in components.complex_types() {
+        let mut elements = Vec::new();
+        let mut next = Some(id);
+        while let Some(id) = next.take() {
+            let complex_type = components.complex_type(id);
+            if let ContentType::Complex(Some(ref particle)) = complex_type.content {
+                element_particles(components, particle, &mut elements);
+            }
+            if let (DerivationMethod::Extension, Ok(TypeDefinition::Complex(base))) =
+                   (complex_type.method, &complex_type.base) {
+                next = Some(*base);
+            }
+        }
+        for (index, &id) in elements.iter().enumerate() {
+            let element = components.element(id);
+            let is_inconsistent = elements[..index].iter()
+                .map(|&other| components.element(other))
+                .any(|other| other.name == element.name && other.type_definition.is_ok() &&
+                     element.type_definition.is_ok() && other.type_definition != element.type_definition);
+            if is_inconsistent {
+                return Err(SchemaError::InconsistentElements(ComponentError {
+                    code: "cos-element-consistent",
+                    kind: ComponentKind::Element,
+                    name: Some(element.name.local_name.clone()),
+                    attribute: "type",
+                    position: element.position,
+                }));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The value of a pattern facet has to be a regular expression, it is compiled when the
+/// components are built so that a type with an invalid pattern is rejected before any value is
+/// validated against it
+/// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#rf-pattern
+fn check_patterns(components: &Components) -> Result<(), SchemaError> {
+    let is_invalid = |facets: &[Facet]| facets.iter()
+        .any(|facet| matches!(*facet, Facet::Pattern(ref pattern) if pattern.regex.is_err()));
+    let simple_types = components.simple_types()
+        .filter(|&(_, simple_type)| match simple_type.derivation {
+            SimpleDerivation::Restriction { ref facets, .. } => is_invalid(facets),
+            _ => false,
+        })
+        .map(|(_, simple_type)| (ComponentKind::SimpleType, &simple_type.name, simple_type.position));
+    let complex_types = components.complex_types()
+        .filter(|&(_, complex_type)| match complex_type.content {
+            ContentType::Simple { ref facets, .. } => is_invalid(facets),
+            _ => false,
+        })
+        .map(|(_, complex_type)| (ComponentKind::ComplexType, &complex_type.name, complex_type.position));
+    match simple_types.chain(complex_types).next() {
+        Some((kind, name, position)) => Err(SchemaError::InvalidRepresentation(ComponentError {
+            code: "s4s-att-invalid-value",
+            kind,
+            name: name.as_ref().map(|name| name.local_name.clone()),
+            attribute: "value",
+            position,
+        })),
+        None => Ok(()),
+    }
+}
+
+/// Checks the constraints which depend on resolved types, e.g. whether a default value is valid
+/// for the type of its declaration. Circular definitions are rejected first, the other checks
+/// rely on derivations and substitution groups that end
+pub fn check_components(components: &Arc<Components>) -> Result<(), SchemaError> {
+    check_cycles(components)?;
+    check_patterns(components)?;
+    check_consistent_elements(components)?;
+    check_substitution_groups(components)?;
+    check_value_constraints(components)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use components::ComponentName;
+    use parser::{ComponentError, ComponentKind, SchemaError};
+    use parser::positions::Position;
+    use parser::schema::Schema;
+    use testing::check_schema as parse;
+
+    #[test]
+    fn derived_from_final() {
+        let xsd = "<xsd:simpleType name=\"Code\" final=\"restriction list\">\
+                   <xsd:restriction base=\"xsd:string\"/></xsd:simpleType>\
+                   <xsd:complexType name=\"Sealed\" final=\"extension\"/>\
+                   <xsd:complexType name=\"Open\"/>";
+        assert_eq!(Ok(()), parse(xsd, ""));
+        assert_eq!(Err(SchemaError::DerivedFromFinal(ComponentError {
+                       code: "st-props-correct.3",
+                       kind: ComponentKind::SimpleType,
+                       name: Some("Derived".to_string()),
+                       attribute: "base",
+                       position: None,
+                   })),
+                   parse(&format!("{}<xsd:simpleType name=\"Derived\">\
+                                   <xsd:restriction base=\"Code\"/></xsd:simpleType>", xsd), ""));
+        assert_eq!(Some("cos-ct-extends.1.1"),
+                   parse(&format!("{}<xsd:complexType name=\"Derived\"><xsd:complexContent>\
+                                   <xsd:extension base=\"Sealed\"/></xsd:complexContent></xsd:complexType>", xsd), "")
+                       .unwrap_err().code());
+        assert_eq!(Some("cos-st-restricts.2.3.1.1"),
+                   parse(&format!("{}<xsd:element name=\"codes\"><xsd:simpleType>\
+                                   <xsd:list itemType=\"Code\"/></xsd:simpleType></xsd:element>", xsd), "")
+                       .unwrap_err().code());
+        // a restriction of Open is allowed unless finalDefault forbids it
+        let restricted = format!("{}<xsd:complexType name=\"Derived\"><xsd:complexContent>\
+                                  <xsd:restriction base=\"Open\"/></xsd:complexContent></xsd:complexType>", xsd);
+        assert_eq!(Ok(()), parse(&restricted, ""));
+        assert_eq!(Some("derivation-ok-restriction.1"), parse(&restricted, "finalDefault=\"#all\"").unwrap_err().code());
+    }
+
+    #[test]
+    fn unresolved_references() {
+        assert_eq!(Err(SchemaError::UnresolvedReference(ComponentError {
+                       code: "src-resolve",
+                       kind: ComponentKind::Element,
+                       name: Some("order".to_string()),
+                       attribute: "type",
+                       position: None,
+                   })),
+                   parse("<xsd:element name=\"order\" type=\"OrderType\"/>", ""));
+        assert_eq!(Some(&ComponentError {
+                       code: "src-resolve",
+                       kind: ComponentKind::Group,
+                       name: Some("items".to_string()),
+                       attribute: "ref",
+                       position: None,
+                   }),
+                   parse("<xsd:complexType name=\"Order\"><xsd:sequence><xsd:group ref=\"items\"/></xsd:sequence>\
+                          </xsd:complexType>", "").unwrap_err().component());
+        // references into other namespaces may be resolved by the schemas loaded for a document
+        assert_eq!(Ok(()), parse("<xsd:element name=\"order\" xmlns:other=\"urn:other\" type=\"other:Order\"/>", ""));
+
+        // the XSD namespace only has the built in types and anyType
+        let error = |xsd: &str| parse(xsd, "").unwrap_err().component()
+            .map(|component| (component.code, component.kind, component.attribute));
+        assert_eq!(Some(("src-resolve", ComponentKind::Element, "type")),
+                   error("<xsd:element name=\"order\" type=\"xsd:strin\"/>"));
+        assert_eq!(Some(("src-resolve", ComponentKind::SimpleType, "base")),
+                   error("<xsd:simpleType name=\"Code\"><xsd:restriction base=\"xsd:strin\"/></xsd:simpleType>"));
+        assert_eq!(Some(("src-resolve", ComponentKind::Attribute, "type")),
+                   error("<xsd:attribute name=\"sku\" type=\"xsd:anyType\"/>"));
+        assert_eq!(Some(("src-resolve", ComponentKind::Element, "ref")),
+                   error("<xsd:complexType name=\"Order\"><xsd:sequence><xsd:element ref=\"xsd:schema\"/>\
+                          </xsd:sequence></xsd:complexType>"));
+        assert_eq!(Ok(()), parse("<xsd:element name=\"order\" type=\"xsd:anyType\"/>\
+                                  <xsd:attribute name=\"sku\" type=\"xsd:anySimpleType\"/>", ""));
+    }
+
+    #[test]
+    fn malformed_components() {
+        let error = |xsd: &str| parse(xsd, "").unwrap_err().component()
+            .map(|component| (component.code, component.kind, component.name.clone(), component.attribute));
+        let name = |name: &str| Some(name.to_string());
+
+        assert_eq!(Some(("s4s-att-invalid-value", ComponentKind::Element, name("item"), "maxOccurs")),
+                   error("<xsd:complexType name=\"Items\"><xsd:sequence>\
+                          <xsd:element name=\"item\" maxOccurs=\"many\"/></xsd:sequence></xsd:complexType>"));
+        assert_eq!(Some(("p-props-correct.2.1", ComponentKind::Element, name("item"), "minOccurs")),
+                   error("<xsd:complexType name=\"Items\"><xsd:sequence>\
+                          <xsd:element name=\"item\" minOccurs=\"2\" maxOccurs=\"1\"/></xsd:sequence></xsd:complexType>"));
+        assert_eq!(Some(("src-resolve.4", ComponentKind::Element, None, "ref")),
+                   error("<xsd:complexType name=\"Items\"><xsd:sequence>\
+                          <xsd:element ref=\"p:b\"/></xsd:sequence></xsd:complexType>"));
+        assert_eq!(Some(("src-resolve.4", ComponentKind::Element, name("order"), "type")),
+                   error("<xsd:element name=\"order\" type=\"p:T\"/>"));
+        assert_eq!(Some(("s4s-att-invalid-value", ComponentKind::SimpleType, name("Code"), "value")),
+                   error("<xsd:simpleType name=\"Code\"><xsd:restriction base=\"xsd:string\">\
+                          <xsd:maxLength value=\"x\"/></xsd:restriction></xsd:simpleType>"));
+        assert_eq!(Some(("s4s-att-invalid-value", ComponentKind::SimpleType, name("Code"), "value")),
+                   error("<xsd:simpleType name=\"Code\"><xsd:restriction base=\"xsd:string\">\
+                          <xsd:whiteSpace value=\"foo\"/></xsd:restriction></xsd:simpleType>"));
+        assert_eq!(Some(("s4s-att-invalid-value", ComponentKind::SimpleType, name("Code"), "value")),
+                   error("<xsd:simpleType name=\"Code\"><xsd:restriction base=\"xsd:string\">\
+                          <xsd:pattern value=\"[\"/></xsd:restriction></xsd:simpleType>"));
+        assert_eq!(Some(("s4s-att-invalid-value", ComponentKind::ComplexType, name("Price"), "value")),
+                   error("<xsd:complexType name=\"Price\"><xsd:simpleContent><xsd:restriction base=\"Amount\">\
+                          <xsd:pattern value=\"\\p{IsKlingon}\"/></xsd:restriction></xsd:simpleContent>\
+                          </xsd:complexType><xsd:complexType name=\"Amount\"><xsd:simpleContent>\
+                          <xsd:extension base=\"xsd:decimal\"/></xsd:simpleContent></xsd:complexType>"));
+        assert!(parse("<xsd:simpleType name=\"Name\"><xsd:restriction base=\"xsd:string\">\
+                       <xsd:pattern value=\"\\p{IsLatinExtendedAdditional}+\"/></xsd:restriction></xsd:simpleType>",
+                      "").is_ok());
+        assert_eq!(Some(("s4s-att-must-appear", ComponentKind::Element, None, "name")),
+                   error("<xsd:element type=\"xsd:string\"/>"));
+        assert_eq!(Some(("s4s-elt-must-match", ComponentKind::Group, name("items"), "")),
+                   error("<xsd:group name=\"items\"/>"));
+        assert_eq!(Some(("s4s-att-must-appear", ComponentKind::Schema, None, "schemaLocation")),
+                   error("<xsd:include/>"));
+        assert_eq!(Some(("s4s-att-must-appear", ComponentKind::IdentityConstraint, name("skus"), "refer")),
+                   error("<xsd:element name=\"order\"><xsd:keyref name=\"skus\"><xsd:selector xpath=\"item\"/>\
+                          <xsd:field xpath=\"@sku\"/></xsd:keyref></xsd:element>"));
+        assert!(matches!(parse("<xsd:element name=\"order\" type=\"p:T\"/>", ""),
+                         Err(SchemaError::UnresolvedReference(_))));
+    }
+
+    #[test]
+    fn error_positions() {
+        let position = |xsd: &str| {
+            let package = DomParser::parse(xsd).expect("Failed to parse");
+            let document = package.as_document();
+            let error = Schema::from_source(&document, xsd).map(|_| ()).unwrap_err();
+            error.component().and_then(|component| component.position)
+        };
+
+        assert_eq!(Some(Position { line: 3, column: 5 }),
+                   position("<xsd:schema xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\n\
+                             <!-- an order -->\n    <xsd:element name=\"order\" type=\"OrderType\"/>\n\
+                             </xsd:schema>"));
+        // unresolved references point to the type that contains them
+        assert_eq!(Some(Position { line: 2, column: 1 }),
+                   position("<xsd:schema xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\n\
+                             <xsd:complexType name=\"Order\">\n  <xsd:sequence>\n\
+                             <xsd:group ref=\"items\"/></xsd:sequence></xsd:complexType></xsd:schema>"));
+        // duplicates point to the second declaration
+        assert_eq!(Some(Position { line: 3, column: 1 }),
+                   position("<xsd:schema xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\n\
+                             <xsd:notation name=\"gif\" public=\"image/gif\"/>\n\
+                             <xsd:notation name=\"gif\" system=\"gif.exe\"/></xsd:schema>"));
+        // schemas created from a document alone know no positions
+        assert_eq!(None, parse("<xsd:element name=\"order\" type=\"OrderType\"/>", "").unwrap_err()
+            .component().and_then(|component| component.position));
+    }
+
+    #[test]
+    fn circular_definitions() {
+        let cycle = |xsd: &str| match parse(xsd, "") {
+            Err(SchemaError::CircularDefinition(error, names)) =>
+                Some((error.code, names.iter().map(|name| name.local_name.clone()).collect::<Vec<String>>())),
+            _ => None,
+        };
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<String>>();
+
+        assert_eq!(Some(("st-props-correct.2", names(&["A", "B"]))),
+                   cycle("<xsd:simpleType name=\"A\"><xsd:restriction base=\"B\"/></xsd:simpleType>\
+                          <xsd:simpleType name=\"B\"><xsd:list itemType=\"A\"/></xsd:simpleType>"));
+        assert_eq!(Some(("ct-props-correct.3", names(&["Base"]))),
+                   cycle("<xsd:complexType name=\"Base\"><xsd:complexContent>\
+                          <xsd:extension base=\"Base\"/></xsd:complexContent></xsd:complexType>"));
+        assert_eq!(Some(("mg-props-correct.2", names(&["a", "b", "c"]))),
+                   cycle("<xsd:group name=\"a\"><xsd:sequence><xsd:group ref=\"b\"/></xsd:sequence></xsd:group>\
+                          <xsd:group name=\"b\"><xsd:choice><xsd:sequence><xsd:group ref=\"c\"/></xsd:sequence>\
+                          </xsd:choice></xsd:group>\
+                          <xsd:group name=\"c\"><xsd:sequence><xsd:group ref=\"a\"/></xsd:sequence></xsd:group>"));
+        assert_eq!(Some(("src-attribute_group.3", names(&["audit"]))),
+                   cycle("<xsd:attributeGroup name=\"audit\"><xsd:attributeGroup ref=\"audit\"/></xsd:attributeGroup>"));
+        assert_eq!(Err(SchemaError::CircularDefinition(ComponentError {
+                       code: "e-props-correct.6",
+                       kind: ComponentKind::Element,
+                       name: Some("a".to_string()),
+                       attribute: "substitutionGroup",
+                       position: None,
+                   }, vec![ComponentName::new(None, "a"), ComponentName::new(None, "b")].into_boxed_slice())),
+                   parse("<xsd:element name=\"a\" substitutionGroup=\"b\"/>\
+                          <xsd:element name=\"b\" substitutionGroup=\"a\"/>", ""));
+
+        // elements may contain themselves through their types and groups through element types
+        assert_eq!(Ok(()), parse("<xsd:complexType name=\"Part\"><xsd:sequence>\
+                                  <xsd:element name=\"part\" type=\"Part\" minOccurs=\"0\"/>\
+                                  <xsd:group ref=\"parts\"/></xsd:sequence></xsd:complexType>\
+                                  <xsd:group name=\"parts\"><xsd:sequence>\
+                                  <xsd:element name=\"subpart\" type=\"Part\" minOccurs=\"0\"/>\
+                                  </xsd:sequence></xsd:group>", ""));
+    }
+
+    #[test]
+    fn duplicate_components() {
+        let duplicate = |kind, name: &str| Err(SchemaError::DuplicateComponent(ComponentError {
+            code: "sch-props-correct.2",
+            kind,
+            name: Some(name.to_string()),
+            attribute: "name",
+            position: None,
+        }));
+        let sku = "<xsd:simpleType name=\"SKU\"><xsd:restriction base=\"xsd:string\"/></xsd:simpleType>";
+
+        // simple and complex types share a symbol space
+        assert_eq!(duplicate(ComponentKind::SimpleType, "SKU"), parse(&format!("{}{}", sku, sku), ""));
+        assert_eq!(duplicate(ComponentKind::ComplexType, "SKU"),
+                   parse(&format!("{}<xsd:complexType name=\"SKU\"/>", sku), ""));
+        assert_eq!(duplicate(ComponentKind::AttributeGroup, "audit"),
+                   parse("<xsd:attributeGroup name=\"audit\"/><xsd:attributeGroup name=\"audit\"/>", ""));
+        assert_eq!(duplicate(ComponentKind::Notation, "gif"),
+                   parse("<xsd:notation name=\"gif\" public=\"image/gif\"/>\
+                          <xsd:notation name=\"gif\" public=\"image/gif\"/>", ""));
+        // identity constraints of local declarations are in the same symbol space as top level ones
+        let key = |category: &str, name: &str| {
+            let refer = if category == "keyref" { " refer=\"skus\"" } else { "" };
+            format!("<xsd:{0} name=\"{1}\"{2}><xsd:selector xpath=\"item\"/><xsd:field xpath=\"@sku\"/></xsd:{0}>",
+                    category, name, refer)
+        };
+        assert_eq!(duplicate(ComponentKind::IdentityConstraint, "skus"),
+                   parse(&format!("<xsd:element name=\"order\">{}{}</xsd:element>",
+                                  key("key", "skus"), key("unique", "skus")), ""));
+        assert_eq!(duplicate(ComponentKind::IdentityConstraint, "skus"),
+                   parse(&format!("<xsd:element name=\"order\">{}</xsd:element>\
+                                   <xsd:element name=\"stock\"><xsd:complexType><xsd:sequence>\
+                                   <xsd:element name=\"item\">{}</xsd:element>\
+                                   </xsd:sequence></xsd:complexType></xsd:element>",
+                                  key("key", "skus"), key("keyref", "skus")), ""));
+
+        // the same name in different symbol spaces
+        assert_eq!(Ok(()), parse(&format!("{}<xsd:element name=\"SKU\" type=\"SKU\"/>\
+                                           <xsd:attribute name=\"SKU\" type=\"SKU\"/>\
+                                           <xsd:group name=\"SKU\"><xsd:sequence/></xsd:group>\
+                                           <xsd:attributeGroup name=\"SKU\"/>\
+                                           <xsd:notation name=\"SKU\" public=\"text/plain\"/>\
+                                           <xsd:element name=\"order\">{}</xsd:element>", sku,
+                                          key("unique", "SKU")), ""));
+    }
+
+    #[test]
+    fn consistent_elements() {
+        let inconsistent = |name: &str| Err(SchemaError::InconsistentElements(ComponentError {
+            code: "cos-element-consistent",
+            kind: ComponentKind::Element,
+            name: Some(name.to_string()),
+            attribute: "type",
+            position: None,
+        }));
+
+        assert_eq!(inconsistent("quantity"),
+                   parse("<xsd:complexType name=\"Item\"><xsd:choice>\
+                          <xsd:element name=\"quantity\" type=\"xsd:int\"/>\
+                          <xsd:sequence><xsd:element name=\"quantity\" type=\"xsd:string\"/></xsd:sequence>\
+                          </xsd:choice></xsd:complexType>", ""));
+        // anonymous types are different types even if they look the same
+        assert_eq!(inconsistent("note"),
+                   parse("<xsd:complexType name=\"Item\"><xsd:sequence>\
+                          <xsd:element name=\"note\"><xsd:complexType/></xsd:element>\
+                          <xsd:element name=\"note\"><xsd:complexType/></xsd:element>\
+                          </xsd:sequence></xsd:complexType>", ""));
+        // the particles of referenced groups and of the base of an extension are siblings too
+        assert_eq!(inconsistent("code"),
+                   parse("<xsd:group name=\"codes\"><xsd:sequence>\
+                          <xsd:element name=\"code\" type=\"xsd:string\"/></xsd:sequence></xsd:group>\
+                          <xsd:complexType name=\"Base\"><xsd:sequence><xsd:group ref=\"codes\"/>\
+                          </xsd:sequence></xsd:complexType>\
+                          <xsd:complexType name=\"Derived\"><xsd:complexContent><xsd:extension base=\"Base\">\
+                          <xsd:sequence><xsd:element name=\"code\" type=\"xsd:int\"/></xsd:sequence>\
+                          </xsd:extension></xsd:complexContent></xsd:complexType>", ""));
+
+        assert_eq!(Ok(()), parse("<xsd:element name=\"quantity\" type=\"xsd:int\"/>\
+                                  <xsd:complexType name=\"Item\"><xsd:sequence>\
+                                  <xsd:element ref=\"quantity\"/>\
+                                  <xsd:element name=\"quantity\" type=\"xsd:int\"/>\
+                                  <xsd:element name=\"price\" type=\"xsd:decimal\"/>\
+                                  </xsd:sequence></xsd:complexType>\
+                                  <xsd:complexType name=\"Other\"><xsd:sequence>\
+                                  <xsd:element name=\"price\" type=\"xsd:string\"/>\
+                                  </xsd:sequence></xsd:complexType>", ""));
+    }
+}
@@ -1,29 +1,209 @@
-use sxd_document::dom::{Element as DomElement};
+use std::collections::HashSet;
 
-use parser::is_of_element;
+use sxd_document::dom::{
+    Element as DomElement,
+    Attribute as DomAttribute,
+};
 
+use parser::{
+    is_of_element,
+    parse_children,
+    parse_id,
+    parse_additional_attributes,
+    parse_boolean_attribute,
+    parse_derivation_set,
+    parse_form,
+    parse_qname_attribute,
+    parse_value_constraint,
+    required_attribute,
+    ParseError,
+    QName,
+    ValueConstraint,
+};
+use parser::types::{Id, AnyType, parse_element_type};
+use parser::groups::{MaxOccurs, parse_occurs};
+use parser::annotations::{Annotation, parse_annotation};
+use parser::schema::FormChoice;
 
+/// Derivations and substitutions which may not be used in place of an element's declared type
+#[derive(Eq, PartialEq, Debug, Hash, Copy, Clone)]
+pub enum ElementBlock {
+    Extension,
+    Restriction,
+    Substitution,
+}
+
+/// Derivations which may not be used by the types of substitution group members
+#[derive(Eq, PartialEq, Debug, Hash, Copy, Clone)]
+pub enum ElementFinal {
+    Extension,
+    Restriction,
+}
+
+/// A top level or local element declaration
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-element
+#[derive(Eq, PartialEq, Debug)]
 pub struct Element<'a> {
+    pub id: Option<Id<'a>>,
+    pub name: &'a str,
+    // taken from either type or a nested simpleType or complexType, defaults to the type of the
+    // first substitution group head or anyType
+    pub element_type: AnyType<'a>,
+    // occurrences are only allowed on local declarations and default to 1
+    pub min_occurs: usize,
+    pub max_occurs: MaxOccurs,
+    // only applies to local declarations, taken from the form attribute or the elementFormDefault
+    // of the schema
+    pub form: FormChoice,
+    // defaults to false
+    pub is_nillable: bool,
+    pub value_constraint: Option<ValueConstraint<'a>>,
+    // defaults to false, abstract elements may only appear through substitution
+    pub is_abstract: bool,
+    pub block_modes: HashSet<ElementBlock>,
+    pub final_modes: HashSet<ElementFinal>,
+    // the heads of the substitution groups of a top level declaration, XSD 1.1 allows more than one
+    pub substitution_groups: Vec<QName<'a>>,
+    pub identity_constraints: Vec<IdentityConstraint<'a>>,
+    pub annotation: Option<Annotation<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
+}
+
+#[derive(Eq, PartialEq, Debug, Hash, Copy, Clone)]
+pub enum IdentityConstraintCategory {
+    Key,
+    Unique,
+    KeyRef,
+}
+
+/// A key, unique or keyref constraint of a top level or local element declaration, the
+/// constraints of all declarations of a namespace share one symbol space. Only their names are
+/// used so far, instances are not checked against them
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-unique
+#[derive(Eq, PartialEq, Debug)]
+pub struct IdentityConstraint<'a> {
+    pub id: Option<Id<'a>>,
     pub name: &'a str,
-    pub element_type: &'a str,
+    pub category: IdentityConstraintCategory,
+    // the key or unique constraint that a keyref refers to
+    pub refer: Option<QName<'a>>,
+    pub annotation: Option<Annotation<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
 }
 
-pub fn parse_elements<'a>(elements: &Vec<DomElement<'a>>) -> Vec<Element<'a>> {
+/// A reference to a top level element declaration inside of a content model
+#[derive(Eq, PartialEq, Debug)]
+pub struct ElementReference<'a> {
+    pub id: Option<Id<'a>>,
+    pub reference: QName<'a>,
+    pub min_occurs: usize,
+    pub max_occurs: MaxOccurs,
+    pub annotation: Option<Annotation<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+}
+
+pub fn parse_elements<'a>(elements: &[DomElement<'a>]) -> Result<Vec<Element<'a>>, ParseError<'a>> {
     elements.iter()
-        .filter(|&element| is_element(&element))
+        .filter(|&element| is_element(element))
         .map(|&element| parse_element(element))
         .collect()
 }
 
 fn is_element(element: &DomElement) -> bool {
-    is_of_element(&element, "element")
+    is_of_element(element, "element")
+}
+
+fn parse_block_modes(element: &DomElement) -> HashSet<ElementBlock> {
+    parse_derivation_set(element, "block", "blockDefault",
+                         &[ElementBlock::Extension, ElementBlock::Restriction, ElementBlock::Substitution],
+                         |method| match method {
+                             "extension" => Some(ElementBlock::Extension),
+                             "restriction" => Some(ElementBlock::Restriction),
+                             "substitution" => Some(ElementBlock::Substitution),
+                             _ => None,
+                         })
+}
+
+fn identity_constraint_category(element: &DomElement) -> Option<IdentityConstraintCategory> {
+    if is_of_element(element, "key") {
+        Some(IdentityConstraintCategory::Key)
+    } else if is_of_element(element, "unique") {
+        Some(IdentityConstraintCategory::Unique)
+    } else if is_of_element(element, "keyref") {
+        Some(IdentityConstraintCategory::KeyRef)
+    } else {
+        None
+    }
 }
 
-pub fn parse_element<'a>(element: DomElement<'a>) -> Element<'a> {
-    let name = element.attribute("name").expect("Element defined without name");
-    let element_type = element.attribute("type").expect("Element defined without type");
-    return Element {
-        name: name.value(),
-        element_type: element_type.value(),
+fn parse_identity_constraint(element: DomElement, category: IdentityConstraintCategory)
+                             -> Result<IdentityConstraint, ParseError> {
+    let refer = match category {
+        IdentityConstraintCategory::KeyRef => {
+            let refer = required_attribute(&element, "refer")?;
+            Some(parse_qname_attribute(&element, refer, "refer")?)
+        }
+        _ => None,
     };
+    Ok(IdentityConstraint {
+        id: parse_id(&element),
+        name: required_attribute(&element, "name")?,
+        category,
+        refer,
+        annotation: parse_annotation(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        source: element,
+    })
+}
+
+pub fn parse_element(element: DomElement) -> Result<Element, ParseError> {
+    let name = required_attribute(&element, "name")?;
+    let (min_occurs, max_occurs) = parse_occurs(&element)?;
+    Ok(Element {
+        id: parse_id(&element),
+        name,
+        element_type: parse_element_type(&element)?,
+        min_occurs,
+        max_occurs,
+        form: parse_form(&element, "form", "elementFormDefault"),
+        is_nillable: parse_boolean_attribute(&element, "nillable", false),
+        value_constraint: parse_value_constraint(&element),
+        is_abstract: parse_boolean_attribute(&element, "abstract", false),
+        block_modes: parse_block_modes(&element),
+        final_modes: parse_derivation_set(&element, "final", "finalDefault",
+                                          &[ElementFinal::Extension, ElementFinal::Restriction],
+                                          |method| match method {
+                                              "extension" => Some(ElementFinal::Extension),
+                                              "restriction" => Some(ElementFinal::Restriction),
+                                              _ => None,
+                                          }),
+        substitution_groups: element.attribute_value("substitutionGroup")
+            .map(|heads| heads.split_whitespace()
+                .map(|head| parse_qname_attribute(&element, head, "substitutionGroup"))
+                .collect())
+            .unwrap_or(Ok(Vec::new()))?,
+        identity_constraints: parse_children(&element, |_| true, |child| identity_constraint_category(&child)
+                .map(|category| parse_identity_constraint(child, category)))
+            .into_iter()
+            .flatten()
+            .collect::<Result<Vec<IdentityConstraint>, ParseError>>()?,
+        annotation: parse_annotation(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        source: element,
+    })
+}
+
+pub fn parse_element_reference(element: DomElement) -> Result<ElementReference, ParseError> {
+    let reference = required_attribute(&element, "ref")?;
+    let (min_occurs, max_occurs) = parse_occurs(&element)?;
+    Ok(ElementReference {
+        id: parse_id(&element),
+        reference: parse_qname_attribute(&element, reference, "ref")?,
+        min_occurs,
+        max_occurs,
+        annotation: parse_annotation(&element),
+        additional_attributes: parse_additional_attributes(&element),
+    })
 }
@@ -0,0 +1,184 @@
+use sxd_document::dom::{
+    Element as DomElement,
+    Attribute as DomAttribute,
+};
+
+use parser::{
+    XSD_NS_URI,
+    parse_children,
+    parse_id,
+    parse_additional_attributes,
+    parse_qname_attribute,
+    required_attribute,
+    is_of_element,
+    ParseError,
+    QName,
+};
+use parser::types::Id;
+use parser::annotations::{Annotation, parse_annotation};
+use parser::elements::{Element, ElementReference, parse_element, parse_element_reference};
+use parser::wildcards::{Any, parse_any};
+
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum MaxOccurs {
+    Bounded(usize),
+    Unbounded,
+}
+
+/// A term together with the number of times it may occur
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cParticles
+#[derive(Eq, PartialEq, Debug)]
+pub enum Particle<'a> {
+    Element(Box<Element<'a>>),
+    ElementReference(ElementReference<'a>),
+    Sequence(ModelGroup<'a>),
+    Choice(ModelGroup<'a>),
+    All(ModelGroup<'a>),
+    Group(GroupReference<'a>),
+    Any(Any<'a>),
+}
+
+/// Content of a sequence, choice or all element
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-sequence
+#[derive(Eq, PartialEq, Debug)]
+pub struct ModelGroup<'a> {
+    pub id: Option<Id<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub annotation: Option<Annotation<'a>>,
+    pub min_occurs: usize,
+    pub max_occurs: MaxOccurs,
+    pub particles: Vec<Particle<'a>>,
+}
+
+/// A top level named model group
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-group
+#[derive(Eq, PartialEq, Debug)]
+pub struct Group<'a> {
+    pub id: Option<Id<'a>>,
+    pub name: &'a str,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
+    pub annotation: Option<Annotation<'a>>,
+    // one of sequence, choice or all
+    pub particle: Particle<'a>,
+}
+
+/// A reference to a named model group inside of a content model
+#[derive(Eq, PartialEq, Debug)]
+pub struct GroupReference<'a> {
+    pub id: Option<Id<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub annotation: Option<Annotation<'a>>,
+    pub min_occurs: usize,
+    pub max_occurs: MaxOccurs,
+    pub reference: QName<'a>,
+}
+
+impl<'a> Particle<'a> {
+    pub fn min_occurs(&self) -> usize {
+        match *self {
+            Particle::Element(ref element) => element.min_occurs,
+            Particle::ElementReference(ref element) => element.min_occurs,
+            Particle::Sequence(ref group) | Particle::Choice(ref group) | Particle::All(ref group) =>
+                group.min_occurs,
+            Particle::Group(ref group) => group.min_occurs,
+            Particle::Any(ref any) => any.min_occurs,
+        }
+    }
+
+    pub fn max_occurs(&self) -> MaxOccurs {
+        match *self {
+            Particle::Element(ref element) => element.max_occurs,
+            Particle::ElementReference(ref element) => element.max_occurs,
+            Particle::Sequence(ref group) | Particle::Choice(ref group) | Particle::All(ref group) =>
+                group.max_occurs,
+            Particle::Group(ref group) => group.max_occurs,
+            Particle::Any(ref any) => any.max_occurs,
+        }
+    }
+}
+
+/// Parses minOccurs and maxOccurs which both default to 1, minOccurs may not exceed maxOccurs
+pub fn parse_occurs<'a>(element: &DomElement<'a>) -> Result<(usize, MaxOccurs), ParseError<'a>> {
+    let invalid = |attribute| ParseError::new("s4s-att-invalid-value", attribute, *element);
+    let min_occurs = match element.attribute_value("minOccurs") {
+        Some(value) => value.trim().parse().map_err(|_| invalid("minOccurs"))?,
+        None => 1,
+    };
+    let max_occurs = match element.attribute_value("maxOccurs").map(str::trim) {
+        Some("unbounded") => MaxOccurs::Unbounded,
+        Some(value) => MaxOccurs::Bounded(value.parse().map_err(|_| invalid("maxOccurs"))?),
+        None => MaxOccurs::Bounded(1),
+    };
+    // see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#p-props-correct
+    if matches!(max_occurs, MaxOccurs::Bounded(max_occurs) if min_occurs > max_occurs) {
+        return Err(ParseError::new("p-props-correct.2.1", "minOccurs", *element));
+    }
+    Ok((min_occurs, max_occurs))
+}
+
+fn is_particle(element: &DomElement) -> bool {
+    element.name().namespace_uri() == Some(XSD_NS_URI)
+        && matches!(element.name().local_part(), "element" | "sequence" | "choice" | "all" | "group" | "any")
+}
+
+/// Parses the particle of a complex type, group or model group if there is one
+pub fn parse_particle_child<'a>(element: &DomElement<'a>) -> Result<Option<Particle<'a>>, ParseError<'a>> {
+    parse_children(element, is_particle, parse_particle)
+        .into_iter()
+        .next()
+        .transpose()
+}
+
+pub fn parse_particle<'a>(element: DomElement<'a>) -> Result<Particle<'a>, ParseError<'a>> {
+    Ok(match element.name().local_part() {
+        "element" if element.attribute("ref").is_some() =>
+            Particle::ElementReference(parse_element_reference(element)?),
+        "element" => Particle::Element(Box::new(parse_element(element)?)),
+        "sequence" => Particle::Sequence(parse_model_group(element)?),
+        "choice" => Particle::Choice(parse_model_group(element)?),
+        "all" => Particle::All(parse_model_group(element)?),
+        "group" => Particle::Group(parse_group_reference(element)?),
+        _ => Particle::Any(parse_any(element)?),
+    })
+}
+
+fn parse_model_group<'a>(element: DomElement<'a>) -> Result<ModelGroup<'a>, ParseError<'a>> {
+    let (min_occurs, max_occurs) = parse_occurs(&element)?;
+    Ok(ModelGroup {
+        id: parse_id(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        annotation: parse_annotation(&element),
+        min_occurs,
+        max_occurs,
+        particles: parse_children(&element, is_particle, parse_particle).into_iter().collect::<Result<_, _>>()?,
+    })
+}
+
+fn parse_group_reference<'a>(element: DomElement<'a>) -> Result<GroupReference<'a>, ParseError<'a>> {
+    let (min_occurs, max_occurs) = parse_occurs(&element)?;
+    let reference = required_attribute(&element, "ref")?;
+    Ok(GroupReference {
+        id: parse_id(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        annotation: parse_annotation(&element),
+        min_occurs,
+        max_occurs,
+        reference: parse_qname_attribute(&element, reference, "ref")?,
+    })
+}
+
+pub fn parse_groups<'a>(elements: &[DomElement<'a>]) -> Result<Vec<Group<'a>>, ParseError<'a>> {
+    elements.iter()
+        .filter(|&element| is_of_element(element, "group"))
+        .map(|&element| Ok(Group {
+            id: parse_id(&element),
+            name: required_attribute(&element, "name")?,
+            additional_attributes: parse_additional_attributes(&element),
+            source: element,
+            annotation: parse_annotation(&element),
+            particle: parse_particle_child(&element)?
+                .ok_or(ParseError::new("s4s-elt-must-match", "", element))?,
+        }))
+        .collect()
+}
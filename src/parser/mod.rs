@@ -1,10 +1,15 @@
 pub mod elements;
+pub mod attributes;
+pub mod groups;
+pub mod wildcards;
 pub mod types;
 pub mod versions;
 pub mod annotations;
+pub mod notations;
 pub mod schema;
-
-extern crate sxd_document;
+pub mod constraints;
+pub mod positions;
+pub mod composition;
 
 use sxd_document::dom::{
     Root,
@@ -13,40 +18,218 @@ use sxd_document::dom::{
     ChildOfElement,
 };
 
-use std::iter::Filter;
-use std::slice::Iter;
-use std::iter::FilterMap;
+use components::ComponentName;
+use parser::schema::FormChoice;
 use parser::types::Id;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use parser::annotations::Annotation;
+use parser::positions::{Position, SourceMap};
+use validator::ValidationError;
 
-static XSD_NS_URI: &'static str = "http://www.w3.org/2001/XMLSchema";
+pub static XSD_NS_URI: &str = "http://www.w3.org/2001/XMLSchema";
+pub static XSI_NS_URI: &str = "http://www.w3.org/2001/XMLSchema-instance";
+pub static XML_NS_URI: &str = "http://www.w3.org/XML/1998/namespace";
 
 #[derive(Eq, PartialEq, Debug)]
 pub struct Language<'a> {
     pub iso_code: &'a str,
 }
 
-fn is_of_element<'a>(element: &'a DomElement, element_name: &str) -> bool {
+/// A name whose prefix has been resolved to its namespace, e.g. xsd:string
+/// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#QName
+#[derive(Eq, PartialEq, Debug, Hash, Copy, Clone)]
+pub struct QName<'a> {
+    pub namespace_uri: Option<&'a str>,
+    pub local_name: &'a str,
+}
+
+/// An element of a schema document that can not be parsed because a required attribute or child
+/// is missing or an attribute value is not valid. It is described as a SchemaError once the
+/// position of the element is known
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct ParseError<'a> {
+    // the code of the violated constraint, e.g. s4s-att-must-appear
+    pub code: &'static str,
+    // empty if a child element is missing
+    pub attribute: &'static str,
+    pub source: DomElement<'a>,
+}
+
+// the code of a QName whose prefix is not declared
+static UNDECLARED_PREFIX: &str = "src-resolve.4";
+
+/// The kind of component that a schema element is part of, include and redefine elements are
+/// described as part of the schema
+fn component_kind(element: &DomElement) -> Option<ComponentKind> {
+    if element.name().namespace_uri() != Some(XSD_NS_URI) {
+        return None;
+    }
+    match element.name().local_part() {
+        "simpleType" => Some(ComponentKind::SimpleType),
+        "complexType" => Some(ComponentKind::ComplexType),
+        "element" => Some(ComponentKind::Element),
+        "attribute" => Some(ComponentKind::Attribute),
+        "group" => Some(ComponentKind::Group),
+        "attributeGroup" => Some(ComponentKind::AttributeGroup),
+        "notation" => Some(ComponentKind::Notation),
+        "key" | "unique" | "keyref" => Some(ComponentKind::IdentityConstraint),
+        "schema" | "include" | "import" | "redefine" => Some(ComponentKind::Schema),
+        _ => None,
+    }
+}
+
+impl<'a> ParseError<'a> {
+    pub fn new(code: &'static str, attribute: &'static str, source: DomElement<'a>) -> ParseError<'a> {
+        ParseError { code, attribute, source }
+    }
+
+    /// Describes the error with the innermost component that contains the offending element, the
+    /// position is the one of the offending element itself
+    pub fn into_schema_error(self, source_map: Option<&SourceMap>) -> SchemaError {
+        let mut current = Some(self.source);
+        let mut component = (ComponentKind::Schema, None);
+        while let Some(element) = current {
+            if let Some(kind) = component_kind(&element) {
+                component = (kind, element.attribute_value("name"));
+                break;
+            }
+            current = element.parent().and_then(|parent| parent.element());
+        }
+        let error = ComponentError {
+            code: self.code,
+            kind: component.0,
+            name: component.1.map(str::to_string),
+            attribute: self.attribute,
+            position: source_map.and_then(|source_map| source_map.position(self.source)),
+        };
+        if self.code == UNDECLARED_PREFIX {
+            SchemaError::UnresolvedReference(error)
+        } else {
+            SchemaError::InvalidRepresentation(error)
+        }
+    }
+}
+
+/// The value of an attribute that the schema for schemas requires
+pub fn required_attribute<'a>(element: &DomElement<'a>, name: &'static str) -> Result<&'a str, ParseError<'a>> {
+    element.attribute_value(name).ok_or(ParseError::new("s4s-att-must-appear", name, *element))
+}
+
+/// Resolves the prefix of a QName attribute value like parse_qname, an undeclared prefix is an
+/// error of the attribute
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#src-resolve
+pub fn parse_qname_attribute<'a>(element: &DomElement<'a>, value: &'a str, attribute: &'static str)
+                                 -> Result<QName<'a>, ParseError<'a>> {
+    parse_qname(element, value).ok_or(ParseError::new(UNDECLARED_PREFIX, attribute, *element))
+}
+
+/// Resolves the prefix of a QName attribute value using the namespaces in scope of the element,
+/// unprefixed names are in the default namespace. Returns None if the prefix is not declared
+pub fn parse_qname<'a>(element: &DomElement<'a>, value: &'a str) -> Option<QName<'a>> {
+    let value = value.trim();
+    match value.find(':') {
+        Some(index) => {
+            element.namespace_uri_for_prefix(&value[..index])
+                .map(|namespace_uri| QName {
+                    namespace_uri: Some(namespace_uri),
+                    local_name: &value[index + 1..],
+                })
+        }
+        None => Some(QName {
+            namespace_uri: element.recursive_default_namespace_uri(),
+            local_name: value,
+        }),
+    }
+}
+
+fn is_of_element(element: &DomElement, element_name: &str) -> bool {
     let name = element.name();
-    return name.namespace_uri() == Some(XSD_NS_URI) && name.local_part() == element_name;
+    name.namespace_uri() == Some(XSD_NS_URI) && name.local_part() == element_name
 }
 
 pub fn is_schema(element: &DomElement) -> bool {
-    is_of_element(&element, "schema")
+    is_of_element(element, "schema")
 }
 
 fn extract_element<'a>(element: &ChildOfElement<'a>) -> Option<DomElement<'a>> {
-    match element {
-        &ChildOfElement::Element(e) => Some(e),
+    match *element {
+        ChildOfElement::Element(e) => Some(e),
         _ => None
     }
 }
 
+/// Finds the schema element which contains the given element
+pub fn find_schema_element<'a>(element: &DomElement<'a>) -> Option<DomElement<'a>> {
+    let mut current = Some(*element);
+    while let Some(element) = current {
+        if is_schema(&element) {
+            return Some(element);
+        }
+        current = element.parent().and_then(|parent| parent.element());
+    }
+    None
+}
+
+/// Finds the targetNamespace of the schema element which contains the given element
+pub fn find_target_namespace<'a>(element: &DomElement<'a>) -> Option<&'a str> {
+    find_schema_element(element)
+        .and_then(|schema| schema.attribute_value("targetNamespace"))
+}
+
+/// The default or fixed value of an element or attribute declaration, both are supplied if the
+/// attribute is absent or the element is empty and a fixed value has to match the actual one
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#a-value_constraint
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum ValueConstraint<'a> {
+    Default(&'a str),
+    Fixed(&'a str),
+}
+
+impl<'a> ValueConstraint<'a> {
+    pub fn value(&self) -> &'a str {
+        match *self {
+            ValueConstraint::Default(value) | ValueConstraint::Fixed(value) => value,
+        }
+    }
+}
+
+pub fn parse_value_constraint<'a>(element: &DomElement<'a>) -> Option<ValueConstraint<'a>> {
+    element.attribute_value("fixed").map(ValueConstraint::Fixed)
+        .or_else(|| element.attribute_value("default").map(ValueConstraint::Default))
+}
+
+/// Parses a whitespace separated list of derivation methods like the final and block attributes,
+/// #all selects all of the given methods. Falls back to the given attribute of the schema element
+/// if the element does not have the attribute, methods that do not apply are ignored
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#ct-final
+pub fn parse_derivation_set<T, F>(element: &DomElement, name: &str, default_name: &str, all: &[T],
+                                  parse_method: F) -> HashSet<T>
+    where T: Eq + Hash + Copy, F: Fn(&str) -> Option<T> {
+    let value = element.attribute_value(name)
+        .or_else(|| find_schema_element(element).and_then(|schema| schema.attribute_value(default_name)));
+    match value {
+        Some(value) if value.trim() == "#all" => all.iter().cloned().collect(),
+        Some(value) => value.split_whitespace().filter_map(parse_method).collect(),
+        None => HashSet::new(),
+    }
+}
+
+/// Parses the form of a local declaration, which defaults to the elementFormDefault or
+/// attributeFormDefault of the schema
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-element
+pub fn parse_form(element: &DomElement, name: &str, default_name: &str) -> FormChoice {
+    let value = element.attribute_value(name)
+        .or_else(|| find_schema_element(element).and_then(|schema| schema.attribute_value(default_name)));
+    match value.map(str::trim) {
+        Some("qualified") => FormChoice::Qualified,
+        _ => FormChoice::Unqualified,
+    }
+}
+
 pub fn parse_id<'a>(element: &DomElement<'a>) -> Option<Id<'a>> {
     element.attribute("id")
-        .map(|attr| Id { id: &attr.value() })
+        .map(|attr| Id { id: attr.value() })
 }
 
 pub fn parse_additional_attributes<'a>(element: &DomElement<'a>) -> Vec<DomAttribute<'a>> {
@@ -60,16 +243,16 @@ pub fn parse_additional_attributes<'a>(element: &DomElement<'a>) -> Vec<DomAttri
         .collect()
 }
 
-pub fn parse_boolean_attribute<'a>(element: &DomElement<'a>, name: &str, default: bool) -> bool {
+pub fn parse_boolean_attribute(element: &DomElement, name: &str, default: bool) -> bool {
     element.attribute(name)
-        .map(|attr| attr.value() == "true")
+        .map(|attr| matches!(attr.value().trim(), "true" | "1"))
         .unwrap_or(default)
 }
 
-pub fn find_schema_children<'a>(root: Root<'a>) -> Vec<DomElement<'a>> {
+pub fn find_schema_children(root: Root) -> Vec<DomElement> {
     root.children().iter()
         .filter_map(|&child| child.element())
-        .filter(|&element| is_schema(&element))
+        .filter(|element| is_schema(element))
         .flat_map(|schema_element| schema_element.children().into_iter())
         .filter_map(|child| child.element())
         .collect()
@@ -104,40 +287,129 @@ pub enum SchemaElement {
 }
 
 pub struct SchemaRoot<'a> {
-    annotations: Vec<Annotation<'a>>,
+    pub annotations: Vec<Annotation<'a>>,
+}
+
+/// The kind of a schema component by the element of its XML representation
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ComponentKind {
+    SimpleType,
+    ComplexType,
+    Element,
+    Attribute,
+    Group,
+    AttributeGroup,
+    Notation,
+    // key, unique and keyref
+    IdentityConstraint,
+    // the schema element and its include, import and redefine children
+    Schema,
+}
+
+/// A schema component that violates a constraint of the spec
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#outcome-cos
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ComponentError {
+    // the W3C constraint code, e.g. cos-ct-extends.1.1
+    pub code: &'static str,
+    pub kind: ComponentKind,
+    // None for anonymous types
+    pub name: Option<String>,
+    // the attribute of the component that causes the violation, e.g. base
+    pub attribute: &'static str,
+    // the start tag of the component, of the component that contains the reference for an
+    // unresolved reference or of the element that can not be parsed. Only known for schemas created by Schema::from_source
+    pub position: Option<Position>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum SchemaError {
     UnsupportedSchemaVersion,
+    // the text of a schema document is not well-formed XML
+    NotWellFormed,
     NoSchemaRootFound,
+    // the schema document is not valid against the schema for schemas
+    InvalidSchema(ValidationError),
+    // a schema element lacks a required attribute or child or has an attribute value that is not
+    // valid for its type, e.g. maxOccurs="many", or a minOccurs larger than its maxOccurs
+    InvalidRepresentation(ComponentError),
+    // a type, element, attribute or group in the target namespace is referenced but not defined
+    UnresolvedReference(ComponentError),
+    // a type is derived from a base type whose final attribute forbids the derivation
+    DerivedFromFinal(ComponentError),
+    // the type of a substitution group member is not derived from the type of its head or the
+    // derivation is excluded by the final attribute of the head
+    InvalidSubstitutionGroup(ComponentError),
+    // NOTATION is used without restricting it by an enumeration
+    NotationWithoutEnumeration(ComponentError),
+    // a default or fixed value is not valid for the type of its declaration
+    InvalidValueConstraint(ComponentError),
+    // two top level components of the same symbol space have the same name
+    DuplicateComponent(ComponentError),
+    // a type or group of a redefine is not derived from the component that it redefines
+    InvalidRedefinition(ComponentError),
+    // a type is derived from itself, a group contains itself or an element is a member of its
+    // own substitution group, the names are those of the components of the cycle in order
+    CircularDefinition(ComponentError, Box<[ComponentName]>),
+    // two element particles of the same content model have the same name but different types
+    InconsistentElements(ComponentError),
+    // the resolver found no schema document at the location
+    UnresolvedLocation(String),
 }
 
+impl SchemaError {
+    /// The W3C code of the violated constraint, errors that do not violate a constraint of the
+    /// spec have none
+    pub fn code(&self) -> Option<&'static str> {
+        match *self {
+            SchemaError::UnsupportedSchemaVersion | SchemaError::NotWellFormed | SchemaError::NoSchemaRootFound |
+            SchemaError::UnresolvedLocation(_) => None,
+            SchemaError::InvalidSchema(ref error) => Some(error.code()),
+            _ => self.component().map(|component| component.code),
+        }
+    }
 
-fn find_schema_group<'a>(element: &DomElement<'a>) -> Option<SchemaElement> {
-    if is_schema(&element) {
+    /// The component that violates a constraint
+    pub fn component(&self) -> Option<&ComponentError> {
+        match *self {
+            SchemaError::UnsupportedSchemaVersion | SchemaError::NotWellFormed | SchemaError::NoSchemaRootFound |
+            SchemaError::InvalidSchema(_) | SchemaError::UnresolvedLocation(_) => None,
+            SchemaError::UnresolvedReference(ref component) |
+            SchemaError::InvalidRepresentation(ref component) |
+            SchemaError::DerivedFromFinal(ref component) |
+            SchemaError::InvalidSubstitutionGroup(ref component) |
+            SchemaError::NotationWithoutEnumeration(ref component) |
+            SchemaError::InvalidValueConstraint(ref component) |
+            SchemaError::DuplicateComponent(ref component) |
+            SchemaError::InvalidRedefinition(ref component) |
+            SchemaError::InconsistentElements(ref component) |
+            SchemaError::CircularDefinition(ref component, _) => Some(component),
+        }
+    }
+}
+
+
+pub fn find_schema_group(element: &DomElement) -> Option<SchemaElement> {
+    if is_schema(element) {
         Some(SchemaElement::Schema)
     } else {
         None
     }
 }
 
-pub fn parse_schema<'a>(root: Root<'a>) -> Result<SchemaRoot<'a>, SchemaError> {
-    let schema_elem = find_root_schema(root)
+pub fn parse_schema(root: Root) -> Result<SchemaRoot, SchemaError> {
+    find_root_schema(root)
         .ok_or(SchemaError::NoSchemaRootFound)?;
 
-    println!("{:?}", schema_elem);
-
     Ok(SchemaRoot {
         annotations: vec![]
     })
 }
 
-pub fn find_root_schema<'a>(root: Root<'a>) -> Option<DomElement<'a>> {
+pub fn find_root_schema(root: Root) -> Option<DomElement> {
     root.children().into_iter()
         .filter_map(|child| child.element())
-        .filter(|child| is_schema(&child))
-        .next()
+        .find(|child| is_schema(child))
 }
 
 pub fn group_root_children<'a, K, G>(element: Root<'a>, groups: G) -> HashMap<K, Vec<DomElement<'a>>>
@@ -146,11 +418,11 @@ pub fn group_root_children<'a, K, G>(element: Root<'a>, groups: G) -> HashMap<K,
     let mut grouped_elements = HashMap::new();
     for elem in element.children().into_iter().filter_map(|child| child.element()) {
         if let Some(group) = groups(&elem) {
-            let results = grouped_elements.entry(group).or_insert(Vec::new());
+            let results = grouped_elements.entry(group).or_insert_with(Vec::new);
             results.push(elem);
         };
     }
-    return grouped_elements;
+    grouped_elements
 }
 
 pub fn group_children<'a, K, G>(element: DomElement<'a>, groups: G) -> HashMap<K, Vec<DomElement<'a>>>
@@ -159,11 +431,11 @@ pub fn group_children<'a, K, G>(element: DomElement<'a>, groups: G) -> HashMap<K
     let mut grouped_elements = HashMap::new();
     for elem in element.children().into_iter().filter_map(|child| child.element()) {
         if let Some(group) = groups(&elem) {
-            let results = grouped_elements.entry(group).or_insert(Vec::new());
+            let results = grouped_elements.entry(group).or_insert_with(Vec::new);
             results.push(elem);
         };
     }
-    return grouped_elements;
+    grouped_elements
 }
 
 #[cfg(test)]
@@ -180,6 +452,7 @@ mod tests {
     use parser::types::SimpleTypeContent;
     use parser::types::Restriction;
     use parser::types::AnySimpleType;
+    use parser::types::AnyType;
     use parser::types::BuiltIn;
     use parser::types::RestrictionRule;
     use parser::types::Pattern;
@@ -188,14 +461,14 @@ mod tests {
     #[test]
     fn empty() {
         let xml = include_str!("../../tests/parser/mod/empty.xsd");
-        let package = DomParser::parse(&xml);
-        assert_eq!(true, package.is_err());
+        let package = DomParser::parse(xml);
+        assert!(package.is_err());
     }
 
     #[test]
     fn wrong_root() {
         let xml = include_str!("../../tests/parser/mod/wrong-root.xsd");
-        let package = DomParser::parse(&xml).expect("Failed to parse");
+        let package = DomParser::parse(xml).expect("Failed to parse");
         let document = package.as_document();
         let schema = Schema::from_document(&document);
 
@@ -205,25 +478,33 @@ mod tests {
     #[test]
     fn parse() {
         let xml = include_str!("../../tests/parser/mod/purchase.xsd");
-        let package = DomParser::parse(&xml).expect("Failed to parse");
+        let package = DomParser::parse(xml).expect("Failed to parse");
         let document = package.as_document();
         let schema = Schema::from_document(&document).expect("Failed to parse schema");
 
         assert_eq!(SchemaVersion::Xsd10, schema.version);
         assert_eq!(2, schema.elements.len());
 
-        let order = schema.elements.get(0).unwrap();
-        assert_eq!("PurchaseOrderType", order.element_type);
+        let order = schema.elements.first().unwrap();
+        assert_eq!(AnyType::Reference(QName { namespace_uri: None, local_name: "PurchaseOrderType" }),
+                   order.element_type);
         assert_eq!("purchaseOrder", order.name);
 
         let order = schema.elements.get(1).unwrap();
-        assert_eq!("xsd:string", order.element_type);
+        assert_eq!(AnyType::AnySimpleType(AnySimpleType::BuiltIn(BuiltIn::String)), order.element_type);
         assert_eq!("comment", order.name);
 
         let types = schema.types;
         assert_eq!(4, types.len());
 
         let sku = types.get(3).unwrap();
+        fn child_elements<'d>(element: &DomElement<'d>) -> Vec<DomElement<'d>> {
+            element.children().into_iter().filter_map(|child| child.element()).collect()
+        }
+        let sku_source = child_elements(&schema.source).into_iter()
+            .find(|element| element.attribute_value("name") == Some("SKU"))
+            .unwrap();
+        let pattern_source = child_elements(&child_elements(&sku_source)[0])[0];
 
         /*
         <xsd:simpleType name="SKU">
@@ -232,8 +513,8 @@ mod tests {
             </xsd:restriction>
         </xsd:simpleType>
         */
-        let expected = TopLevelType::SimpleType(SimpleType {
-            name: "SKU",
+        let expected = TopLevelType::SimpleType(Box::new(SimpleType {
+            name: Some("SKU"),
             annotation: None,
             final_modes: HashSet::new(),
             additional_attributes: vec![],
@@ -248,11 +529,13 @@ mod tests {
                         additional_attributes: Vec::new(),
                         value: "\\d{3}-[A-Z]{2}",
                         annotation: None,
+                        source: pattern_source,
                     })
                 ],
             })),
             id: None,
-        });
+            source: sku_source,
+        }));
         assert_eq!(expected, *sku);
     }
 }
@@ -0,0 +1,43 @@
+use sxd_document::dom::{
+    Element as DomElement,
+    Attribute as DomAttribute,
+};
+
+use parser::{
+    parse_id,
+    parse_additional_attributes,
+    is_of_element,
+    required_attribute,
+    ParseError,
+};
+use parser::types::Id;
+use parser::annotations::{Annotation, parse_annotation};
+
+/// A top level notation declaration which NOTATION values can refer to
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-notation
+#[derive(Eq, PartialEq, Debug)]
+pub struct Notation<'a> {
+    pub id: Option<Id<'a>>,
+    pub name: &'a str,
+    // at least one of public and system is required
+    pub public: Option<&'a str>,
+    pub system: Option<&'a str>,
+    pub annotation: Option<Annotation<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
+}
+
+pub fn parse_notations<'a>(elements: &[DomElement<'a>]) -> Result<Vec<Notation<'a>>, ParseError<'a>> {
+    elements.iter()
+        .filter(|&element| is_of_element(element, "notation"))
+        .map(|&element| Ok(Notation {
+            id: parse_id(&element),
+            name: required_attribute(&element, "name")?,
+            public: element.attribute_value("public"),
+            system: element.attribute_value("system"),
+            annotation: parse_annotation(&element),
+            additional_attributes: parse_additional_attributes(&element),
+            source: element,
+        }))
+        .collect()
+}
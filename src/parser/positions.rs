@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use sxd_document::dom::{Document, Element as DomElement};
+
+/// A line and column in the text of a schema document, both start at 1
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// The positions of the start tags of all elements of a document. sxd-document does not keep
+/// positions, so they are recovered by scanning the text that was parsed
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct SourceMap<'d> {
+    positions: HashMap<DomElement<'d>, Position>,
+}
+
+/// The byte offset right after the first occurrence of the delimiter
+fn skip_past(source: &str, offset: usize, delimiter: &str) -> usize {
+    source[offset..].find(delimiter)
+        .map(|index| offset + index + delimiter.len())
+        .unwrap_or(source.len())
+}
+
+/// The byte offset right after the closing > of a tag or declaration, quoted values and the
+/// internal subset of a DOCTYPE may contain >
+fn skip_tag(source: &str, offset: usize) -> usize {
+    let mut quote = None;
+    let mut depth = 0;
+    for (index, character) in source[offset..].char_indices() {
+        match (quote, character) {
+            (Some(open), _) if character == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(character),
+            (None, '[') => depth += 1,
+            (None, ']') => depth -= 1,
+            (None, '>') if depth <= 0 => return offset + index + 1,
+            _ => {}
+        }
+    }
+    source.len()
+}
+
+/// The positions of the start tags in the text in document order
+fn start_tags(source: &str) -> Vec<Position> {
+    let mut offsets = Vec::new();
+    let mut offset = 0;
+    while let Some(index) = source[offset..].find('<') {
+        let start = offset + index;
+        let rest = &source[start..];
+        offset = if rest.starts_with("<!--") {
+            skip_past(source, start, "-->")
+        } else if rest.starts_with("<![CDATA[") {
+            skip_past(source, start, "]]>")
+        } else if rest.starts_with("<?") {
+            skip_past(source, start, "?>")
+        } else if rest.starts_with("<!") || rest.starts_with("</") {
+            skip_tag(source, start)
+        } else {
+            offsets.push(start);
+            skip_tag(source, start)
+        };
+    }
+
+    let mut positions = Vec::with_capacity(offsets.len());
+    let mut position = Position { line: 1, column: 1 };
+    let mut offsets = offsets.into_iter().peekable();
+    for (index, character) in source.char_indices() {
+        if offsets.peek() == Some(&index) {
+            positions.push(position);
+            offsets.next();
+        }
+        if character == '\n' {
+            position = Position { line: position.line + 1, column: 1 };
+        } else {
+            position.column += 1;
+        }
+    }
+    positions
+}
+
+/// The elements of a document in document order
+fn elements<'d>(document: &Document<'d>) -> Vec<DomElement<'d>> {
+    let mut elements = Vec::new();
+    let mut pending: Vec<DomElement> = document.root().children().into_iter()
+        .rev()
+        .filter_map(|child| child.element())
+        .collect();
+    while let Some(element) = pending.pop() {
+        elements.push(element);
+        pending.extend(element.children().into_iter().rev().filter_map(|child| child.element()));
+    }
+    elements
+}
+
+impl<'d> SourceMap<'d> {
+    /// Maps the elements of a document to the positions of their start tags in the text that the
+    /// document was parsed from
+    pub fn new(source: &str, document: &Document<'d>) -> SourceMap<'d> {
+        SourceMap { positions: elements(document).into_iter().zip(start_tags(source)).collect() }
+    }
+
+    /// The position of the start tag of an element of the document that the map was created for
+    pub fn position(&self, element: DomElement) -> Option<Position> {
+        self.positions.get(&element).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use super::*;
+
+    #[test]
+    fn positions() {
+        let xml = "<?xml version=\"1.0\"?>\n<?style <b> ?>\n<a x=\"1 > 0\">\n  <!-- <c/> -->\n\
+                   <b><![CDATA[<c/>]]></b>\n  <c/></a>";
+        let package = DomParser::parse(xml).expect("Failed to parse");
+        let document = package.as_document();
+        let source_map = SourceMap::new(xml, &document);
+        let a = document.root().children().into_iter().find_map(|child| child.element()).unwrap();
+        let children: Vec<DomElement> = a.children().into_iter().filter_map(|child| child.element()).collect();
+
+        assert_eq!(Some(Position { line: 3, column: 1 }), source_map.position(a));
+        assert_eq!(Some(Position { line: 5, column: 1 }), source_map.position(children[0]));
+        assert_eq!(Some(Position { line: 6, column: 3 }), source_map.position(children[1]));
+    }
+}
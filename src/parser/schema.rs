@@ -1,32 +1,62 @@
-extern crate sxd_document;
+use std::collections::HashSet;
+use std::sync::Arc;
 
 use sxd_document::{parser as DomParser, Package};
-use sxd_document::dom::{Document, Attribute as DomAttribute};
+use sxd_document::dom::{Document, Element as DomElement, Attribute as DomAttribute};
 
-use parser::{find_schema_children, parse_schema, SchemaError};
+use parser::{
+    find_schema_children,
+    find_root_schema,
+    parse_schema,
+    parse_id,
+    parse_additional_attributes,
+    parse_derivation_set,
+    parse_form,
+    ParseError,
+    SchemaError,
+    QName,
+    XML_NS_URI,
+    XSI_NS_URI,
+};
 use parser::elements::{parse_elements, Element};
+use parser::attributes::{parse_attributes, parse_attribute_groups, Attribute, AttributeGroup};
+use parser::groups::{parse_groups, Group};
+use parser::notations::{parse_notations, Notation};
+use parser::composition::{parse_includes, parse_imports, parse_redefines, Include, Import, Redefine};
 use parser::versions::{parse_version, SchemaVersion};
 use parser::types::{parse_types, TopLevelType};
+use parser::constraints::{check_schema, check_components, find_duplicate};
 use parser::annotations::{Annotation, parse_annotations};
+use parser::positions::{Position, SourceMap};
 use parser::types::Id;
 use parser::Language;
+use components::Components;
+use validator::{Validator, Validation, ValidationError, validate_with_resolver};
+use resolver::SchemaResolver;
 
-static XSD_10_SCHEMA_STR: &'static str = include_str!("schemas/1.0.xsd");
-static XSD_11_SCHEMA_STR: &'static str = include_str!("schemas/1.1.xsd");
+static XSD_10_SCHEMA_STR: &str = include_str!("schemas/1.0.xsd");
+static XSD_11_SCHEMA_STR: &str = include_str!("schemas/1.1.xsd");
+static XML_SCHEMA_STR: &str = include_str!("schemas/xml.xsd");
+static XSI_SCHEMA_STR: &str = include_str!("schemas/XMLSchema-instance.xsd");
 
+/// Whether the name of a local declaration is in the target namespace
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-schema
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
 pub enum FormChoice {
     Qualified,
     Unqualified,
 }
 
-#[derive(Eq, PartialEq, Hash, Debug)]
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-schema
+#[derive(Eq, PartialEq, Hash, Debug, Copy, Clone)]
 pub enum BlockDefault {
     Extension,
     Restriction,
     Substitution,
 }
 
-#[derive(Eq, PartialEq, Hash, Debug)]
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-schema
+#[derive(Eq, PartialEq, Hash, Debug, Copy, Clone)]
 pub enum FinalDefault {
     Extension,
     Restriction,
@@ -38,107 +68,302 @@ pub enum FinalDefault {
 pub struct Schema<'a> {
     pub version: SchemaVersion,
     pub target_namespace: Option<&'a str>,
-    pub final_default: Vec<FinalDefault>,
-    pub block_default: Vec<BlockDefault>,
+    // already applied to the final and block sets of all components
+    pub final_default: HashSet<FinalDefault>,
+    pub block_default: HashSet<BlockDefault>,
     // default unqualified
     pub attribute_form_default: FormChoice,
     // default unqualified
     pub element_form_default: FormChoice,
     pub id: Option<Id<'a>>,
     pub elements: Vec<Element<'a>>,
+    pub attributes: Vec<Attribute<'a>>,
     pub types: Vec<TopLevelType<'a>>,
     pub annotations: Vec<Annotation<'a>>,
-    // TODO
-    pub includes: Vec<&'a str>,
-    pub imports: Vec<&'a str>,
-    pub redefines: Vec<&'a str>,
-    pub groups: Vec<&'a str>,
-    pub attribute_groups: Vec<&'a str>,
-    pub notations: Vec<&'a str>,
+    pub includes: Vec<Include<'a>>,
+    pub imports: Vec<Import<'a>>,
+    pub redefines: Vec<Redefine<'a>>,
+    pub groups: Vec<Group<'a>>,
+    pub attribute_groups: Vec<AttributeGroup<'a>>,
+    pub notations: Vec<Notation<'a>>,
     // xml:lang
     pub language: Option<Language<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    // the schema element
+    pub source: DomElement<'a>,
+    // only known if the schema was parsed from its text
+    pub source_map: Option<SourceMap<'a>>,
+    // the resolved components that instances are validated against
+    pub components: Arc<Components>,
 }
 
 
-/// Makes sure that a schema is correct by validating it using the official schemas
-fn create_schema_spec<'a>(package: &'a Package) -> Schema<'a> {
-    let document = package.as_document();
-    let children = find_schema_children(document.root());
-    return Schema {
-        version: SchemaVersion::Xsd10,
-        target_namespace: None,
-        final_default: Vec::new(),
-        block_default: Vec::new(),
-        attribute_form_default: FormChoice::Unqualified,
-        element_form_default: FormChoice::Unqualified,
-        id: None,
-        elements: parse_elements(&children),
-        types: parse_types(&children),
+/// Parses the top level components of a schema document
+fn parse_schema_document(schema_element: DomElement, version: SchemaVersion) -> Result<Schema, ParseError> {
+    let children = find_schema_children(schema_element.document().root());
+    Ok(Schema {
+        version,
+        target_namespace: schema_element.attribute_value("targetNamespace"),
+        final_default: parse_derivation_set(&schema_element, "finalDefault", "finalDefault",
+                                            &[FinalDefault::Extension, FinalDefault::Restriction,
+                                                FinalDefault::List, FinalDefault::Union],
+                                            |method| match method {
+                                                "extension" => Some(FinalDefault::Extension),
+                                                "restriction" => Some(FinalDefault::Restriction),
+                                                "list" => Some(FinalDefault::List),
+                                                "union" => Some(FinalDefault::Union),
+                                                _ => None,
+                                            }),
+        block_default: parse_derivation_set(&schema_element, "blockDefault", "blockDefault",
+                                            &[BlockDefault::Extension, BlockDefault::Restriction,
+                                                BlockDefault::Substitution],
+                                            |method| match method {
+                                                "extension" => Some(BlockDefault::Extension),
+                                                "restriction" => Some(BlockDefault::Restriction),
+                                                "substitution" => Some(BlockDefault::Substitution),
+                                                _ => None,
+                                            }),
+        attribute_form_default: parse_form(&schema_element, "attributeFormDefault", "attributeFormDefault"),
+        element_form_default: parse_form(&schema_element, "elementFormDefault", "elementFormDefault"),
+        id: parse_id(&schema_element),
+        elements: parse_elements(&children)?,
+        attributes: parse_attributes(&children)?,
+        types: parse_types(&children)?,
         annotations: parse_annotations(&children),
-        includes: Vec::new(),
-        imports: Vec::new(),
-        redefines: Vec::new(),
-        groups: Vec::new(),
-        attribute_groups: Vec::new(),
-        notations: Vec::new(),
+        includes: parse_includes(&children)?,
+        imports: parse_imports(&children),
+        redefines: parse_redefines(&children)?,
+        groups: parse_groups(&children)?,
+        attribute_groups: parse_attribute_groups(&children)?,
+        notations: parse_notations(&children)?,
         language: None,
-        additional_attributes: Vec::new(),
-    };
+        additional_attributes: parse_additional_attributes(&schema_element),
+        source: schema_element,
+        source_map: None,
+        components: Arc::default(),
+    })
+}
+
+/// Makes sure that a schema is correct by validating it using the official schemas
+fn create_schema_spec(package: &Package) -> Schema<'_> {
+    find_root_schema(package.as_document().root())
+        .and_then(|schema_element| parse_schema_document(schema_element, SchemaVersion::Xsd10).ok())
+        .expect("Failed to parse the schema for schemas")
 }
 
 #[allow(dead_code)]
 fn validate_schema<'b>(schema: Schema<'b>, schema_document: &'b Document) -> Result<Schema<'b>, SchemaError> {
-    if schema.version == SchemaVersion::Xsd10 {
-        let package = DomParser::parse(&XSD_10_SCHEMA_STR)
-            .expect("Failed to parse Schema 1.0 XSD");
-        let schema_schema = create_schema_spec(&package);
-        schema_schema.validate(&schema_document)?;
-    } else if schema.version == SchemaVersion::Xsd11 {
-        let package = DomParser::parse(&XSD_11_SCHEMA_STR)
-            .expect("Failed to parse Schema 1.1 XSD");
-        let schema_schema = create_schema_spec(&package);
-        schema_schema.validate(&schema_document)?;
-    } else {
-        return Err(SchemaError::UnsupportedSchemaVersion);
-    }
-    return Ok(schema);
+    let schema_str = match schema.version {
+        SchemaVersion::Xsd10 => XSD_10_SCHEMA_STR,
+        SchemaVersion::Xsd11 => XSD_11_SCHEMA_STR,
+    };
+    let package = DomParser::parse(schema_str)
+        .expect("Failed to parse the schema for schemas");
+    let schema_schema = create_schema_spec(&package);
+    schema_schema.validate(schema_document)
+        .map_err(SchemaError::InvalidSchema)?;
+    Ok(schema)
+}
+
+/// Parses a schema document without checking the constraints between its components, e.g. to
+/// check them together with the ones of other documents
+pub(crate) fn parse_unchecked<'b>(document: &'b Document, source_map: Option<SourceMap<'b>>)
+                                  -> Result<Schema<'b>, SchemaError> {
+    let version = parse_version(document);
+    parse_schema(document.root())?;
+    let schema_element = find_root_schema(document.root()).ok_or(SchemaError::NoSchemaRootFound)?;
+    let mut schema = parse_schema_document(schema_element, version)
+        .map_err(|error| error.into_schema_error(source_map.as_ref()))?;
+    schema.source_map = source_map;
+    Ok(schema)
+}
+
+/// The embedded schema document of a namespace whose components are well known, these never
+/// have to be resolved
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#schema-repr
+pub fn builtin_schema(namespace: Option<&str>) -> Option<&'static str> {
+    match namespace {
+        Some(namespace) if namespace == XML_NS_URI => Some(XML_SCHEMA_STR),
+        Some(namespace) if namespace == XSI_NS_URI => Some(XSI_SCHEMA_STR),
+        _ => None,
+    }
+}
+
+/// Parses the embedded schema documents of the namespaces that the schemas import but none of
+/// them has as its target namespace
+pub(crate) fn builtin_packages(schemas: &[&Schema]) -> Vec<Package> {
+    let mut sources: Vec<&str> = Vec::new();
+    for import in schemas.iter().flat_map(|schema| &schema.imports) {
+        let is_loaded = schemas.iter().any(|schema| schema.target_namespace == import.namespace);
+        match builtin_schema(import.namespace) {
+            Some(source) if !is_loaded && !sources.contains(&source) => sources.push(source),
+            _ => {}
+        }
+    }
+    sources.iter()
+        .map(|source| DomParser::parse(source).expect("Failed to parse an embedded schema"))
+        .collect()
+}
+
+/// Parses the top level components of an embedded schema document
+pub(crate) fn parse_builtin<'b>(document: &'b Document) -> Schema<'b> {
+    parse_unchecked(document, None).expect("Failed to parse an embedded schema")
+}
+
+fn parse_and_check<'b>(document: &'b Document, source_map: Option<SourceMap<'b>>) -> Result<Schema<'b>, SchemaError> {
+    let mut schema = parse_unchecked(document, source_map)?;
+    let packages = builtin_packages(&[&schema]);
+    let documents: Vec<Document> = packages.iter().map(Package::as_document).collect();
+    let builtins: Vec<Schema> = documents.iter().map(parse_builtin).collect();
+    let schemas: Vec<&Schema> = Some(&schema).into_iter().chain(&builtins).collect();
+    check_schema(&schema, &schemas)?;
+    if let Some((_, error)) = find_duplicate(&[&schema]) {
+        return Err(SchemaError::DuplicateComponent(error));
+    }
+    let components = Arc::new(Components::new(&schemas));
+    check_components(&components)?;
+    schema.components = components;
+
+    // uncomment once https://github.com/shepmaster/sxd-document/issues/50 is fixed
+    // return validate_schema(schema, &document);
+    Ok(schema)
 }
 
 impl<'a> Schema<'a> {
     pub fn from_document<'b>(document: &'b Document) -> Result<Schema<'b>, SchemaError> {
-        let version = parse_version(&document);
-        let children = find_schema_children(document.root());
-        let schema_meta = parse_schema(document.root())?;
-
-        let schema = Schema {
-            version: version,
-            target_namespace: None,
-            final_default: Vec::new(),
-            block_default: Vec::new(),
-            attribute_form_default: FormChoice::Unqualified,
-            element_form_default: FormChoice::Unqualified,
-            id: None,
-            elements: parse_elements(&children),
-            types: parse_types(&children),
-            annotations: parse_annotations(&children),
-            includes: Vec::new(),
-            imports: Vec::new(),
-            redefines: Vec::new(),
-            groups: Vec::new(),
-            attribute_groups: Vec::new(),
-            notations: Vec::new(),
-            language: None,
-            additional_attributes: Vec::new(),
+        parse_and_check(document, None)
+    }
+
+    /// Parses a schema document like from_document and remembers the positions of its
+    /// components in the text that the document was parsed from, errors then carry the
+    /// line and column of the offending component
+    pub fn from_source<'b>(document: &'b Document, source: &str) -> Result<Schema<'b>, SchemaError> {
+        parse_and_check(document, Some(SourceMap::new(source, document)))
+    }
+
+    /// The line and column of the start tag of an element of the schema document, e.g. the
+    /// source of a component. Only known for schemas created by from_source
+    pub fn position(&self, element: DomElement) -> Option<Position> {
+        if element.document() != self.source.document() {
+            return None;
+        }
+        self.source_map.as_ref()?.position(element)
+    }
+
+    /// Validates a document and returns the schema information of all elements and attributes
+    /// that were validated, including their normalized and typed values
+    pub fn validate<'d>(&self, document: &Document<'d>) -> Result<Validation<'d>, ValidationError> {
+        Validator::new(self).validate(document)
+    }
+
+    /// Validates a whole document and collects all violations instead of stopping at the first one
+    pub fn validate_all<'d>(&self, document: &Document<'d>) -> Validation<'d> {
+        Validator::new(self).validate_all(document)
+    }
+
+    /// Validates a document and loads the schemas for namespaces that this schema does not
+    /// cover from the xsi:schemaLocation and xsi:noNamespaceSchemaLocation hints using the resolver
+    pub fn validate_with_resolver<'d, R>(&self, document: &Document<'d>, resolver: &R)
+                                         -> Result<Validation<'d>, ValidationError>
+        where R: SchemaResolver + ?Sized {
+        validate_with_resolver(&self.components, self.target_namespace, document, resolver)
+    }
+
+    /// Checks if a top level component with the given local name is referenced by the given name
+    fn is_referenced(&self, reference: &QName, name: &str) -> bool {
+        reference.namespace_uri == self.target_namespace && reference.local_name == name
+    }
+
+    pub fn find_element(&self, reference: &QName) -> Option<&Element<'a>> {
+        self.elements.iter()
+            .find(|element| self.is_referenced(reference, element.name))
+    }
+
+    pub fn find_attribute(&self, reference: &QName) -> Option<&Attribute<'a>> {
+        self.attributes.iter()
+            .find(|attribute| self.is_referenced(reference, attribute.name))
+    }
+
+    pub fn find_type(&self, reference: &QName) -> Option<&TopLevelType<'a>> {
+        self.types.iter()
+            .find(|top_level_type| {
+                let name = match **top_level_type {
+                    TopLevelType::SimpleType(ref simple_type) => simple_type.name,
+                    TopLevelType::ComplexType(ref complex_type) => complex_type.name,
+                };
+                name.is_some_and(|name| self.is_referenced(reference, name))
+            })
+    }
+
+    pub fn find_group(&self, reference: &QName) -> Option<&Group<'a>> {
+        self.groups.iter()
+            .find(|group| self.is_referenced(reference, group.name))
+    }
+
+    pub fn find_notation(&self, reference: &QName) -> Option<&Notation<'a>> {
+        self.notations.iter()
+            .find(|notation| self.is_referenced(reference, notation.name))
+    }
+
+    pub fn find_attribute_group(&self, reference: &QName) -> Option<&AttributeGroup<'a>> {
+        self.attribute_groups.iter()
+            .find(|group| self.is_referenced(reference, group.name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use super::*;
+    use parser::types::SimpleTypeContent;
+
+    #[test]
+    fn builtin_imports() {
+        let xsd = include_str!("../../tests/parser/schema/builtins.xsd");
+        let schema_package = DomParser::parse(xsd).expect("Failed to parse");
+        let schema_document = schema_package.as_document();
+        let schema = Schema::from_document(&schema_document).expect("Failed to parse schema");
+        let validate = |lang: &str, space: &str| {
+            let xml = format!("<d:document xmlns:d=\"urn:document\" {}><paragraph xml:space=\"{}\">Text</paragraph>\
+                               </d:document>", lang, space);
+            let package = DomParser::parse(&xml).expect("Failed to parse");
+            schema.validate(&package.as_document()).map(|_| ())
         };
 
-        // uncomment once https://github.com/shepmaster/sxd-document/issues/50 is fixed
-        // return validate_schema(schema, &document);
-        return Ok(schema);
+        assert_eq!(Ok(()), validate("xml:lang=\"en-GB\"", "preserve"));
+        assert_eq!(Ok(()), validate("xml:lang=\"\"", "default"));
+        assert!(validate("xml:lang=\"not a language\"", "preserve").is_err());
+        assert!(validate("xml:lang=\"en\"", "keep").is_err());
+        assert_eq!(Err(ValidationError::MissingAttribute), validate("", "preserve"));
+
+        for namespace in &[XML_NS_URI, XSI_NS_URI] {
+            let package = DomParser::parse(builtin_schema(Some(namespace)).unwrap()).expect("Failed to parse");
+            assert!(Schema::from_document(&package.as_document()).is_ok());
+        }
     }
 
-    #[allow(unused_variables)]
-    pub fn validate(&self, document: &Document) -> Result<(), SchemaError> {
-        Ok(())
+    #[test]
+    fn positions() {
+        let xsd = include_str!("../../tests/parser/schema/positions.xsd");
+        let schema_package = DomParser::parse(xsd).expect("Failed to parse");
+        let schema_document = schema_package.as_document();
+        let schema = Schema::from_source(&schema_document, xsd).expect("Failed to parse schema");
+        let rules = match schema.types[0] {
+            TopLevelType::SimpleType(ref simple_type) => match *simple_type.content {
+                SimpleTypeContent::Restriction(ref restriction) => &restriction.rules,
+                _ => panic!("Expected a restriction"),
+            },
+            _ => panic!("Expected a simple type"),
+        };
+
+        assert_eq!(Some(Position { line: 3, column: 3 }), schema.annotations[0].position(&schema));
+        assert_eq!(Some(Position { line: 8, column: 7 }), rules[0].position(&schema));
+        assert_eq!(Some(Position { line: 10, column: 7 }), rules[1].position(&schema));
+
+        let unmapped = Schema::from_document(&schema_document).expect("Failed to parse schema");
+        assert_eq!(None, unmapped.annotations[0].position(&unmapped));
     }
-}
\ No newline at end of file
+}
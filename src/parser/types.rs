@@ -13,20 +13,31 @@ use parser::{
     parse_additional_attributes,
     parse_id,
     parse_boolean_attribute,
+    parse_derivation_set,
+    parse_qname_attribute,
+    required_attribute,
+    ParseError,
+    QName,
 };
 
 use parser::annotations::{
     Annotation,
     parse_annotation,
 };
+use parser::groups::{Particle, parse_particle_child};
+use parser::attributes::{AttributeUse, parse_attribute_uses};
+use parser::wildcards::AnyAttribute;
+use parser::positions::Position;
+use parser::schema::Schema;
 
 
 /// This is a list of already built in simple types that can be referenced by using
 /// NS:Type, e.g. xsd:string where xsd = http://www.w3.org/2001/XMLSchema
 /// They hold no values since their implementations are built in
 /// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#built-in-datatypes
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
 pub enum BuiltIn {
+    AnySimpleType,
     String,
     NormalizedString,
     Token,
@@ -63,6 +74,7 @@ pub enum BuiltIn {
     Date,
     GYearMonth,
     GYear,
+    GMonthDay,
     GDay,
     GMonth,
     HexBinary,
@@ -72,6 +84,87 @@ pub enum BuiltIn {
     Notation,
 }
 
+impl BuiltIn {
+    /// Looks up a built in type by its local name in the XSD namespace
+    pub fn from_name(name: &str) -> Option<BuiltIn> {
+        let builtin = match name {
+            "anySimpleType" => BuiltIn::AnySimpleType,
+            "string" => BuiltIn::String,
+            "normalizedString" => BuiltIn::NormalizedString,
+            "token" => BuiltIn::Token,
+            "language" => BuiltIn::Language,
+            "Name" => BuiltIn::Name,
+            "NCName" => BuiltIn::NcName,
+            "ID" => BuiltIn::Id,
+            "IDREF" => BuiltIn::IdRef,
+            "IDREFS" => BuiltIn::IdRefs,
+            "ENTITY" => BuiltIn::Entity,
+            "ENTITIES" => BuiltIn::Entities,
+            "NMTOKEN" => BuiltIn::NmToken,
+            "NMTOKENS" => BuiltIn::NmTokens,
+            "boolean" => BuiltIn::Boolean,
+            "decimal" => BuiltIn::Decimal,
+            "integer" => BuiltIn::Integer,
+            "nonPositiveInteger" => BuiltIn::NonPositiveInteger,
+            "negativeInteger" => BuiltIn::NegativeInteger,
+            "nonNegativeInteger" => BuiltIn::NonNegativeInteger,
+            "positiveInteger" => BuiltIn::PositiveInteger,
+            "long" => BuiltIn::Long,
+            "unsignedLong" => BuiltIn::UnsignedLong,
+            "int" => BuiltIn::Int,
+            "unsignedInt" => BuiltIn::UnsignedInt,
+            "short" => BuiltIn::Short,
+            "unsignedShort" => BuiltIn::UnsignedShort,
+            "byte" => BuiltIn::Byte,
+            "unsignedByte" => BuiltIn::UnsignedByte,
+            "float" => BuiltIn::Float,
+            "double" => BuiltIn::Double,
+            "duration" => BuiltIn::Duration,
+            "dateTime" => BuiltIn::DateTime,
+            "time" => BuiltIn::Time,
+            "date" => BuiltIn::Date,
+            "gYearMonth" => BuiltIn::GYearMonth,
+            "gYear" => BuiltIn::GYear,
+            "gMonthDay" => BuiltIn::GMonthDay,
+            "gDay" => BuiltIn::GDay,
+            "gMonth" => BuiltIn::GMonth,
+            "hexBinary" => BuiltIn::HexBinary,
+            "base64Binary" => BuiltIn::Base64Binary,
+            "anyURI" => BuiltIn::AnyUri,
+            "QName" => BuiltIn::QName,
+            "NOTATION" => BuiltIn::Notation,
+            _ => return None,
+        };
+        Some(builtin)
+    }
+
+    /// The type a built in type is derived from by restriction, None for anySimpleType whose base
+    /// is anyType. The built in list types are derived from anySimpleType
+    /// see https://www.w3.org/TR/xmlschema11-2/#built-in-datatypes
+    pub fn base(self) -> Option<BuiltIn> {
+        let base = match self {
+            BuiltIn::AnySimpleType => return None,
+            BuiltIn::NormalizedString => BuiltIn::String,
+            BuiltIn::Token => BuiltIn::NormalizedString,
+            BuiltIn::Language | BuiltIn::Name | BuiltIn::NmToken => BuiltIn::Token,
+            BuiltIn::NcName => BuiltIn::Name,
+            BuiltIn::Id | BuiltIn::IdRef | BuiltIn::Entity => BuiltIn::NcName,
+            BuiltIn::Integer => BuiltIn::Decimal,
+            BuiltIn::NonPositiveInteger | BuiltIn::NonNegativeInteger | BuiltIn::Long => BuiltIn::Integer,
+            BuiltIn::NegativeInteger => BuiltIn::NonPositiveInteger,
+            BuiltIn::Int => BuiltIn::Long,
+            BuiltIn::Short => BuiltIn::Int,
+            BuiltIn::Byte => BuiltIn::Short,
+            BuiltIn::UnsignedLong | BuiltIn::PositiveInteger => BuiltIn::NonNegativeInteger,
+            BuiltIn::UnsignedInt => BuiltIn::UnsignedLong,
+            BuiltIn::UnsignedShort => BuiltIn::UnsignedInt,
+            BuiltIn::UnsignedByte => BuiltIn::UnsignedShort,
+            _ => BuiltIn::AnySimpleType,
+        };
+        Some(base)
+    }
+}
+
 /// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#ID
 #[derive(Eq, PartialEq, Debug)]
 pub struct Id<'a> {
@@ -94,6 +187,7 @@ pub struct Restriction<'a> {
 pub struct MinExclusive<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: &'a str,
     // default false
     pub fixed: bool,
@@ -105,6 +199,7 @@ pub struct MinExclusive<'a> {
 pub struct MinInclusive<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: &'a str,
     // default false
     pub fixed: bool,
@@ -116,6 +211,7 @@ pub struct MinInclusive<'a> {
 pub struct MaxExclusive<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: &'a str,
     // default false
     pub fixed: bool,
@@ -127,6 +223,7 @@ pub struct MaxExclusive<'a> {
 pub struct MaxInclusive<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: &'a str,
     // default false
     pub fixed: bool,
@@ -138,13 +235,14 @@ pub struct MaxInclusive<'a> {
 pub struct WhiteSpace<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: WhiteSpaceValue,
     // default false
     pub fixed: bool,
     pub annotation: Option<Annotation<'a>>,
 }
 
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
 pub enum WhiteSpaceValue {
     Collapse,
     Preserve,
@@ -156,6 +254,7 @@ pub enum WhiteSpaceValue {
 pub struct TotalDigits<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: usize,
     // default false
     pub fixed: bool,
@@ -167,6 +266,7 @@ pub struct TotalDigits<'a> {
 pub struct FractionDigits<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: usize,
     // default false
     pub fixed: bool,
@@ -178,6 +278,7 @@ pub struct FractionDigits<'a> {
 pub struct Pattern<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: &'a str,
     pub annotation: Option<Annotation<'a>>,
 }
@@ -187,6 +288,7 @@ pub struct Pattern<'a> {
 pub struct Enumeration<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: &'a str,
     pub annotation: Option<Annotation<'a>>,
 }
@@ -196,6 +298,7 @@ pub struct Enumeration<'a> {
 pub struct Length<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: usize,
     // default false
     pub fixed: bool,
@@ -207,6 +310,7 @@ pub struct Length<'a> {
 pub struct MinLength<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: usize,
     // default false
     pub fixed: bool,
@@ -218,6 +322,7 @@ pub struct MinLength<'a> {
 pub struct MaxLength<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
     pub value: usize,
     // default false
     pub fixed: bool,
@@ -240,13 +345,40 @@ pub enum RestrictionRule<'a> {
     Pattern(Pattern<'a>),
 }
 
+impl<'a> RestrictionRule<'a> {
+    /// The element of the facet
+    pub fn source(&self) -> DomElement<'a> {
+        match *self {
+            RestrictionRule::MinExclusive(ref facet) => facet.source,
+            RestrictionRule::MinInclusive(ref facet) => facet.source,
+            RestrictionRule::MaxExclusive(ref facet) => facet.source,
+            RestrictionRule::MaxInclusive(ref facet) => facet.source,
+            RestrictionRule::TotalDigits(ref facet) => facet.source,
+            RestrictionRule::FractionDigits(ref facet) => facet.source,
+            RestrictionRule::Length(ref facet) => facet.source,
+            RestrictionRule::MinLength(ref facet) => facet.source,
+            RestrictionRule::MaxLength(ref facet) => facet.source,
+            RestrictionRule::Enumeration(ref facet) => facet.source,
+            RestrictionRule::WhiteSpace(ref facet) => facet.source,
+            RestrictionRule::Pattern(ref facet) => facet.source,
+        }
+    }
+
+    /// The line and column of the facet element, only known for schemas created by
+    /// Schema::from_source
+    pub fn position(&self, schema: &Schema) -> Option<Position> {
+        schema.position(self.source())
+    }
+}
+
 /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-union
 #[derive(Eq, PartialEq, Debug)]
 pub struct Union<'a> {
     pub id: Option<Id<'a>>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
     pub annotation: Option<Annotation<'a>>,
-    pub member_types: Vec<SimpleType<'a>>,  // choose from memberTypes(QName) or nested simpleType
+    // choose from memberTypes(QName) or nested simpleType
+    pub member_types: Vec<AnySimpleType<'a>>,
 }
 
 /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-list
@@ -256,50 +388,93 @@ pub struct List<'a> {
     pub additional_attributes: Vec<DomAttribute<'a>>,
     // choose from itemType(QName) or nested simpleType
     pub annotation: Option<Annotation<'a>>,
-    pub item_type: SimpleType<'a>,
+    pub item_type: AnySimpleType<'a>,
 }
 
 #[derive(Eq, PartialEq, Debug)]
 pub enum AnyType<'a> {
-    ComplexType(ComplexType<'a>),
+    ComplexType(Box<ComplexType<'a>>),
     AnySimpleType(AnySimpleType<'a>),
+    // xsd:anyType which allows any attributes and content
+    Any,
+    // a top level simple or complex type referenced by its name
+    Reference(QName<'a>),
 }
 
 #[derive(Eq, PartialEq, Debug)]
 pub enum AnySimpleType<'a> {
     BuiltIn(BuiltIn),
-    SimpleType(SimpleType<'a>),
+    SimpleType(Box<SimpleType<'a>>),
+    // a top level simple type referenced by its name
+    Reference(QName<'a>),
 }
 
 #[derive(Eq, PartialEq, Debug)]
 pub enum TopLevelType<'a> {
-    SimpleType(SimpleType<'a>),
-    ComplexType(ComplexType<'a>),
+    SimpleType(Box<SimpleType<'a>>),
+    ComplexType(Box<ComplexType<'a>>),
 }
 
-#[derive(Eq, PartialEq, Debug, Hash)]
+/// Derivations which may not use a simple type as their base
+/// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#dc-final
+#[derive(Eq, PartialEq, Debug, Hash, Copy, Clone)]
 pub enum SimpleFinal {
     Extension,
     Restriction,
+    List,
     Union,
 }
 
-#[derive(Eq, PartialEq, Debug, Hash)]
+/// Derivations which may not use a complex type as their base
+#[derive(Eq, PartialEq, Debug, Hash, Copy, Clone)]
 pub enum ComplexFinal {
     Extension,
     Restriction,
 }
 
-#[derive(Eq, PartialEq, Debug, Hash)]
+/// Derived types which may not be used in place of a complex type in instances
+#[derive(Eq, PartialEq, Debug, Hash, Copy, Clone)]
 pub enum ComplexBlock {
     Extension,
     Restriction,
 }
 
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
+pub enum DerivationMethod {
+    Extension,
+    Restriction,
+}
+
+/// The extension or restriction inside of simpleContent or complexContent
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-complexContent
+#[derive(Eq, PartialEq, Debug)]
+pub struct ContentDerivation<'a> {
+    pub id: Option<Id<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub annotation: Option<Annotation<'a>>,
+    pub method: DerivationMethod,
+    pub base: AnyType<'a>,
+    // simpleContent restrictions may restrict the content with a nested simpleType and facets
+    pub simple_type: Option<SimpleType<'a>>,
+    pub rules: Vec<RestrictionRule<'a>>,
+    pub particle: Option<Particle<'a>>,
+    pub attributes: Vec<AttributeUse<'a>>,
+    pub any_attribute: Option<AnyAttribute<'a>>,
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub enum ComplexTypeContent<'a> {
+    SimpleContent(ContentDerivation<'a>),
+    // content which is defined directly inside of the complexType is parsed as the restriction
+    // of anyType it is a shorthand for
+    ComplexContent(ContentDerivation<'a>),
+}
+
 /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-complexType
 #[derive(Eq, PartialEq, Debug)]
 pub struct ComplexType<'a> {
-    pub name: &'a str,
+    // None for anonymous types
+    pub name: Option<&'a str>,
     pub id: Option<Id<'a>>,
     // defaults to false
     pub is_mixed: bool,
@@ -309,18 +484,21 @@ pub struct ComplexType<'a> {
     pub block_modes: HashSet<ComplexBlock>,
     pub final_modes: HashSet<ComplexFinal>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
-    // TODO: Content: (simpleContent | complexContent | ((group | all | choice | sequence)?, ((attribute | attributeGroup)*, anyAttribute?)))
+    pub source: DomElement<'a>,
+    pub content: Box<ComplexTypeContent<'a>>,
 }
 
 /// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#element-simpleType
 #[derive(Eq, PartialEq, Debug)]
 pub struct SimpleType<'a> {
     pub id: Option<Id<'a>>,
-    pub name: &'a str,
+    // None for anonymous types
+    pub name: Option<&'a str>,
     pub content: Box<SimpleTypeContent<'a>>,
     pub annotation: Option<Annotation<'a>>,
     pub final_modes: HashSet<SimpleFinal>,
     pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub source: DomElement<'a>,
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -332,51 +510,302 @@ pub enum SimpleTypeContent<'a> {
 
 #[inline]
 fn is_type(element: &DomElement) -> bool {
-    is_of_element(&element, "simpleType") || is_of_element(&element, "complexType")
+    is_of_element(element, "simpleType") || is_of_element(element, "complexType")
 }
 
-pub fn parse_types<'a>(elements: &Vec<DomElement<'a>>) -> Vec<TopLevelType<'a>> {
+pub fn parse_types<'a>(elements: &[DomElement<'a>]) -> Result<Vec<TopLevelType<'a>>, ParseError<'a>> {
     elements.iter()
-        .filter(|&element| is_type(&element))
+        .filter(|&element| is_type(element))
         .map(|&element| parse_type(element))
         .collect()
 }
 
-pub fn parse_type<'a>(element: DomElement<'a>) -> TopLevelType<'a> {
-    let type_name = element.attribute("name").expect("Element defined without name");
+pub fn parse_type(element: DomElement) -> Result<TopLevelType, ParseError> {
+    required_attribute(&element, "name")?;
     if element.name().local_part() == "simpleType" {
-        return TopLevelType::SimpleType(SimpleType {
-            name: &type_name.value(),
-            annotation: parse_annotation(&element),
-            final_modes: HashSet::new(),
-            additional_attributes: parse_additional_attributes(&element),
-            id: parse_id(&element),
-            content: Box::new(SimpleTypeContent::Restriction(Restriction {
-                additional_attributes: vec![],
-                annotation: None,
-                id: None,
-                restriction_type: AnySimpleType::BuiltIn(BuiltIn::String),
-                rules: vec![
-                    RestrictionRule::Pattern(Pattern {
-                        id: None,
-                        additional_attributes: vec![],
-                        value: "\\d{3}-[A-Z]{2}",
-                        annotation: None,
-                    })
-                ],
-            })),
-
-        });
+        Ok(TopLevelType::SimpleType(Box::new(parse_simple_type(element)?)))
     } else {
-        return TopLevelType::ComplexType(ComplexType {
-            name: &type_name.value(),
-            id: parse_id(&element),
-            annotation: parse_annotation(&element),
-            additional_attributes: parse_additional_attributes(&element),
-            is_mixed: parse_boolean_attribute(&element, "mixed", false),
-            is_abstract: parse_boolean_attribute(&element, "mixed", false),
-            block_modes: HashSet::new(),
-            final_modes: HashSet::new(),
-        });
+        Ok(TopLevelType::ComplexType(Box::new(parse_complex_type(element)?)))
+    }
+}
+
+pub fn parse_complex_type(element: DomElement) -> Result<ComplexType, ParseError> {
+    let simple_content = parse_child(&element, |el| is_of_element(el, "simpleContent"), |el| el);
+    let complex_content = parse_child(&element, |el| is_of_element(el, "complexContent"), |el| el);
+    // mixed on complexContent overrides mixed on the complexType
+    let is_mixed = match complex_content {
+        Some(content) if content.attribute("mixed").is_some() => parse_boolean_attribute(&content, "mixed", false),
+        _ => parse_boolean_attribute(&element, "mixed", false),
+    };
+    let content = match (simple_content, complex_content) {
+        (Some(content), _) => ComplexTypeContent::SimpleContent(parse_content_derivation(content)?),
+        (_, Some(content)) => ComplexTypeContent::ComplexContent(parse_content_derivation(content)?),
+        _ => ComplexTypeContent::ComplexContent(parse_implicit_content(element)?),
+    };
+    Ok(ComplexType {
+        name: element.attribute_value("name"),
+        id: parse_id(&element),
+        annotation: parse_annotation(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        source: element,
+        is_mixed,
+        is_abstract: parse_boolean_attribute(&element, "abstract", false),
+        block_modes: parse_derivation_set(&element, "block", "blockDefault",
+                                          &[ComplexBlock::Extension, ComplexBlock::Restriction],
+                                          |method| match method {
+                                              "extension" => Some(ComplexBlock::Extension),
+                                              "restriction" => Some(ComplexBlock::Restriction),
+                                              _ => None,
+                                          }),
+        final_modes: parse_derivation_set(&element, "final", "finalDefault",
+                                          &[ComplexFinal::Extension, ComplexFinal::Restriction],
+                                          |method| match method {
+                                              "extension" => Some(ComplexFinal::Extension),
+                                              "restriction" => Some(ComplexFinal::Restriction),
+                                              _ => None,
+                                          }),
+        content: Box::new(content),
+    })
+}
+
+/// Parses the facets of a restriction, other children like annotations are skipped
+fn parse_restriction_rules<'a>(element: &DomElement<'a>) -> Result<Vec<RestrictionRule<'a>>, ParseError<'a>> {
+    parse_children(element, |_| true, parse_restriction_rule)
+        .into_iter()
+        .filter_map(Result::transpose)
+        .collect()
+}
+
+fn parse_content_derivation(element: DomElement) -> Result<ContentDerivation, ParseError> {
+    let (method, derivation) = match parse_child(&element, |el| is_of_element(el, "extension"), |el| el) {
+        Some(extension) => (DerivationMethod::Extension, extension),
+        None => (DerivationMethod::Restriction,
+                 parse_child(&element, |el| is_of_element(el, "restriction"), |el| el)
+                     .ok_or(ParseError::new("s4s-elt-must-match", "", element))?),
+    };
+    let base = required_attribute(&derivation, "base")?;
+    let (attributes, any_attribute) = parse_attribute_uses(&derivation)?;
+    Ok(ContentDerivation {
+        id: parse_id(&derivation),
+        additional_attributes: parse_additional_attributes(&derivation),
+        annotation: parse_annotation(&derivation),
+        method,
+        base: parse_any_type_name(&derivation, base, "base")?,
+        simple_type: parse_child(&derivation, |el| is_of_element(el, "simpleType"), parse_simple_type).transpose()?,
+        rules: parse_restriction_rules(&derivation)?,
+        particle: parse_particle_child(&derivation)?,
+        attributes,
+        any_attribute,
+    })
+}
+
+fn parse_implicit_content(element: DomElement) -> Result<ContentDerivation, ParseError> {
+    let (attributes, any_attribute) = parse_attribute_uses(&element)?;
+    Ok(ContentDerivation {
+        id: None,
+        additional_attributes: Vec::new(),
+        annotation: None,
+        method: DerivationMethod::Restriction,
+        base: AnyType::Any,
+        simple_type: None,
+        rules: Vec::new(),
+        particle: parse_particle_child(&element)?,
+        attributes,
+        any_attribute,
+    })
+}
+
+/// Resolves a type name which can reference either a simple or a complex type
+pub fn parse_any_type_name<'a>(element: &DomElement<'a>, name: &'a str, attribute: &'static str)
+                               -> Result<AnyType<'a>, ParseError<'a>> {
+    Ok(match parse_type_name(element, name, attribute)? {
+        AnySimpleType::Reference(name) if name.namespace_uri == Some(XSD_NS_URI) && name.local_name == "anyType" =>
+            AnyType::Any,
+        AnySimpleType::Reference(name) => AnyType::Reference(name),
+        simple_type => AnyType::AnySimpleType(simple_type),
+    })
+}
+
+/// Parses the type of an element declaration from its type attribute or nested type
+pub fn parse_element_type<'a>(element: &DomElement<'a>) -> Result<AnyType<'a>, ParseError<'a>> {
+    if let Some(name) = element.attribute_value("type") {
+        return parse_any_type_name(element, name, "type");
+    }
+    let simple_type = parse_child(element, |el| is_of_element(el, "simpleType"), |el| parse_simple_type(el)
+        .map(|simple_type| AnyType::AnySimpleType(AnySimpleType::SimpleType(Box::new(simple_type)))));
+    let complex_type = || parse_child(element, |el| is_of_element(el, "complexType"), |el| parse_complex_type(el)
+        .map(|complex_type| AnyType::ComplexType(Box::new(complex_type))));
+    Ok(simple_type.or_else(complex_type).transpose()?.unwrap_or(AnyType::Any))
+}
+
+pub fn parse_simple_type<'a>(element: DomElement<'a>) -> Result<SimpleType<'a>, ParseError<'a>> {
+    let content = parse_child(&element, |&el| is_of_element(&el, "restriction"),
+                              |el| parse_restriction(el).map(SimpleTypeContent::Restriction))
+        .or_else(|| parse_child(&element, |&el| is_of_element(&el, "list"),
+                                |el| parse_list(el).map(SimpleTypeContent::List)))
+        .or_else(|| parse_child(&element, |&el| is_of_element(&el, "union"),
+                                |el| parse_union(el).map(SimpleTypeContent::Union)))
+        .ok_or(ParseError::new("s4s-elt-must-match", "", element))??;
+    Ok(SimpleType {
+        name: element.attribute_value("name"),
+        annotation: parse_annotation(&element),
+        final_modes: parse_simple_final(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        source: element,
+        id: parse_id(&element),
+        content: Box::new(content),
+    })
+}
+
+/// Extension is only allowed in the final attribute of XSD 1.1 simple types
+fn parse_simple_final(element: &DomElement) -> HashSet<SimpleFinal> {
+    parse_derivation_set(element, "final", "finalDefault",
+                         &[SimpleFinal::Extension, SimpleFinal::Restriction, SimpleFinal::List, SimpleFinal::Union],
+                         |method| match method {
+                             "extension" => Some(SimpleFinal::Extension),
+                             "restriction" => Some(SimpleFinal::Restriction),
+                             "list" => Some(SimpleFinal::List),
+                             "union" => Some(SimpleFinal::Union),
+                             _ => None,
+                         })
+}
+
+/// Resolves a type name to a built in type if it is in the XSD namespace
+pub fn parse_type_name<'a>(element: &DomElement<'a>, name: &'a str, attribute: &'static str)
+                           -> Result<AnySimpleType<'a>, ParseError<'a>> {
+    let name = parse_qname_attribute(element, name, attribute)?;
+    let builtin = match name.namespace_uri {
+        Some(namespace) if namespace == XSD_NS_URI => BuiltIn::from_name(name.local_name),
+        _ => None,
+    };
+    Ok(builtin.map(AnySimpleType::BuiltIn)
+        .unwrap_or(AnySimpleType::Reference(name)))
+}
+
+/// Types are either referenced by an attribute or defined in a nested simpleType
+fn parse_type_attribute_or_child<'a>(element: &DomElement<'a>, attribute: &'static str)
+                                     -> Result<AnySimpleType<'a>, ParseError<'a>> {
+    match element.attribute_value(attribute) {
+        Some(name) => parse_type_name(element, name, attribute),
+        None => parse_child(element, |&el| is_of_element(&el, "simpleType"),
+                            |el| parse_simple_type(el).map(|simple_type| AnySimpleType::SimpleType(Box::new(simple_type))))
+            .ok_or(ParseError::new("s4s-att-must-appear", attribute, *element))?,
+    }
+}
+
+fn parse_restriction<'a>(element: DomElement<'a>) -> Result<Restriction<'a>, ParseError<'a>> {
+    Ok(Restriction {
+        id: parse_id(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        annotation: parse_annotation(&element),
+        restriction_type: parse_type_attribute_or_child(&element, "base")?,
+        rules: parse_restriction_rules(&element)?,
+    })
+}
+
+fn parse_list<'a>(element: DomElement<'a>) -> Result<List<'a>, ParseError<'a>> {
+    Ok(List {
+        id: parse_id(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        annotation: parse_annotation(&element),
+        item_type: parse_type_attribute_or_child(&element, "itemType")?,
+    })
+}
+
+fn parse_union<'a>(element: DomElement<'a>) -> Result<Union<'a>, ParseError<'a>> {
+    let mut member_types: Vec<AnySimpleType<'a>> = element.attribute_value("memberTypes")
+        .map(|names| names.split_whitespace()
+            .map(|name| parse_type_name(&element, name, "memberTypes"))
+            .collect())
+        .unwrap_or(Ok(Vec::new()))?;
+    for simple_type in parse_children(&element, |&el| is_of_element(&el, "simpleType"), parse_simple_type) {
+        member_types.push(AnySimpleType::SimpleType(Box::new(simple_type?)));
+    }
+    Ok(Union {
+        id: parse_id(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        annotation: parse_annotation(&element),
+        member_types,
+    })
+}
+
+fn parse_facet_value<'a>(element: &DomElement<'a>) -> Result<&'a str, ParseError<'a>> {
+    required_attribute(element, "value")
+}
+
+fn parse_facet_number<'a>(element: &DomElement<'a>) -> Result<usize, ParseError<'a>> {
+    parse_facet_value(element)?.trim().parse()
+        .map_err(|_| ParseError::new("s4s-att-invalid-value", "value", *element))
+}
+
+fn parse_white_space_value<'a>(element: &DomElement<'a>) -> Result<WhiteSpaceValue, ParseError<'a>> {
+    match parse_facet_value(element)?.trim() {
+        "preserve" => Ok(WhiteSpaceValue::Preserve),
+        "replace" => Ok(WhiteSpaceValue::Replace),
+        "collapse" => Ok(WhiteSpaceValue::Collapse),
+        _ => Err(ParseError::new("s4s-att-invalid-value", "value", *element)),
+    }
+}
+
+/// Parses a facet, returns None for elements that are not facets, e.g. annotations
+pub fn parse_restriction_rule<'a>(element: DomElement<'a>) -> Result<Option<RestrictionRule<'a>>, ParseError<'a>> {
+    if element.name().namespace_uri() != Some(XSD_NS_URI) {
+        return Ok(None);
     }
+    let id = parse_id(&element);
+    let additional_attributes = parse_additional_attributes(&element);
+    let annotation = parse_annotation(&element);
+    let fixed = parse_boolean_attribute(&element, "fixed", false);
+    let rule = match element.name().local_part() {
+        "minExclusive" => RestrictionRule::MinExclusive(MinExclusive {
+            id, additional_attributes, annotation, source: element, fixed,
+            value: parse_facet_value(&element)?,
+        }),
+        "minInclusive" => RestrictionRule::MinInclusive(MinInclusive {
+            id, additional_attributes, annotation, source: element, fixed,
+            value: parse_facet_value(&element)?,
+        }),
+        "maxExclusive" => RestrictionRule::MaxExclusive(MaxExclusive {
+            id, additional_attributes, annotation, source: element, fixed,
+            value: parse_facet_value(&element)?,
+        }),
+        "maxInclusive" => RestrictionRule::MaxInclusive(MaxInclusive {
+            id, additional_attributes, annotation, source: element, fixed,
+            value: parse_facet_value(&element)?,
+        }),
+        "totalDigits" => RestrictionRule::TotalDigits(TotalDigits {
+            id, additional_attributes, annotation, source: element, fixed,
+            value: parse_facet_number(&element)?,
+        }),
+        "fractionDigits" => RestrictionRule::FractionDigits(FractionDigits {
+            id, additional_attributes, annotation, source: element, fixed,
+            value: parse_facet_number(&element)?,
+        }),
+        "length" => RestrictionRule::Length(Length {
+            id, additional_attributes, annotation, source: element, fixed,
+            value: parse_facet_number(&element)?,
+        }),
+        "minLength" => RestrictionRule::MinLength(MinLength {
+            id, additional_attributes, annotation, source: element, fixed,
+            value: parse_facet_number(&element)?,
+        }),
+        "maxLength" => RestrictionRule::MaxLength(MaxLength {
+            id, additional_attributes, annotation, source: element, fixed,
+            value: parse_facet_number(&element)?,
+        }),
+        "whiteSpace" => RestrictionRule::WhiteSpace(WhiteSpace {
+            id, additional_attributes, annotation, source: element, fixed,
+            value: parse_white_space_value(&element)?,
+        }),
+        "enumeration" => RestrictionRule::Enumeration(Enumeration {
+            id, additional_attributes, annotation, source: element,
+            value: parse_facet_value(&element)?,
+        }),
+        "pattern" => RestrictionRule::Pattern(Pattern {
+            id, additional_attributes, annotation, source: element,
+            value: parse_facet_value(&element)?,
+        }),
+        _ => return Ok(None),
+    };
+    Ok(Some(rule))
 }
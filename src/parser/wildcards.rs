@@ -0,0 +1,115 @@
+use sxd_document::dom::{
+    Element as DomElement,
+    Attribute as DomAttribute,
+};
+
+use parser::{
+    parse_id,
+    parse_additional_attributes,
+    find_target_namespace,
+    ParseError,
+};
+use parser::types::Id;
+use parser::groups::{MaxOccurs, parse_occurs};
+use parser::annotations::{Annotation, parse_annotation};
+
+/// Namespaces whose elements or attributes a wildcard allows, None stands for no namespace
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#Wildcards
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub enum NamespaceConstraint<'a> {
+    // ##any
+    Any,
+    // ##other, any namespace except the target namespace and no namespace
+    Not(Option<&'a str>),
+    // a list of namespaces including ##targetNamespace and ##local
+    List(Vec<Option<&'a str>>),
+}
+
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum ProcessContents {
+    Strict,
+    Lax,
+    Skip,
+}
+
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-any
+#[derive(Eq, PartialEq, Debug)]
+pub struct Any<'a> {
+    pub id: Option<Id<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub annotation: Option<Annotation<'a>>,
+    pub min_occurs: usize,
+    pub max_occurs: MaxOccurs,
+    // defaults to ##any
+    pub namespace: NamespaceConstraint<'a>,
+    // defaults to strict
+    pub process_contents: ProcessContents,
+}
+
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-anyAttribute
+#[derive(Eq, PartialEq, Debug)]
+pub struct AnyAttribute<'a> {
+    pub id: Option<Id<'a>>,
+    pub additional_attributes: Vec<DomAttribute<'a>>,
+    pub annotation: Option<Annotation<'a>>,
+    // defaults to ##any
+    pub namespace: NamespaceConstraint<'a>,
+    // defaults to strict
+    pub process_contents: ProcessContents,
+}
+
+impl<'a> NamespaceConstraint<'a> {
+    pub fn allows(&self, namespace: Option<&str>) -> bool {
+        match *self {
+            NamespaceConstraint::Any => true,
+            NamespaceConstraint::Not(excluded) => namespace.is_some() && namespace != excluded,
+            NamespaceConstraint::List(ref namespaces) => namespaces.contains(&namespace),
+        }
+    }
+}
+
+fn parse_namespace_constraint<'a>(element: &DomElement<'a>) -> NamespaceConstraint<'a> {
+    let target_namespace = find_target_namespace(element);
+    match element.attribute_value("namespace").map(str::trim) {
+        None | Some("##any") => NamespaceConstraint::Any,
+        Some("##other") => NamespaceConstraint::Not(target_namespace),
+        Some(namespaces) => NamespaceConstraint::List(namespaces.split_whitespace()
+            .map(|namespace| match namespace {
+                "##targetNamespace" => target_namespace,
+                "##local" => None,
+                _ => Some(namespace),
+            })
+            .collect()),
+    }
+}
+
+fn parse_process_contents(element: &DomElement) -> ProcessContents {
+    match element.attribute_value("processContents").map(str::trim) {
+        Some("lax") => ProcessContents::Lax,
+        Some("skip") => ProcessContents::Skip,
+        _ => ProcessContents::Strict,
+    }
+}
+
+pub fn parse_any<'a>(element: DomElement<'a>) -> Result<Any<'a>, ParseError<'a>> {
+    let (min_occurs, max_occurs) = parse_occurs(&element)?;
+    Ok(Any {
+        id: parse_id(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        annotation: parse_annotation(&element),
+        min_occurs,
+        max_occurs,
+        namespace: parse_namespace_constraint(&element),
+        process_contents: parse_process_contents(&element),
+    })
+}
+
+pub fn parse_any_attribute<'a>(element: DomElement<'a>) -> AnyAttribute<'a> {
+    AnyAttribute {
+        id: parse_id(&element),
+        additional_attributes: parse_additional_attributes(&element),
+        annotation: parse_annotation(&element),
+        namespace: parse_namespace_constraint(&element),
+        process_contents: parse_process_contents(&element),
+    }
+}
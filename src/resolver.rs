@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+/// Loads schema documents by their location, e.g. for schemaLocation hints in instance documents
+pub trait SchemaResolver {
+    /// Returns the content of the schema document for a namespace and a location or None if
+    /// the location can not be resolved
+    fn resolve(&self, namespace: Option<&str>, location: &str) -> Option<String>;
+}
+
+impl<F> SchemaResolver for F where F: Fn(Option<&str>, &str) -> Option<String> {
+    fn resolve(&self, namespace: Option<&str>, location: &str) -> Option<String> {
+        self(namespace, location)
+    }
+}
+
+/// The length of the scheme of a URI including the colon, single letters are taken as drive
+/// letters of Windows paths
+fn scheme_length(location: &str) -> Option<usize> {
+    let colon = location.find(':')?;
+    let scheme = &location[..colon];
+    let is_scheme = scheme.len() > 1 && scheme.starts_with(|c: char| c.is_ascii_alphabetic()) &&
+        scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+    if is_scheme { Some(colon + 1) } else { None }
+}
+
+/// Removes the . and .. segments of a path, relative paths keep the .. segments that lead out
+/// of the directory they start in
+fn remove_dot_segments(path: &str) -> String {
+    let is_absolute = path.starts_with('/');
+    let mut segments: Vec<&str> = Vec::new();
+    let parts: Vec<&str> = path.split('/').collect();
+    for (index, &segment) in parts.iter().enumerate() {
+        let is_last = index == parts.len() - 1;
+        match segment {
+            "." | ".." if is_last => {
+                if segment == ".." {
+                    remove_segment(&mut segments, is_absolute);
+                }
+                segments.push("");
+            }
+            "." => {}
+            ".." => remove_segment(&mut segments, is_absolute),
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+fn remove_segment(segments: &mut Vec<&str>, is_absolute: bool) {
+    match segments.last() {
+        // the empty segment before the leading slash of an absolute path
+        Some(&"") if is_absolute && segments.len() == 1 => {}
+        Some(&last) if last != ".." => {
+            segments.pop();
+        }
+        _ if !is_absolute => segments.push(".."),
+        _ => {}
+    }
+}
+
+/// Resolves a location relative to the location of the document that refers to it, e.g. the
+/// schemaLocation of an include. Locations may be URIs or file paths
+/// see https://www.rfc-editor.org/rfc/rfc3986#section-5.2
+pub fn resolve_location(base: &str, location: &str) -> String {
+    if scheme_length(location).is_some() {
+        return location.to_string();
+    }
+    let scheme = scheme_length(base).map_or("", |length| &base[..length]);
+    let rest = &base[scheme.len()..];
+    // the authority of hierarchical URIs like http://example.com
+    let authority = match rest.strip_prefix("//") {
+        Some(after) => &rest[..2 + after.find('/').unwrap_or(after.len())],
+        None => "",
+    };
+    let base_path = &rest[authority.len()..];
+    let base_path = &base_path[..base_path.find(['?', '#']).unwrap_or(base_path.len())];
+    if location.starts_with("//") {
+        return format!("{}{}", scheme, location);
+    }
+    let path = if location.starts_with('/') {
+        location.to_string()
+    } else {
+        match base_path.rfind('/') {
+            Some(index) => format!("{}{}", &base_path[..=index], location),
+            None if !authority.is_empty() => format!("/{}", location),
+            None => location.to_string(),
+        }
+    };
+    format!("{}{}{}", scheme, authority, remove_dot_segments(&path))
+}
+
+/// The path of a local file named by a location, i.e. the path of a file URI or the location itself
+pub fn to_path(location: &str) -> PathBuf {
+    PathBuf::from(location.strip_prefix("file://").unwrap_or(location))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations() {
+        assert_eq!("http://example.com/schemas/common.xsd",
+                   resolve_location("http://example.com/schemas/order.xsd", "common.xsd"));
+        assert_eq!("http://example.com/common.xsd",
+                   resolve_location("http://example.com/schemas/order.xsd?v=1", "../common.xsd"));
+        assert_eq!("http://example.com/common.xsd", resolve_location("http://example.com", "common.xsd"));
+        assert_eq!("http://other.org/common.xsd",
+                   resolve_location("http://example.com/order.xsd", "//other.org/common.xsd"));
+        assert_eq!("file:///schemas/common.xsd", resolve_location("file:///schemas/v1/order.xsd", "/schemas/common.xsd"));
+        assert_eq!("urn:common", resolve_location("schemas/order.xsd", "urn:common"));
+        assert_eq!("schemas/types/common.xsd", resolve_location("schemas/order.xsd", "./types/common.xsd"));
+        assert_eq!("../common.xsd", resolve_location("order.xsd", "../common.xsd"));
+        assert_eq!("C:/schemas/common.xsd", resolve_location("C:/schemas/order.xsd", "common.xsd"));
+    }
+}
@@ -0,0 +1,419 @@
+use std::sync::Arc;
+
+use sxd_document::{parser as DomParser, Package};
+use sxd_document::dom::Document;
+
+use compiled::{read_header, write_header};
+use components::{CacheError, Components, Reader, Writer};
+use parser::SchemaError;
+use parser::composition::include_into;
+use parser::constraints::{check_components, check_schema, find_duplicate};
+use parser::positions::SourceMap;
+use parser::schema::{Schema, builtin_packages, builtin_schema, parse_builtin, parse_unchecked};
+use resolver::{SchemaResolver, resolve_location};
+use validator::{Validation, ValidationError, Validator};
+
+/// An error in one of the documents of a schema set
+#[derive(Debug, Eq, PartialEq)]
+pub struct SchemaSetError {
+    // the index of the document in the given sources, None for errors that are only found once
+    // the components of all documents are merged, e.g. an invalid default of a type in another
+    // document
+    pub document: Option<usize>,
+    pub error: SchemaError,
+}
+
+/// The components of schema documents for one or more target namespaces, merged into a single
+/// graph so that instances may use the elements and types of all of them. Documents may
+/// reference each other regardless of their order, components of the same namespace may be
+/// spread over multiple documents
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#composition-schemaImport
+#[derive(Clone, Debug)]
+pub struct SchemaSet {
+    // the texts of the documents in the order they were given or loaded
+    sources: Vec<String>,
+    // the distinct target namespaces in the order of the documents
+    target_namespaces: Vec<Option<String>>,
+    components: Arc<Components>,
+}
+
+impl SchemaSet {
+    /// Parses and checks the texts of schema documents together. A document whose text was
+    /// already given is only loaded once, any other top level component that has the name of
+    /// a component of the same symbol space in its namespace is a conflicting duplicate.
+    /// Imports of the xml and xsi namespaces use the embedded schemas unless a document for
+    /// the namespace is given
+    pub fn from_sources(sources: &[&str]) -> Result<SchemaSet, SchemaSetError> {
+        let sources: Vec<(&str, Option<&str>)> = sources.iter().map(|&source| (source, None)).collect();
+        SchemaSet::from_documents(&sources)
+    }
+
+    /// Loads a schema document and all documents that it includes or imports, directly or
+    /// through other documents, with the resolver. Relative locations are resolved against the
+    /// location of the referring document and imports without a schemaLocation are looked up by
+    /// their namespace, imports of the xml and xsi namespaces always use the embedded schemas.
+    /// Included and redefined documents without a target namespace become part of the including
+    /// namespace.
+    /// Only the location of the first document has to be resolved, references into documents
+    /// that can not be found are reported as unresolved. Errors name the documents in the order
+    /// they were loaded, the first document being 0
+    pub fn load<R>(location: &str, resolver: &R) -> Result<SchemaSet, SchemaSetError>
+        where R: SchemaResolver + ?Sized {
+        let root = resolver.resolve(None, location).ok_or_else(|| SchemaSetError {
+            document: Some(0),
+            error: SchemaError::UnresolvedLocation(location.to_string()),
+        })?;
+        // the location of each document and the namespace that it is included into, the key
+        // under which a document is only loaded once
+        let mut loaded: Vec<(String, Option<String>)> = vec![(location.to_string(), None)];
+        // the namespace that each document takes as a chameleon, None if it has a target namespace
+        let mut chameleon_namespaces: Vec<Option<String>> = Vec::new();
+        let mut sources = vec![root];
+        let mut next = 0;
+        while next < sources.len() {
+            let at = |error| SchemaSetError { document: Some(next), error };
+            let package = DomParser::parse(&sources[next]).map_err(|_| at(SchemaError::NotWellFormed))?;
+            let document = package.as_document();
+            let is_chameleon = match loaded[next].1 {
+                Some(ref namespace) => include_into(&document, namespace),
+                None => false,
+            };
+            chameleon_namespaces.push(if is_chameleon { loaded[next].1.clone() } else { None });
+            let schema = parse_unchecked(&document, None).map_err(at)?;
+            let includes = schema.includes.iter().map(|include| include.schema_location)
+                .chain(schema.redefines.iter().map(|redefine| redefine.schema_location))
+                .map(|schema_location| (schema.target_namespace, Some(schema_location), true));
+            let imports = schema.imports.iter()
+                .filter(|import| builtin_schema(import.namespace).is_none())
+                .map(|import| (import.namespace, import.schema_location, false));
+            let mut found = Vec::new();
+            for (namespace, schema_location, is_include) in includes.chain(imports) {
+                let referenced = match (schema_location, namespace) {
+                    (Some(schema_location), _) => resolve_location(&loaded[next].0, schema_location.trim()),
+                    (None, Some(namespace)) => namespace.to_string(),
+                    (None, None) => continue,
+                };
+                let key = (referenced, if is_include { namespace.map(str::to_string) } else { None });
+                if loaded.contains(&key) || found.iter().any(|(known, _)| *known == key) {
+                    continue;
+                }
+                if let Some(source) = resolver.resolve(namespace, &key.0) {
+                    found.push((key, source));
+                }
+            }
+            for (key, source) in found {
+                loaded.push(key);
+                sources.push(source);
+            }
+            next += 1;
+        }
+        let sources: Vec<(&str, Option<&str>)> = sources.iter().zip(&chameleon_namespaces)
+            .map(|(source, namespace)| (source.as_str(), namespace.as_deref()))
+            .collect();
+        SchemaSet::from_documents(&sources)
+    }
+
+    /// Builds the set from the texts of schema documents and the namespaces that documents
+    /// without a target namespace of their own are included into
+    fn from_documents(sources: &[(&str, Option<&str>)]) -> Result<SchemaSet, SchemaSetError> {
+        let at = |document| move |error| SchemaSetError { document: Some(document), error };
+        let mut unique: Vec<(usize, (&str, Option<&str>))> = Vec::with_capacity(sources.len());
+        for (index, &source) in sources.iter().enumerate() {
+            if !unique.iter().any(|&(_, known)| known == source) {
+                unique.push((index, source));
+            }
+        }
+        let packages = unique.iter()
+            .map(|&(index, (source, _))| DomParser::parse(source).map_err(|_| at(index)(SchemaError::NotWellFormed)))
+            .collect::<Result<Vec<Package>, SchemaSetError>>()?;
+        let documents: Vec<Document> = packages.iter().map(Package::as_document).collect();
+        for (document, &(_, (_, namespace))) in documents.iter().zip(&unique) {
+            if let Some(namespace) = namespace {
+                include_into(document, namespace);
+            }
+        }
+        let schemas = documents.iter().zip(&unique)
+            .map(|(document, &(index, (source, _)))| {
+                parse_unchecked(document, Some(SourceMap::new(source, document))).map_err(at(index))
+            })
+            .collect::<Result<Vec<Schema>, SchemaSetError>>()?;
+        let given: Vec<&Schema> = schemas.iter().collect();
+        let builtin_packages = builtin_packages(&given);
+        let builtin_documents: Vec<Document> = builtin_packages.iter().map(Package::as_document).collect();
+        let builtins: Vec<Schema> = builtin_documents.iter().map(parse_builtin).collect();
+        let all: Vec<&Schema> = given.iter().cloned().chain(&builtins).collect();
+
+        for (schema, &(index, _)) in given.iter().zip(&unique) {
+            check_schema(schema, &all).map_err(at(index))?;
+        }
+        if let Some((position, error)) = find_duplicate(&given) {
+            return Err(at(unique[position].0)(SchemaError::DuplicateComponent(error)));
+        }
+        let components = Arc::new(Components::new(&all));
+        check_components(&components).map_err(|error| SchemaSetError { document: None, error })?;
+
+        let mut target_namespaces = Vec::new();
+        for schema in &given {
+            let target_namespace = schema.target_namespace.map(str::to_string);
+            if !target_namespaces.contains(&target_namespace) {
+                target_namespaces.push(target_namespace);
+            }
+        }
+        Ok(SchemaSet {
+            sources: sources.iter().map(|&(source, _)| source.to_string()).collect(),
+            target_namespaces,
+            components,
+        })
+    }
+
+    /// The texts of the documents in the order they were given or loaded, a cache is only
+    /// loaded by from_cache for the same texts in the same order
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    /// Serializes the merged components to a versioned binary cache like CompiledSchema::to_cache,
+    /// the cache stores a hash of the texts of all documents of the set
+    pub fn to_cache(&self) -> Vec<u8> {
+        let sources: Vec<&str> = self.sources.iter().map(String::as_str).collect();
+        let mut writer = Writer::new();
+        write_header(&mut writer, &sources);
+        writer.write(&self.target_namespaces);
+        writer.write(&*self.components);
+        writer.into_bytes()
+    }
+
+    /// Loads a set from a cache written by to_cache without parsing XML. The cache is stale
+    /// unless the texts are the ones of the documents of the set in the order they were given
+    /// or loaded, see sources
+    pub fn from_cache(cache: &[u8], sources: &[&str]) -> Result<SchemaSet, CacheError> {
+        let mut reader = Reader::new(cache);
+        read_header(&mut reader, sources)?;
+        let target_namespaces = reader.read()?;
+        let components = reader.read()?;
+        if !reader.is_empty() {
+            return Err(CacheError::Corrupt);
+        }
+        Ok(SchemaSet {
+            sources: sources.iter().map(|&source| source.to_string()).collect(),
+            target_namespaces,
+            components: Arc::new(components),
+        })
+    }
+
+    pub fn target_namespaces(&self) -> &[Option<String>] {
+        &self.target_namespaces
+    }
+
+    pub fn components(&self) -> &Components {
+        &self.components
+    }
+
+    /// A validator for the components, e.g. to limit the number of violations of validate_all
+    pub fn validator(&self) -> Validator {
+        Validator::from_components(self.components.clone())
+    }
+
+    /// Validates a document and stops at the first violation
+    pub fn validate<'d>(&self, document: &Document<'d>) -> Result<Validation<'d>, ValidationError> {
+        self.validator().validate(document)
+    }
+
+    /// Validates a whole document and collects all violations
+    pub fn validate_all<'d>(&self, document: &Document<'d>) -> Validation<'d> {
+        self.validator().validate_all(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use parser::{ComponentError, ComponentKind, QName};
+    use parser::positions::Position;
+    use super::*;
+
+    static ORDER: &str = include_str!("../tests/schema_set/order.xsd");
+    static ITEMS: &str = include_str!("../tests/schema_set/items.xsd");
+    static ADDRESS: &str = include_str!("../tests/schema_set/address.xsd");
+
+    #[test]
+    fn namespaces() {
+        let schema_set = SchemaSet::from_sources(&[ORDER, ITEMS, ADDRESS, ADDRESS]).expect("Failed to load schemas");
+        assert_eq!(&[Some("urn:order".to_string()), Some("urn:address".to_string())], schema_set.target_namespaces());
+
+        let xml = include_str!("../tests/schema_set/order.xml");
+        let validate = |xml: &str| {
+            let package = DomParser::parse(xml).expect("Failed to parse");
+            schema_set.validate(&package.as_document()).map(|_| ())
+        };
+        assert_eq!(Ok(()), validate(xml));
+        assert_eq!(Err(ValidationError::Facet(::datatypes::facets::FacetError::Length)),
+                   validate(&xml.replace("country=\"DE\"", "country=\"DEU\"")));
+        assert_eq!(Err(ValidationError::IncompleteContent), validate(&xml.replace("<city>Berlin</city>", "")));
+    }
+
+    #[test]
+    fn cache() {
+        let schema_set = SchemaSet::from_sources(&[ORDER, ITEMS, ADDRESS]).expect("Failed to load schemas");
+        let cache = schema_set.to_cache();
+        let sources: Vec<&str> = schema_set.sources().iter().map(String::as_str).collect();
+        assert_eq!(vec![ORDER, ITEMS, ADDRESS], sources);
+        let loaded = SchemaSet::from_cache(&cache, &sources).expect("Failed to load cache");
+        assert_eq!(schema_set.target_namespaces(), loaded.target_namespaces());
+
+        let xml = include_str!("../tests/schema_set/order.xml");
+        let package = DomParser::parse(xml).expect("Failed to parse");
+        assert!(loaded.validate(&package.as_document()).is_ok());
+        let package = DomParser::parse(&xml.replace("country=\"DE\"", "country=\"DEU\"")).expect("Failed to parse");
+        assert!(loaded.validate(&package.as_document()).is_err());
+
+        let changed = ADDRESS.replace("length value=\"2\"", "length value=\"3\"");
+        assert_ne!(ADDRESS, changed);
+        assert_eq!(Err(CacheError::Stale), SchemaSet::from_cache(&cache, &[ORDER, ITEMS, &changed]).map(|_| ()));
+        assert_eq!(Err(CacheError::Stale), SchemaSet::from_cache(&cache, &[ITEMS, ORDER, ADDRESS]).map(|_| ()));
+        assert_eq!(Err(CacheError::Stale), SchemaSet::from_cache(&cache, &[ORDER]).map(|_| ()));
+        assert_eq!(Err(CacheError::Corrupt), SchemaSet::from_cache(&cache[..cache.len() - 1], &sources).map(|_| ()));
+    }
+
+    #[test]
+    fn invalid_sets() {
+        // the second document of the namespace is missing
+        assert_eq!(Some(0), SchemaSet::from_sources(&[ORDER, ADDRESS]).unwrap_err().document);
+        // references into namespaces that are not part of the set are left to the validator
+        assert!(SchemaSet::from_sources(&[ORDER, ITEMS]).is_ok());
+
+        let conflicting = ADDRESS.replace("<xsd:length value=\"2\"/>", "<xsd:length value=\"3\"/>");
+        assert_eq!(Err(SchemaSetError {
+                       document: Some(3),
+                       error: SchemaError::DuplicateComponent(ComponentError {
+                           code: "sch-props-correct.2",
+                           kind: ComponentKind::Element,
+                           name: Some("address".to_string()),
+                           attribute: "name",
+                           position: Some(Position { line: 5, column: 5 }),
+                       }),
+                   }),
+                   SchemaSet::from_sources(&[ORDER, ITEMS, ADDRESS, &conflicting]).map(|_| ()));
+        assert_eq!(Some(1), SchemaSet::from_sources(&[ADDRESS, "<xsd:schema"]).unwrap_err().document);
+
+        // an included document that can not be parsed is reported instead of stopping the loader
+        let resolver = |_: Option<&str>, location: &str| match location {
+            "order.xsd" => Some(ORDER.replace("</xsd:schema>", "<xsd:include schemaLocation=\"broken.xsd\"/></xsd:schema>")),
+            "broken.xsd" => Some(ADDRESS.replace("<xsd:length value=\"2\"/>", "<xsd:length value=\"two\"/>")),
+            _ => None,
+        };
+        let error = SchemaSet::load("order.xsd", &resolver).unwrap_err();
+        assert_eq!((Some(1), Some("s4s-att-invalid-value")), (error.document, error.error.code()));
+    }
+
+    #[test]
+    fn final_bases_of_other_documents() {
+        let base = "<xsd:schema xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" targetNamespace=\"urn:base\">\
+                    <xsd:complexType name=\"Sealed\" final=\"#all\"/>\
+                    <xsd:simpleType name=\"Code\" final=\"restriction\">\
+                    <xsd:restriction base=\"xsd:string\"/></xsd:simpleType></xsd:schema>";
+        let derived = |content: &str| format!("<xsd:schema xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" \
+                                               xmlns:b=\"urn:base\" targetNamespace=\"urn:derived\">{}</xsd:schema>",
+                                              content);
+        let error = |derived: &str| SchemaSet::from_sources(&[base, derived]).map(|_| ())
+            .map_err(|error| (error.document, error.error.code()));
+
+        assert_eq!(Err((Some(1), Some("cos-ct-extends.1.1"))),
+                   error(&derived("<xsd:complexType name=\"Extended\"><xsd:complexContent>\
+                                   <xsd:extension base=\"b:Sealed\"/></xsd:complexContent></xsd:complexType>")));
+        assert_eq!(Err((Some(1), Some("st-props-correct.3"))),
+                   error(&derived("<xsd:simpleType name=\"Restricted\">\
+                                   <xsd:restriction base=\"b:Code\"/></xsd:simpleType>")));
+        assert_eq!(Ok(()),
+                   error(&derived("<xsd:simpleType name=\"Codes\"><xsd:list itemType=\"b:Code\"/></xsd:simpleType>")));
+    }
+
+    #[test]
+    fn chameleon_includes() {
+        let resolver = |_: Option<&str>, location: &str| match location {
+            "order.xsd" => Some(include_str!("../tests/schema_set/chameleon/order.xsd").to_string()),
+            "stock.xsd" => Some(include_str!("../tests/schema_set/chameleon/stock.xsd").to_string()),
+            "common.xsd" => Some(include_str!("../tests/schema_set/chameleon/common.xsd").to_string()),
+            _ => None,
+        };
+        let schema_set = SchemaSet::load("order.xsd", &resolver).expect("Failed to load schemas");
+        assert_eq!(&[Some("urn:order".to_string()), Some("urn:stock".to_string())], schema_set.target_namespaces());
+        let find_type = |namespace_uri| schema_set.components().find_type(&QName { namespace_uri, local_name: "SKU" });
+        assert!(find_type(Some("urn:order")).is_some());
+        assert!(find_type(Some("urn:stock")).is_some());
+        assert!(find_type(None).is_none());
+
+        let validate = |sku: &str| {
+            let xml = format!("<o:order xmlns:o=\"urn:order\"><item><sku>123-AB</sku></item>\
+                               <s:stock xmlns:s=\"urn:stock\"><sku>{}</sku></s:stock></o:order>", sku);
+            let package = DomParser::parse(&xml).expect("Failed to parse");
+            schema_set.validate(&package.as_document()).map(|_| ())
+        };
+        assert_eq!(Ok(()), validate("456-CD"));
+        assert_eq!(Err(ValidationError::Pattern), validate("456"));
+    }
+
+    #[test]
+    fn mutual_includes() {
+        let resolver = |_: Option<&str>, location: &str| match location {
+            "person.xsd" => Some(include_str!("../tests/schema_set/cycle/person.xsd").to_string()),
+            "contact.xsd" => Some(include_str!("../tests/schema_set/cycle/contact.xsd").to_string()),
+            _ => None,
+        };
+        let schema_set = SchemaSet::load("person.xsd", &resolver).expect("Failed to load schemas");
+        assert_eq!(&[Some("urn:people".to_string())], schema_set.target_namespaces());
+
+        let validate = |email: &str| {
+            let xml = format!("<p:person xmlns:p=\"urn:people\"><name>Ann</name>\
+                               <contact><email>{}</email></contact></p:person>", email);
+            let package = DomParser::parse(&xml).expect("Failed to parse");
+            schema_set.validate(&package.as_document()).map(|_| ())
+        };
+        assert_eq!(Ok(()), validate("ann@example.com"));
+        assert_eq!(Err(ValidationError::Pattern), validate("ann"));
+    }
+
+    #[test]
+    fn redefines() {
+        let vendor = include_str!("../tests/schema_set/redefine/vendor.xsd");
+        let load = |vendor: &str| {
+            let vendor = vendor.to_string();
+            let resolver = move |_: Option<&str>, location: &str| match location {
+                "vendor.xsd" => Some(vendor.clone()),
+                "legacy.xsd" => Some(include_str!("../tests/schema_set/redefine/legacy.xsd").to_string()),
+                _ => None,
+            };
+            SchemaSet::load("vendor.xsd", &resolver)
+        };
+        let schema_set = load(vendor).expect("Failed to load schemas");
+        let validate = |line: &str, party: &str| {
+            let xml = format!("<v:document xmlns:v=\"urn:vendor\" created=\"2024-01-01\" modified=\"2024-02-01\">\
+                               <party><name>ACME</name>{}</party><line>{}</line><note>Urgent</note></v:document>",
+                              party, line);
+            let package = DomParser::parse(&xml).expect("Failed to parse");
+            schema_set.validate(&package.as_document()).map(|_| ())
+        };
+        assert_eq!(Ok(()), validate("ABC", "<email>info@acme.com</email>"));
+        // the redefinitions replace the originals in the components of the legacy schema
+        assert_eq!(Err(ValidationError::Pattern), validate("abc", "<email>info@acme.com</email>"));
+        assert_eq!(Err(ValidationError::Facet(::datatypes::facets::FacetError::MaxLength)),
+                   validate("ABCDEFGHIJKL", "<email>info@acme.com</email>"));
+        assert_eq!(Err(ValidationError::IncompleteContent), validate("ABC", ""));
+
+        let code = |vendor: &str| load(vendor).err()
+            .and_then(|error| error.error.component().map(|component| component.code));
+        assert_eq!(Some("src-redefine.5"), code(&vendor.replace("base=\"v:Code\"", "base=\"xsd:string\"")));
+        let self_reference = "<xsd:group ref=\"v:Lines\"/>";
+        assert_eq!(Some("src-redefine.6.1.1"), code(&vendor.replace(self_reference, &self_reference.repeat(2))));
+        assert_eq!(Some("src-redefine.6.1.2"),
+                   code(&vendor.replace(self_reference, "<xsd:group ref=\"v:Lines\" maxOccurs=\"2\"/>")));
+        assert_eq!(Some("src-redefine.7.2.2"), code(&vendor.replace("<xsd:attributeGroup ref=\"v:Audit\"/>", "")));
+        // attribute groups without a reference to themselves may only restrict the original
+        let restricted = vendor.replace("<xsd:attribute name=\"modified\" type=\"xsd:date\"/>", "")
+            .replace("<xsd:attributeGroup ref=\"v:Audit\"/>", "<xsd:attribute name=\"created\" type=\"xsd:date\"/>");
+        assert!(load(&restricted).is_ok());
+    }
+}
@@ -0,0 +1,21 @@
+use sxd_document::parser as DomParser;
+
+use compiled::CompiledSchema;
+use parser::SchemaError;
+use parser::schema::Schema;
+
+/// Compiles a schema document of the tests directory, the schema owns its components so that it
+/// outlives the document it was parsed from
+pub fn compile(source: &str) -> CompiledSchema {
+    CompiledSchema::new(source).expect("Failed to parse schema")
+}
+
+/// Parses and checks a schema document with the given top level components and attributes of the
+/// schema element, errors carry no positions
+pub fn check_schema(content: &str, attributes: &str) -> Result<(), SchemaError> {
+    let xml = format!("<xsd:schema xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" {}>{}</xsd:schema>",
+                      attributes, content);
+    let package = DomParser::parse(&xml).expect("Failed to parse");
+    let document = package.as_document();
+    Schema::from_document(&document).map(|_| ())
+}
@@ -0,0 +1,388 @@
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashSet};
+use std::rc::Rc;
+
+use sxd_document::dom::{Element as DomElement, ChildOfElement};
+
+use components::{ComplexTypeId, ComponentName, Compositor, ContentType, ElementId, Particle, Term, TypeDefinition};
+use parser::QName;
+use parser::groups::MaxOccurs;
+use parser::types::DerivationMethod;
+use parser::wildcards::ProcessContents;
+
+use validator::{Validator, Validation, ValidationError, Node, resolve, to_qname, child_elements};
+
+/// The element declaration or wildcard a child element was matched by
+#[derive(Copy, Clone)]
+enum Match {
+    Element(ElementId),
+    Wildcard(ProcessContents),
+}
+
+/// The matches of all children so far, linked backwards so that alternative matches can share
+/// a common beginning
+struct Step {
+    matched: Match,
+    previous: Path,
+}
+
+type Path = Option<Rc<Step>>;
+
+/// The positions in the list of children that can be reached, each with the first path found
+type Frontier = BTreeMap<usize, Path>;
+
+fn merge(frontier: &mut Frontier, other: Frontier) {
+    for (position, path) in other {
+        frontier.entry(position).or_insert(path);
+    }
+}
+
+/// A particle that can not match any element, complex types without other particles have empty content
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#ct-content_type
+fn is_empty_particle(particle: &Particle) -> bool {
+    match particle.term {
+        _ if particle.max_occurs == MaxOccurs::Bounded(0) => true,
+        Term::ModelGroup(ref group) if group.compositor == Compositor::Choice =>
+            group.particles.is_empty() && particle.min_occurs == 0,
+        Term::ModelGroup(ref group) => group.particles.is_empty(),
+        _ => false,
+    }
+}
+
+/// Whitespace as defined by the XML specification
+fn is_whitespace(text: &str) -> bool {
+    text.chars().all(|c| matches!(c, ' ' | '\t' | '\r' | '\n'))
+}
+
+fn is_below(max_occurs: MaxOccurs, count: usize) -> bool {
+    match max_occurs {
+        MaxOccurs::Bounded(max) => count < max,
+        MaxOccurs::Unbounded => true,
+    }
+}
+
+/// Matches the names of the child elements against particles by computing all positions that a
+/// particle can reach from a set of start positions. This handles ambiguous content models
+/// without backtracking and attributes each child to a term of the content model
+struct Matcher<'v, 'd> {
+    validator: &'v Validator,
+    names: Vec<QName<'d>>,
+    // the number of children that the longest match of the beginning of the content model consumed
+    furthest: Cell<usize>,
+    // a position after the names that any element matches, to find out if more elements are allowed
+    probe: Option<usize>,
+}
+
+impl<'v, 'd> Matcher<'v, 'd> {
+    fn new(validator: &'v Validator, names: Vec<QName<'d>>) -> Matcher<'v, 'd> {
+        Matcher { validator, names, furthest: Cell::new(0), probe: None }
+    }
+
+    fn is_match<F>(&self, position: usize, matches: F) -> bool where F: Fn(&QName) -> bool {
+        self.probe == Some(position) || self.names.get(position).is_some_and(matches)
+    }
+
+    fn reach(&self, frontier: Frontier) -> Frontier {
+        if let Some(&position) = frontier.keys().next_back() {
+            self.furthest.set(self.furthest.get().max(position));
+        }
+        frontier
+    }
+
+    /// Matches a particle between its minOccurs and maxOccurs times, every repetition has to
+    /// consume at least one child unless it is needed to reach minOccurs
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cvc-particle
+    fn advance_particle(&self, particle: &Particle, frontier: &Frontier) -> Result<Frontier, ValidationError> {
+        let min_occurs = particle.min_occurs;
+        let max_occurs = particle.max_occurs;
+        let mut result = Frontier::new();
+        let mut current = frontier.clone();
+        let mut count = 0;
+        while !current.is_empty() {
+            if count >= min_occurs {
+                merge(&mut result, current.clone());
+            }
+            if !is_below(max_occurs, count) {
+                break;
+            }
+            let next = self.advance_term(particle, &current)?;
+            if count < min_occurs {
+                // a term that can match nothing reaches the same positions on every repetition
+                count = if next.keys().eq(current.keys()) { min_occurs } else { count + 1 };
+                current = next;
+            } else {
+                // positions reached before were already continued with more repetitions left
+                current = next.into_iter()
+                    .filter(|&(position, _)| !result.contains_key(&position))
+                    .collect();
+                count += 1;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Matches a single occurrence of a particle's term, references to top level element
+    /// declarations also match the members of their substitution group
+    fn advance_term(&self, particle: &Particle, frontier: &Frontier) -> Result<Frontier, ValidationError> {
+        let validator = self.validator;
+        let components = validator.components();
+        match particle.term {
+            Term::Element(ref declaration) => {
+                let declaration = resolve(declaration)?;
+                let element = components.element(declaration);
+                let mut result = self.advance_element(&element.name, Match::Element(declaration), frontier);
+                if element.is_global {
+                    for member in validator.substitutes(declaration)? {
+                        let name = &components.element(member).name;
+                        merge(&mut result, self.advance_element(name, Match::Element(member), frontier));
+                    }
+                }
+                Ok(result)
+            }
+            Term::ModelGroup(ref group) => match group.compositor {
+                Compositor::Sequence => {
+                    let mut current = frontier.clone();
+                    for particle in &group.particles {
+                        current = self.advance_particle(particle, &current)?;
+                    }
+                    Ok(current)
+                }
+                Compositor::Choice => {
+                    let mut result = Frontier::new();
+                    for particle in &group.particles {
+                        merge(&mut result, self.advance_particle(particle, frontier)?);
+                    }
+                    Ok(result)
+                }
+                Compositor::All => self.advance_all(&group.particles, frontier),
+            },
+            Term::Group(ref group) => self.advance_particle(&components.group(resolve(group)?).particle, frontier),
+            Term::Wildcard(ref wildcard) => Ok(self.reach(frontier.iter()
+                .filter(|&(&position, _)| self.is_match(position, |name| wildcard.namespace.allows(name.namespace_uri)))
+                .map(|(&position, path)| (position + 1, Some(Rc::new(Step {
+                    matched: Match::Wildcard(wildcard.process_contents),
+                    previous: path.clone(),
+                }))))
+                .collect())),
+        }
+    }
+
+    fn advance_element(&self, name: &ComponentName, matched: Match, frontier: &Frontier) -> Frontier {
+        self.reach(frontier.iter()
+            .filter(|&(&position, _)| self.is_match(position, |child| name.matches(child)))
+            .map(|(&position, path)| (position + 1, Some(Rc::new(Step {
+                matched,
+                previous: path.clone(),
+            }))))
+            .collect())
+    }
+
+    /// The particles of an all group may occur in any order, this searches all combinations of
+    /// positions and the number of times each particle was matched
+    fn advance_all(&self, particles: &[Particle], frontier: &Frontier) -> Result<Frontier, ValidationError> {
+        let mut result = Frontier::new();
+        for (&start, path) in frontier {
+            let mut visited = HashSet::new();
+            let mut pending = vec![(start, vec![0; particles.len()], path.clone())];
+            while let Some((position, counts, path)) = pending.pop() {
+                if !visited.insert((position, counts.clone())) {
+                    continue;
+                }
+                let is_complete = particles.iter()
+                    .zip(&counts)
+                    .all(|(particle, &count)| count >= particle.min_occurs);
+                if is_complete {
+                    result.entry(position).or_insert_with(|| path.clone());
+                }
+                for (index, particle) in particles.iter().enumerate() {
+                    if !is_below(particle.max_occurs, counts[index]) {
+                        continue;
+                    }
+                    let start = Some((position, path.clone())).into_iter().collect();
+                    for (end, path) in self.advance_term(particle, &start)? {
+                        if end > position {
+                            let mut counts = counts.clone();
+                            counts[index] += 1;
+                            pending.push((end, counts, path));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Validator {
+    /// The particles of a complex type, an extension appends its particle to the ones of its base
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#ct-content_type
+    fn effective_particles(&self, id: ComplexTypeId) -> Result<Vec<&Particle>, ValidationError> {
+        let complex_type = self.components.complex_type(id);
+        let particle = match complex_type.content {
+            ContentType::Complex(ref particle) => particle,
+            ContentType::Simple { .. } => return Ok(Vec::new()),
+        };
+        let mut particles = match (complex_type.method, resolve(&complex_type.base)?) {
+            (DerivationMethod::Extension, TypeDefinition::Complex(base)) => self.effective_particles(base)?,
+            _ => Vec::new(),
+        };
+        particles.extend(particle.as_ref());
+        Ok(particles)
+    }
+
+    /// Computes the positions in the list of child names that the content model of a complex
+    /// type can reach when it is matched from the first child on
+    fn match_children(&self, complex_type: ComplexTypeId, matcher: &Matcher) -> Result<Frontier, ValidationError> {
+        let mut frontier = Frontier::new();
+        frontier.insert(0, None);
+        for particle in self.effective_particles(complex_type)? {
+            frontier = matcher.advance_particle(particle, &frontier)?;
+        }
+        Ok(frontier)
+    }
+
+    /// Tells apart the clauses of cvc-complex-type.2.4 by the longest beginning of the children
+    /// that the content model matched and whether any element may follow it
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cvc-complex-type
+    fn content_error(&self, complex_type: ComplexTypeId, matcher: Matcher) -> Result<ValidationError, ValidationError> {
+        let furthest = matcher.furthest.get();
+        if furthest == matcher.names.len() {
+            return Ok(ValidationError::IncompleteContent);
+        }
+        let mut names = matcher.names;
+        names.truncate(furthest);
+        let probe = Matcher { probe: Some(furthest), ..Matcher::new(self, names) };
+        self.match_children(complex_type, &probe)?;
+        if probe.furthest.get() > furthest {
+            Ok(ValidationError::UnexpectedContent)
+        } else {
+            Ok(ValidationError::NoElementExpected)
+        }
+    }
+
+    /// Checks if the content model of a complex type is satisfied without any child elements
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cos-group-emptiable
+    pub(super) fn is_emptiable(&self, complex_type: ComplexTypeId) -> Result<bool, ValidationError> {
+        Ok(self.match_children(complex_type, &Matcher::new(self, Vec::new()))?.contains_key(&0))
+    }
+
+    /// Character data may only be interleaved with the child elements of mixed types, element only
+    /// content allows whitespace and empty content does not allow any characters
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cvc-complex-type
+    fn check_text(&self, complex_type: ComplexTypeId, element: &DomElement) -> Result<(), ValidationError> {
+        if self.components.complex_type(complex_type).is_mixed {
+            return Ok(());
+        }
+        let is_empty = self.effective_particles(complex_type)?.into_iter().all(is_empty_particle);
+        let has_unexpected_text = element.children().into_iter()
+            .filter_map(|child| match child {
+                ChildOfElement::Text(text) => Some(text.text()),
+                _ => None,
+            })
+            .any(|text| is_empty || !is_whitespace(text));
+        if has_unexpected_text {
+            Err(ValidationError::UnexpectedText)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Validates the text and child elements of an element with complex content against its
+    /// content model and validates each child against the declaration it was matched by
+    pub(super) fn validate_content<'d>(&self, complex_type: ComplexTypeId, element: DomElement<'d>,
+                                       validation: &mut Validation<'d>) -> Result<(), ValidationError> {
+        let result = self.check_text(complex_type, &element);
+        validation.recover(Some(Node::Element(element)), result)?;
+        let children = child_elements(&element);
+        let matches = match self.match_terms(complex_type, &children) {
+            Ok(matches) => matches,
+            Err(error) => {
+                // the children are still validated against their global declarations
+                validation.recover(Some(Node::Element(element)), Err(error))?;
+                return self.validate_lax_children(element, validation);
+            }
+        };
+
+        for (child, matched) in children.into_iter().zip(matches) {
+            let result = match matched {
+                Match::Element(declaration) => self.validate_element(declaration, child, validation),
+                Match::Wildcard(process_contents) =>
+                    match (process_contents, self.components.find_element(&to_qname(child.name()))) {
+                        (ProcessContents::Skip, _) => Ok(()),
+                        (_, Some(declaration)) => self.validate_element(declaration, child, validation),
+                        (ProcessContents::Lax, None) => self.validate_undeclared(child, validation),
+                        (ProcessContents::Strict, None) => Err(ValidationError::UndeclaredElement),
+                    },
+            };
+            validation.recover(Some(Node::Element(child)), result)?;
+        }
+        Ok(())
+    }
+
+    /// The declarations or wildcards of the content model that the children of an element are
+    /// matched by
+    fn match_terms(&self, complex_type: ComplexTypeId, children: &[DomElement]) -> Result<Vec<Match>, ValidationError> {
+        let matcher = Matcher::new(self, children.iter().map(|child| to_qname(child.name())).collect());
+        let mut frontier = self.match_children(complex_type, &matcher)?;
+        let mut path = match frontier.remove(&children.len()) {
+            Some(path) => path,
+            None => return Err(self.content_error(complex_type, matcher)?),
+        };
+        let mut matches = Vec::with_capacity(children.len());
+        while let Some(step) = path {
+            matches.push(step.matched);
+            path = step.previous.clone();
+        }
+        matches.reverse();
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use testing::compile;
+    use validator::ValidationError;
+
+    #[test]
+    fn content_models() {
+        let schema = compile(include_str!("../../tests/validator/content/content.xsd"));
+        let validate = |xml: &str| {
+            let package = DomParser::parse(xml).expect("Failed to parse");
+            schema.validate(&package.as_document()).map(|_| ())
+        };
+
+        // ambiguous repetitions, (a, b?)+ followed by b
+        assert_eq!(Ok(()), validate("<repeated><a/><b/><a/><b/></repeated>"));
+        assert_eq!(Ok(()), validate("<repeated><a/><a/><b/></repeated>"));
+        assert_eq!(Err(ValidationError::IncompleteContent), validate("<repeated><a/></repeated>"));
+
+        // emptiable groups satisfy minOccurs without consuming anything
+        assert_eq!(Ok(()), validate("<emptiable/>"));
+        assert_eq!(Ok(()), validate("<emptiable><c/><c/></emptiable>"));
+
+        assert_eq!(Ok(()), validate("<unordered><b/><a/></unordered>"));
+        assert_eq!(Ok(()), validate("<unordered><a/></unordered>"));
+        assert_eq!(Err(ValidationError::UnexpectedContent), validate("<unordered><a/><a/></unordered>"));
+
+        // the extension's particle follows the particle of its base
+        assert_eq!(Ok(()), validate("<extended><a/><c/><note>x</note></extended>"));
+        assert_eq!(Err(ValidationError::UnexpectedContent), validate("<extended><note>x</note><a/></extended>"));
+
+        assert_eq!(Ok(()), validate("<open><a/><anything><goes/></anything></open>"));
+        assert_eq!(Err(ValidationError::UndeclaredElement), validate("<strict><unknown/></strict>"));
+        assert_eq!(Ok(()), validate("<strict><a/></strict>"));
+
+        // only mixed content allows text between the children, empty content not even whitespace
+        assert_eq!(Ok(()), validate("<narrative>Some <a/> text<a/>.</narrative>"));
+        assert_eq!(Ok(()), validate("<narrative>Only text</narrative>"));
+        assert_eq!(Ok(()), validate("<repeated>\n  <a/>\n  <b/>\n</repeated>"));
+        assert_eq!(Err(ValidationError::UnexpectedText), validate("<repeated><a/>text<b/></repeated>"));
+        assert_eq!(Ok(()), validate("<marker id=\"x\"/>"));
+        assert_eq!(Err(ValidationError::UnexpectedText), validate("<marker> </marker>"));
+        assert_eq!(Err(ValidationError::NoElementExpected), validate("<marker><a/></marker>"));
+    }
+}
@@ -0,0 +1,339 @@
+use std::sync::Arc;
+
+use sxd_document::dom::Element as DomElement;
+
+use components::{
+    AttributeId,
+    AttributeUse,
+    Components,
+    ContentType,
+    ElementId,
+    SimpleTypeDefinition,
+    TypeDefinition,
+    ValueConstraint,
+};
+use datatypes::Value;
+use parser::{ComponentError, ComponentKind, SchemaError};
+use parser::attributes::Usage;
+
+use validator::{
+    Validator,
+    Validation,
+    ValidationError,
+    Node,
+    NodeInfo,
+    Validity,
+    child_elements,
+    resolve,
+    has_content,
+    text_content,
+};
+
+/// A value that was supplied by the default or fixed value of a declaration
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#sic-attrDefault
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum SuppliedValue<'d> {
+    // the normalized value of an attribute that is absent from the element
+    Attribute {
+        element: DomElement<'d>,
+        namespace_uri: Option<String>,
+        local_name: String,
+        value: String,
+    },
+    // the content of an empty element
+    Element {
+        element: DomElement<'d>,
+        value: String,
+    },
+}
+
+impl<'d> Validation<'d> {
+    /// The values that were supplied for absent attributes and empty elements
+    pub fn supplied_values(&self) -> &[SuppliedValue<'d>] {
+        &self.supplied_values
+    }
+
+    /// Adds the supplied attributes and the content of empty elements to the validated document,
+    /// the added attributes get the schema information of their declaration. Calling this again
+    /// does not add them a second time
+    pub fn insert_supplied_values(&mut self) {
+        if self.is_supplied {
+            return;
+        }
+        self.is_supplied = true;
+        for (supplied_value, info) in self.supplied_values.iter().zip(self.supplied_infos.drain(..)) {
+            match *supplied_value {
+                SuppliedValue::Attribute { element, ref namespace_uri, ref local_name, ref value } => {
+                    let name = ::sxd_document::QName::with_namespace_uri(namespace_uri.as_ref().map(String::as_str),
+                                                                        local_name);
+                    let attribute = element.set_attribute_value(name, value);
+                    if let Some(info) = info {
+                        self.nodes.insert(Node::Attribute(attribute), info);
+                    }
+                }
+                SuppliedValue::Element { element, ref value } =>
+                    element.append_child(element.document().create_text(value)),
+            }
+        }
+    }
+
+    fn supply_value(&mut self, supplied_value: SuppliedValue<'d>, info: Option<NodeInfo>) {
+        self.supplied_values.push(supplied_value);
+        self.supplied_infos.push(info);
+    }
+}
+
+/// Distinguishes invalid values from types that can not be resolved
+fn is_valid_value<T>(result: Result<T, ValidationError>) -> Result<bool, ValidationError> {
+    match result {
+        Ok(_) => Ok(true),
+        Err(error @ ValidationError::UnresolvedReference) |
+        Err(error @ ValidationError::InvalidPattern(_)) => Err(error),
+        Err(_) => Ok(false),
+    }
+}
+
+impl Validator {
+    /// Fixed values are compared in the value space of the type, e.g. 1.0 matches a fixed 1
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cvc-au
+    pub(super) fn check_fixed(&self, simple_type: SimpleTypeDefinition, fixed: &str, value: &Value)
+                              -> Result<(), ValidationError> {
+        let (_, fixed) = self.validate_simple(simple_type, fixed)?;
+        if fixed == *value {
+            Ok(())
+        } else {
+            Err(ValidationError::FixedValueMismatch)
+        }
+    }
+
+    /// Validates the text of an element with simple content, an empty element is validated with
+    /// the default or fixed value of its declaration instead
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cvc-elt
+    pub(super) fn validate_text<'d, F>(&self, element: DomElement<'d>, value_constraint: Option<&ValueConstraint>,
+                                       validation: &mut Validation<'d>, validate: F) -> Result<(), ValidationError>
+        where F: Fn(&str) -> Result<(String, Value), ValidationError> {
+        let (normalized, value) = match value_constraint {
+            Some(value_constraint) if !has_content(&element) => {
+                let (normalized, value) = validate(value_constraint.value())?;
+                validation.supply_value(SuppliedValue::Element { element, value: normalized.clone() }, None);
+                (normalized, value)
+            }
+            _ => validate(&text_content(&element))?,
+        };
+        if let Some(ValueConstraint::Fixed(fixed)) = value_constraint {
+            if validate(fixed)?.1 != value {
+                return Err(ValidationError::FixedValueMismatch);
+            }
+        }
+        self.check_references(&value, element)?;
+        validation.insert_value(Node::Element(element), normalized, value)
+    }
+
+    /// Mixed elements take the default or fixed value of their declaration as text if they are
+    /// empty, a fixed value is compared to the text of the element as a string
+    pub(super) fn check_mixed_value<'d>(&self, element: DomElement<'d>, value_constraint: Option<&ValueConstraint>,
+                                        validation: &mut Validation<'d>) -> Result<(), ValidationError> {
+        match value_constraint {
+            Some(value_constraint) if !has_content(&element) => {
+                validation.supply_value(SuppliedValue::Element {
+                    element,
+                    value: value_constraint.value().to_string(),
+                }, None);
+                Ok(())
+            }
+            Some(ValueConstraint::Fixed(fixed)) =>
+                if child_elements(&element).is_empty() && text_content(&element) == *fixed {
+                    Ok(())
+                } else {
+                    Err(ValidationError::FixedValueMismatch)
+                },
+            _ => Ok(()),
+        }
+    }
+
+    /// Reports the default or fixed value of an attribute that is absent from an element
+    pub(super) fn supply_attribute<'d>(&self, element: DomElement<'d>, declaration: AttributeId,
+                                       value_constraint: &ValueConstraint, validation: &mut Validation<'d>)
+                                       -> Result<(), ValidationError> {
+        let attribute = self.components.attribute(declaration);
+        let simple_type = resolve(&attribute.type_definition)?;
+        let (normalized, value) = self.validate_simple(simple_type, value_constraint.value())?;
+        let info = NodeInfo {
+            declaration: Some(self.attribute_info(declaration)),
+            type_definition: Some(self.type_info(TypeDefinition::Simple(simple_type))),
+            validity: Validity::Valid,
+            is_nil: false,
+            normalized_value: Some(normalized.clone()),
+            value: Some(value),
+        };
+        validation.supply_value(SuppliedValue::Attribute {
+            element,
+            namespace_uri: attribute.name.namespace_uri.clone(),
+            local_name: attribute.name.local_name.clone(),
+            value: normalized,
+        }, Some(info));
+        Ok(())
+    }
+
+    /// Default and fixed values of element declarations have to be valid for their type, elements
+    /// with complex content can only have them if they are mixed and their content is emptiable
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cos-valid-default
+    fn is_valid_element_value(&self, element: ElementId, value_constraint: &ValueConstraint)
+                              -> Result<bool, ValidationError> {
+        match self.element_type(element)? {
+            TypeDefinition::Simple(simple_type) =>
+                is_valid_value(self.validate_simple(simple_type, value_constraint.value())),
+            TypeDefinition::Complex(complex_type) => match self.components.complex_type(complex_type).content {
+                ContentType::Simple { .. } =>
+                    is_valid_value(self.validate_simple_content(complex_type, value_constraint.value())),
+                ContentType::Complex(_) =>
+                    Ok(self.components.complex_type(complex_type).is_mixed && self.is_emptiable(complex_type)?),
+            },
+            // anyType is mixed and its content is emptiable
+            TypeDefinition::Any => Ok(true),
+        }
+    }
+
+    /// Default and fixed values of attribute declarations have to be valid for their type
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#a-props-correct
+    fn is_valid_attribute_value(&self, attribute: AttributeId, value_constraint: &ValueConstraint)
+                                -> Result<bool, ValidationError> {
+        let simple_type = resolve(&self.components.attribute(attribute).type_definition)?;
+        is_valid_value(self.validate_simple(simple_type, value_constraint.value()))
+    }
+}
+
+/// The attribute of a declaration that holds its value constraint
+fn constraint_attribute(value_constraint: &ValueConstraint) -> &'static str {
+    match *value_constraint {
+        ValueConstraint::Default(_) => "default",
+        ValueConstraint::Fixed(_) => "fixed",
+    }
+}
+
+/// Checks the default and fixed values of all declarations, declarations whose type can not be
+/// resolved are left to the validation of instances. Defaults are only allowed on optional
+/// attributes
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#src-attribute
+pub(crate) fn check_value_constraints(components: &Arc<Components>) -> Result<(), SchemaError> {
+    let validator = Validator::from_components(components.clone());
+    for (id, element) in components.elements() {
+        if let Some(ref value_constraint) = element.value_constraint {
+            if let Ok(false) = validator.is_valid_element_value(id, value_constraint) {
+                return Err(SchemaError::InvalidValueConstraint(ComponentError {
+                    code: "e-props-correct.2",
+                    kind: ComponentKind::Element,
+                    name: Some(element.name.local_name.clone()),
+                    attribute: constraint_attribute(value_constraint),
+                    position: element.position,
+                }));
+            }
+        }
+    }
+    let attribute_uses = components.complex_types()
+        .flat_map(|(_, complex_type)| complex_type.attribute_uses.iter())
+        .chain(components.attribute_groups().flat_map(|(_, group)| group.attribute_uses.iter()));
+    for attribute_use in attribute_uses {
+        if let AttributeUse { declaration: Ok(id), usage, value_constraint: Some(ValueConstraint::Default(_)) } =
+            *attribute_use {
+            let attribute = components.attribute(id);
+            if !attribute.is_global && usage != Usage::Optional {
+                return Err(SchemaError::InvalidValueConstraint(ComponentError {
+                    code: "src-attribute.2",
+                    kind: ComponentKind::Attribute,
+                    name: Some(attribute.name.local_name.clone()),
+                    attribute: "default",
+                    position: attribute.position,
+                }));
+            }
+        }
+    }
+    for (id, attribute) in components.attributes() {
+        if let Some(ref value_constraint) = attribute.value_constraint {
+            if let Ok(false) = validator.is_valid_attribute_value(id, value_constraint) {
+                return Err(SchemaError::InvalidValueConstraint(ComponentError {
+                    code: "a-props-correct.2",
+                    kind: ComponentKind::Attribute,
+                    name: Some(attribute.name.local_name.clone()),
+                    attribute: constraint_attribute(value_constraint),
+                    position: attribute.position,
+                }));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use parser::{ComponentError, ComponentKind};
+    use testing::{check_schema, compile};
+    use validator::ValidationError;
+    use super::*;
+
+    #[test]
+    fn default_and_fixed_values() {
+        let schema = compile(include_str!("../../tests/validator/defaults/defaults.xsd"));
+        let validate = |xml: &str| {
+            let package = DomParser::parse(xml).expect("Failed to parse");
+            schema.validate(&package.as_document()).map(|_| ())
+        };
+
+        // fixed values are compared in the value space
+        assert_eq!(Ok(()), validate("<order version=\"1.00\"><quantity> 01 </quantity></order>"));
+        assert_eq!(Err(ValidationError::FixedValueMismatch), validate("<order version=\"2\"/>"));
+        assert_eq!(Err(ValidationError::FixedValueMismatch), validate("<order><quantity>2</quantity></order>"));
+        assert_eq!(Ok(()), validate("<order><remark>n/a</remark></order>"));
+        assert_eq!(Ok(()), validate("<order><remark>other <em>text</em></remark></order>"));
+
+        let package = DomParser::parse("<order><note/><quantity/><remark><!-- empty --></remark></order>")
+            .expect("Failed to parse");
+        let document = package.as_document();
+        let order = document.root().children()[0].element().unwrap();
+        let mut validation = schema.validate(&document).expect("Failed to validate");
+        let supplied: Vec<(&str, &str)> = validation.supplied_values().iter()
+            .map(|supplied_value| match *supplied_value {
+                SuppliedValue::Attribute { ref local_name, ref value, .. } => (local_name.as_str(), value.as_str()),
+                SuppliedValue::Element { element, ref value } => (element.name().local_part(), value.as_str()),
+            })
+            .collect();
+        assert_eq!(vec![("currency", "EUR"), ("version", "1"), ("note", "none"), ("quantity", "1.0"), ("remark", "n/a")],
+                   supplied);
+
+        validation.insert_supplied_values();
+        validation.insert_supplied_values();
+        assert_eq!(Some("EUR"), order.attribute_value("currency"));
+        let note = order.children()[0].element().unwrap();
+        assert_eq!(vec!["none"], note.children().iter().filter_map(|child| child.text()).map(|text| text.text())
+            .collect::<Vec<&str>>());
+        let currency = order.attribute("currency").unwrap();
+        assert_eq!(Some("EUR"), validation.normalized_value(Node::Attribute(currency)));
+        assert_eq!(Some(Validity::Valid), validation.node_info(Node::Attribute(currency)).map(|info| info.validity));
+    }
+
+    #[test]
+    fn invalid_value_constraints() {
+        let parse = |content: &str| check_schema(content, "").map_err(|error| error.component().cloned());
+
+        assert_eq!(Err(Some(ComponentError {
+                       code: "e-props-correct.2",
+                       kind: ComponentKind::Element,
+                       name: Some("count".to_string()),
+                       attribute: "default",
+                       position: None,
+                   })),
+                   parse("<xsd:element name=\"count\" type=\"xsd:int\" default=\"many\"/>"));
+        assert_eq!(Err(Some("e-props-correct.2")),
+                   parse("<xsd:element name=\"list\" default=\"x\"><xsd:complexType><xsd:sequence>\
+                          <xsd:element name=\"item\"/></xsd:sequence></xsd:complexType></xsd:element>")
+                       .map_err(|component| component.map(|component| component.code)));
+        assert_eq!(Err(Some("src-attribute.2")),
+                   parse("<xsd:attributeGroup name=\"group\">\
+                          <xsd:attribute name=\"a\" default=\"x\" use=\"required\"/></xsd:attributeGroup>")
+                       .map_err(|component| component.map(|component| component.code)));
+    }
+}
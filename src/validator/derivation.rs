@@ -0,0 +1,121 @@
+use components::{ElementDeclaration, ElementId, SimpleDerivation, SimpleTypeDefinition, TypeDefinition};
+use parser::elements::ElementBlock;
+use parser::types::{BuiltIn, ComplexBlock, DerivationMethod};
+
+use validator::{Validator, ValidationError, resolve};
+
+impl Validator {
+    /// The base type of a type and the method it was derived with, None for anyType which is
+    /// the root of the type hierarchy. Lists and unions are restrictions of anySimpleType
+    fn base_type(&self, type_definition: TypeDefinition)
+                 -> Result<Option<(TypeDefinition, DerivationMethod)>, ValidationError> {
+        let base = match type_definition {
+            TypeDefinition::Any => return Ok(None),
+            TypeDefinition::Complex(id) => {
+                let complex_type = self.components.complex_type(id);
+                return Ok(Some((resolve(&complex_type.base)?, complex_type.method)));
+            }
+            TypeDefinition::Simple(SimpleTypeDefinition::BuiltIn(builtin)) => builtin.base()
+                .map(|base| TypeDefinition::Simple(SimpleTypeDefinition::BuiltIn(base)))
+                .unwrap_or(TypeDefinition::Any),
+            TypeDefinition::Simple(SimpleTypeDefinition::Defined(id)) => match self.components.simple_type(id).derivation {
+                SimpleDerivation::Restriction { ref base, .. } => TypeDefinition::Simple(resolve(base)?),
+                SimpleDerivation::List(_) | SimpleDerivation::Union(_) =>
+                    TypeDefinition::Simple(SimpleTypeDefinition::BuiltIn(BuiltIn::AnySimpleType)),
+            },
+        };
+        Ok(Some((base, DerivationMethod::Restriction)))
+    }
+
+    /// Returns the methods used on the way from a derived type to its base type or None if the
+    /// type is not derived from it. Types are also derived from a union if they are derived
+    /// from one of its members
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cos-ct-derived-ok
+    pub(super) fn derivation_methods(&self, derived: TypeDefinition, base: TypeDefinition)
+                                     -> Result<Option<Vec<DerivationMethod>>, ValidationError> {
+        if let TypeDefinition::Simple(SimpleTypeDefinition::Defined(id)) = base {
+            if let SimpleDerivation::Union(ref member_types) = self.components.simple_type(id).derivation {
+                for member_type in member_types {
+                    let member_type = TypeDefinition::Simple(resolve(member_type)?);
+                    if let Some(methods) = self.derivation_methods(derived, member_type)? {
+                        return Ok(Some(methods));
+                    }
+                }
+            }
+        }
+        let mut methods = Vec::new();
+        let mut current = derived;
+        while current != base {
+            match self.base_type(current)? {
+                Some((base_type, method)) => {
+                    methods.push(method);
+                    current = base_type;
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(methods))
+    }
+
+    /// The type named by xsi:type has to be derived from the declared type without using a
+    /// derivation method that is blocked by the element declaration or the declared type
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cvc-elt
+    pub(super) fn check_type_substitution(&self, declaration: ElementId, xsi_type: TypeDefinition,
+                                          declared_type: TypeDefinition) -> Result<(), ValidationError> {
+        let methods = self.derivation_methods(xsi_type, declared_type)?
+            .ok_or(ValidationError::NotDerived)?;
+        if self.is_blocked(self.components.element(declaration), declared_type, &methods) {
+            Err(ValidationError::Blocked)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks if one of the derivation methods is blocked by the element declaration or its type
+    pub(super) fn is_blocked(&self, declaration: &ElementDeclaration, declared_type: TypeDefinition,
+                             methods: &[DerivationMethod]) -> bool {
+        let is_blocked_by_type = |method| match declared_type {
+            TypeDefinition::Complex(id) => self.components.complex_type(id).block_modes.contains(&method),
+            _ => false,
+        };
+        methods.iter()
+            .any(|&method| match method {
+                DerivationMethod::Extension => declaration.block_modes.contains(&ElementBlock::Extension) ||
+                    is_blocked_by_type(ComplexBlock::Extension),
+                DerivationMethod::Restriction => declaration.block_modes.contains(&ElementBlock::Restriction) ||
+                    is_blocked_by_type(ComplexBlock::Restriction),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use testing::compile;
+    use validator::ValidationError;
+
+    #[test]
+    fn block_and_abstract() {
+        let schema = compile(include_str!("../../tests/validator/derivation/derivation.xsd"));
+        let validate = |content: &str| {
+            let xml = format!("<root xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">{}</root>", content);
+            let package = DomParser::parse(&xml).expect("Failed to parse");
+            schema.validate(&package.as_document()).map(|_| ())
+        };
+
+        assert_eq!(Err(ValidationError::AbstractType), validate("<shape/>"));
+        assert_eq!(Err(ValidationError::AbstractType), validate("<shape xsi:nil=\"true\"/>"));
+        assert_eq!(Ok(()), validate("<shape xsi:type=\"Circle\" radius=\"2\"/>"));
+        assert_eq!(Err(ValidationError::AbstractElement), validate("<item>text</item>"));
+
+        // the block attribute of the declared type
+        assert_eq!(Err(ValidationError::Blocked), validate("<sealed xsi:type=\"SealedPlus\"><b>x</b></sealed>"));
+
+        // blockDefault applies unless the declaration has its own block attribute
+        assert_eq!(Err(ValidationError::Blocked), validate("<base xsi:type=\"Narrow\"/>"));
+        assert_eq!(Ok(()), validate("<open xsi:type=\"Narrow\"/>"));
+    }
+}
@@ -0,0 +1,87 @@
+use sxd_document::dom::Element as DomElement;
+
+use datatypes::Value;
+use parser::parse_qname;
+
+use validator::{Validator, ValidationError};
+
+impl Validator {
+    /// Declares the names of the unparsed entities of the document's DTD which ENTITY values may
+    /// refer to, sxd-document does not expose the DTD so they have to be supplied by the caller
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#ENTITY
+    pub fn with_unparsed_entities<I, S>(mut self, names: I) -> Validator
+        where I: IntoIterator<Item = S>, S: Into<String> {
+        self.unparsed_entities.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// ENTITY values have to name a declared unparsed entity and NOTATION values a notation
+    /// declared in one of the schemas, the prefix of a notation is resolved on the element the
+    /// value appears in
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-2-20041028/datatypes.html#NOTATION
+    pub(super) fn check_references(&self, value: &Value, element: DomElement) -> Result<(), ValidationError> {
+        match *value {
+            Value::Entity(ref name) if !self.unparsed_entities.contains(name) =>
+                Err(ValidationError::UndeclaredEntity),
+            Value::Notation(ref name) => {
+                let is_declared = parse_qname(&element, name)
+                    .is_some_and(|name| self.components.find_notation(&name).is_some());
+                if is_declared {
+                    Ok(())
+                } else {
+                    Err(ValidationError::UndeclaredNotation)
+                }
+            }
+            Value::List(ref items) => items.iter().try_for_each(|item| self.check_references(item, element)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use parser::{ComponentError, ComponentKind};
+    use testing::{check_schema, compile};
+    use validator::ValidationError;
+
+    #[test]
+    fn entities_and_notations() {
+        let schema = compile(include_str!("../../tests/validator/entities/entities.xsd"));
+        assert_eq!(2, schema.components().notations().len());
+        let validator = schema.validator().with_unparsed_entities(vec!["logo", "banner"]);
+        let validate = |xml: &str| {
+            let package = DomParser::parse(xml).expect("Failed to parse");
+            validator.validate(&package.as_document()).map(|_| ())
+        };
+
+        assert_eq!(Ok(()), validate("<image format=\"gif\" source=\"logo\" sources=\"logo banner\"/>"));
+        assert_eq!(Err(ValidationError::UndeclaredEntity), validate("<image source=\"unknown\"/>"));
+        assert_eq!(Err(ValidationError::UndeclaredEntity), validate("<image sources=\"logo unknown\"/>"));
+        assert_eq!(Err(ValidationError::UndeclaredNotation), validate("<image format=\"jpeg\"/>"));
+
+        // without a DTD there are no unparsed entities
+        let package = DomParser::parse("<image source=\"logo\"/>").expect("Failed to parse");
+        assert_eq!(Err(ValidationError::UndeclaredEntity), schema.validate(&package.as_document()).map(|_| ()));
+    }
+
+    #[test]
+    fn notation_requires_enumeration() {
+        let parse = |content: &str| check_schema(content, "").map_err(|error| error.component().cloned());
+
+        assert_eq!(Err(Some(ComponentError {
+                       code: "enumeration-required-notation",
+                       kind: ComponentKind::Attribute,
+                       name: Some("format".to_string()),
+                       attribute: "type",
+                       position: None,
+                   })),
+                   parse("<xsd:attribute name=\"format\" type=\"xsd:NOTATION\"/>"));
+        assert_eq!(Some("base"),
+                   parse("<xsd:simpleType name=\"Format\"><xsd:restriction base=\"xsd:NOTATION\"/></xsd:simpleType>")
+                       .unwrap_err().map(|component| component.attribute));
+    }
+}
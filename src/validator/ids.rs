@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use sxd_document::dom::Element as DomElement;
+
+use datatypes::Value;
+
+use validator::{Validation, ValidationError, Node};
+
+impl<'d> Validation<'d> {
+    /// The element that has an attribute or content of type ID with the given value
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cvc-id
+    pub fn element_by_id(&self, id: &str) -> Option<DomElement<'d>> {
+        self.ids.get(id).cloned()
+    }
+
+    /// All elements by the values of their ID typed attributes or content
+    pub fn ids(&self) -> &HashMap<String, DomElement<'d>> {
+        &self.ids
+    }
+
+    /// Stores the normalized and typed value of a validated node and remembers the IDs it
+    /// declares and the IDs it references, the values of lists are searched item by item
+    pub(super) fn insert_value(&mut self, node: Node<'d>, normalized: String, value: Value)
+                               -> Result<(), ValidationError> {
+        let element = match node {
+            Node::Element(element) => Some(element),
+            Node::Attribute(attribute) => attribute.parent(),
+        };
+        if let Some(element) = element {
+            self.insert_ids(node, element, &value)?;
+        }
+        let info = self.node_info_mut(node);
+        info.normalized_value = Some(normalized);
+        info.value = Some(value);
+        Ok(())
+    }
+
+    fn insert_ids(&mut self, node: Node<'d>, element: DomElement<'d>, value: &Value) -> Result<(), ValidationError> {
+        match *value {
+            Value::Id(ref id) if self.ids.contains_key(id) => return Err(ValidationError::DuplicateId),
+            Value::Id(ref id) => {
+                self.ids.insert(id.clone(), element);
+            }
+            Value::IdRef(ref id) => self.id_references.push((id.clone(), node)),
+            Value::List(ref items) => for item in items {
+                self.insert_ids(node, element, item)?;
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Every IDREF has to match an ID of the same document
+    pub(super) fn check_id_references(&mut self) -> Result<(), ValidationError> {
+        let unresolved: Vec<Node<'d>> = self.id_references.iter()
+            .filter(|&(id, _)| !self.ids.contains_key(id))
+            .map(|&(_, node)| node)
+            .collect();
+        for node in unresolved {
+            self.recover(Some(node), Err(ValidationError::UnresolvedIdReference))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use testing::compile;
+    use validator::ValidationError;
+
+    #[test]
+    fn ids_and_references() {
+        let schema = compile(include_str!("../../tests/validator/ids/ids.xsd"));
+        let validate = |content: &str| {
+            let package = DomParser::parse(&format!("<library>{}</library>", content)).expect("Failed to parse");
+            schema.validate(&package.as_document()).map(|_| ())
+        };
+
+        assert_eq!(Ok(()), validate("<book id=\"b1\"/><book id=\"b2\"/><code>c1</code>\
+                                     <loan book=\"b1\" books=\"b1 b2\" others=\" c1 \"/>"));
+        assert_eq!(Err(ValidationError::DuplicateId), validate("<book id=\"b1\"/><book id=\"b1\"/>"));
+        assert_eq!(Err(ValidationError::DuplicateId), validate("<book id=\"c1\"/><code>c1</code>"));
+        assert_eq!(Err(ValidationError::UnresolvedIdReference), validate("<loan book=\"b1\"/>"));
+        assert_eq!(Err(ValidationError::UnresolvedIdReference), validate("<book id=\"b1\"/><loan books=\"b1 b2\"/>"));
+        assert_eq!(Err(ValidationError::UnresolvedIdReference), validate("<book id=\"b1\"/><loan others=\"b2\"/>"));
+
+        let package = DomParser::parse("<library><book id=\"b1\"/><code>c2</code><loan book=\"c2\"/></library>")
+            .expect("Failed to parse");
+        let document = package.as_document();
+        let validation = schema.validate(&document).expect("Failed to validate");
+        assert_eq!(Some("book"), validation.element_by_id("b1").map(|element| element.name().local_part()));
+        assert_eq!(Some("code"), validation.element_by_id("c2").map(|element| element.name().local_part()));
+        assert_eq!(2, validation.ids().len());
+    }
+}
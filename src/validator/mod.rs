@@ -0,0 +1,604 @@
+mod simple;
+mod content;
+mod derivation;
+mod xsi;
+mod substitution;
+mod ids;
+mod entities;
+mod defaults;
+mod psvi;
+mod report;
+
+pub use self::xsi::validate_with_resolver;
+use self::xsi::is_nil;
+pub use self::defaults::SuppliedValue;
+pub use self::psvi::{DeclarationInfo, NodeInfo, TypeInfo, Validity};
+pub use self::report::Violation;
+pub use components::ComponentName;
+pub(crate) use self::defaults::check_value_constraints;
+pub(crate) use self::substitution::check_substitution_groups;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use sxd_document::dom::{
+    Document,
+    Element as DomElement,
+    Attribute as DomAttribute,
+    ChildOfElement,
+};
+
+use components::{
+    AttributeGroupId,
+    AttributeId,
+    AttributeUse,
+    ComplexTypeId,
+    Components,
+    ContentType,
+    ElementId,
+    Reference,
+    TypeDefinition,
+    ValueConstraint,
+    Wildcard,
+};
+use datatypes::ValueError;
+use datatypes::facets::FacetError;
+use datatypes::patterns::PatternError;
+use parser::{QName, XSI_NS_URI};
+use parser::schema::Schema;
+use parser::attributes::Usage;
+use parser::wildcards::ProcessContents;
+use parser::types::DerivationMethod;
+
+/// Raised when a document is not valid against a schema
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub enum ValidationError {
+    NoRootElement,
+    // an element which is neither declared nor allowed by a wildcard
+    UndeclaredElement,
+    // a child element is not allowed at its position in the content model
+    UnexpectedContent,
+    // the child elements end before the content model is complete
+    IncompleteContent,
+    // a child element follows the complete content and no more elements are allowed
+    NoElementExpected,
+    // an element whose type has simple content has child elements
+    ElementInSimpleContent,
+    // character data in an element with element only or empty content
+    UnexpectedText,
+    UndeclaredAttribute,
+    MissingAttribute,
+    InvalidValue(ValueError),
+    Facet(FacetError),
+    // the value does not match any of the patterns of a derivation step
+    Pattern,
+    InvalidPattern(PatternError),
+    // a type, element, attribute or group reference that can not be found in the schema
+    UnresolvedReference,
+    // xsi:type names a type which is not defined
+    UnknownType,
+    // xsi:type names a type which is not derived from the declared type
+    NotDerived,
+    // the derivation used by xsi:type is blocked by the element declaration or the declared type
+    Blocked,
+    // an abstract element declaration is used directly instead of through substitution
+    AbstractElement,
+    // the type of an element is abstract and no concrete type is given by xsi:type
+    AbstractType,
+    NotNillable,
+    // an element with xsi:nil="true" has text or child elements
+    NilledWithContent,
+    // a schema loaded from a schemaLocation hint can not be parsed
+    InvalidSchemaDocument,
+    // two elements of a document have the same ID
+    DuplicateId,
+    // an IDREF does not match any ID of the document
+    UnresolvedIdReference,
+    // an ENTITY value does not name an unparsed entity of the document
+    UndeclaredEntity,
+    // a NOTATION value does not name a notation declared in the schema
+    UndeclaredNotation,
+    // the value of an element or attribute is not equal to the fixed value of its declaration
+    FixedValueMismatch,
+}
+
+/// An element or attribute of a validated document
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub enum Node<'d> {
+    Element(DomElement<'d>),
+    Attribute(DomAttribute<'d>),
+}
+
+/// The result of a validation
+#[derive(Debug)]
+pub struct Validation<'d> {
+    nodes: HashMap<Node<'d>, NodeInfo>,
+    ids: HashMap<String, DomElement<'d>>,
+    // IDREF values and the nodes they appear in, they are resolved once the whole document is validated
+    id_references: Vec<(String, Node<'d>)>,
+    supplied_values: Vec<SuppliedValue<'d>>,
+    // the schema information of supplied attributes in the order of the supplied values
+    supplied_infos: Vec<Option<NodeInfo>>,
+    // supplied values are only added to the document once
+    is_supplied: bool,
+    violations: Vec<Violation<'d>>,
+    // validation stops once this many violations are found
+    max_errors: Option<usize>,
+    // set once the maximum number of errors is reached, the error that stopped validation is
+    // passed up without being recorded again
+    is_stopped: bool,
+}
+
+impl<'d> Validation<'d> {
+    /// The whitespace normalized value of an attribute or an element with simple content, this is
+    /// the value that was checked against the lexical space, patterns and facets of its type
+    pub fn normalized_value(&self, node: Node<'d>) -> Option<&str> {
+        self.nodes.get(&node).and_then(|info| info.normalized_value.as_ref()).map(String::as_str)
+    }
+}
+
+/// An attribute use of a complex type with its declaration resolved
+struct ResolvedAttributeUse<'c> {
+    declaration: AttributeId,
+    name: &'c ComponentName,
+    usage: Usage,
+    value_constraint: Option<&'c ValueConstraint>,
+}
+
+/// All attribute uses and the attribute wildcard of a complex type including inherited ones
+struct AttributeUses<'c> {
+    declarations: Vec<ResolvedAttributeUse<'c>>,
+    wildcard: Option<&'c Wildcard>,
+}
+
+/// Validates documents against the components of one or more schemas
+pub struct Validator {
+    // the components of the schema to validate against and of the schemas loaded from
+    // schemaLocation hints
+    components: Arc<Components>,
+    // the unparsed entities of the document's DTD
+    unparsed_entities: HashSet<String>,
+    // the number of violations after which validate_all stops
+    max_errors: Option<usize>,
+}
+
+/// A reference that can not be resolved is only an error once validation reaches it
+fn resolve<T: Copy>(reference: &Reference<T>) -> Result<T, ValidationError> {
+    match *reference {
+        Ok(component) => Ok(component),
+        Err(_) => Err(ValidationError::UnresolvedReference),
+    }
+}
+
+fn to_qname<'d>(name: ::sxd_document::QName<'d>) -> QName<'d> {
+    QName {
+        namespace_uri: name.namespace_uri(),
+        local_name: name.local_part(),
+    }
+}
+
+fn child_elements<'d>(element: &DomElement<'d>) -> Vec<DomElement<'d>> {
+    element.children().into_iter()
+        .filter_map(|child| child.element())
+        .collect()
+}
+
+/// Elements with a simple type may only have the attributes from the XMLSchema-instance namespace
+fn check_no_attributes(element: &DomElement) -> Result<(), ValidationError> {
+    let is_unexpected_attribute = element.attributes().iter()
+        .any(|attribute| attribute.name().namespace_uri() != Some(XSI_NS_URI));
+    if is_unexpected_attribute {
+        Err(ValidationError::UndeclaredAttribute)
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks if an element has text or child elements, comments and processing instructions do not count
+fn has_content(element: &DomElement) -> bool {
+    element.children().iter()
+        .any(|child| matches!(*child, ChildOfElement::Element(_) | ChildOfElement::Text(_)))
+}
+
+fn text_content(element: &DomElement) -> String {
+    element.children().into_iter()
+        .filter_map(|child| match child {
+            ChildOfElement::Text(text) => Some(text.text()),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Validator {
+    pub fn new(schema: &Schema) -> Validator {
+        Validator::from_components(schema.components.clone())
+    }
+
+    /// Validates against a component graph which may have been built from several schemas
+    pub fn from_components(components: Arc<Components>) -> Validator {
+        Validator {
+            components,
+            unparsed_entities: HashSet::new(),
+            max_errors: None,
+        }
+    }
+
+    pub fn components(&self) -> &Components {
+        &self.components
+    }
+
+    /// Validates a document and stops at the first violation
+    pub fn validate<'d>(&self, document: &Document<'d>) -> Result<Validation<'d>, ValidationError> {
+        let validation = self.assess(document, Some(1));
+        match validation.violations.first() {
+            Some(violation) => Err(violation.error.clone()),
+            None => Ok(validation),
+        }
+    }
+
+    /// Validates a whole document and collects its violations up to the maximum number of errors
+    pub fn validate_all<'d>(&self, document: &Document<'d>) -> Validation<'d> {
+        self.assess(document, self.max_errors)
+    }
+
+    fn assess<'d>(&self, document: &Document<'d>, max_errors: Option<usize>) -> Validation<'d> {
+        let mut validation = Validation {
+            nodes: HashMap::new(),
+            ids: HashMap::new(),
+            id_references: Vec::new(),
+            supplied_values: Vec::new(),
+            supplied_infos: Vec::new(),
+            is_supplied: false,
+            violations: Vec::new(),
+            max_errors,
+            is_stopped: false,
+        };
+        // the error returned once the maximum number of errors is reached is already recorded
+        let _ = self.validate_document(document, &mut validation);
+        validation
+    }
+
+    fn validate_document<'d>(&self, document: &Document<'d>, validation: &mut Validation<'d>)
+                             -> Result<(), ValidationError> {
+        let root = match document.root().children().into_iter().filter_map(|child| child.element()).next() {
+            Some(root) => root,
+            None => return validation.recover(None, Err(ValidationError::NoRootElement)),
+        };
+        let result = match self.components.find_element(&to_qname(root.name())) {
+            Some(declaration) => self.validate_element(declaration, root, validation),
+            None => Err(ValidationError::UndeclaredElement),
+        };
+        validation.recover(Some(Node::Element(root)), result)?;
+        validation.check_id_references()
+    }
+
+    /// Abstract complex types can not be the type of an element in an instance
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cvc-elt
+    fn check_not_abstract(&self, element_type: TypeDefinition) -> Result<(), ValidationError> {
+        match element_type {
+            TypeDefinition::Complex(id) if self.components.complex_type(id).is_abstract =>
+                Err(ValidationError::AbstractType),
+            _ => Ok(()),
+        }
+    }
+
+    /// Validates an element against its declaration, the declared type can be replaced by a
+    /// type derived from it with xsi:type
+    fn validate_element<'d>(&self, declaration: ElementId, element: DomElement<'d>,
+                            validation: &mut Validation<'d>) -> Result<(), ValidationError> {
+        let info = validation.node_info_mut(Node::Element(element));
+        info.declaration = Some(self.element_info(declaration));
+        info.validity = Validity::Valid;
+        let (element_type, is_nil) = match self.governing_type(declaration, &element) {
+            Ok(governing_type) => governing_type,
+            Err(error) => {
+                // the attributes and children are still validated against their global declarations
+                validation.recover(Some(Node::Element(element)), Err(error))?;
+                return self.validate_lax(element, validation);
+            }
+        };
+        let info = validation.node_info_mut(Node::Element(element));
+        info.type_definition = Some(self.type_info(element_type));
+        info.is_nil = is_nil;
+        if is_nil {
+            return self.validate_nil(declaration, element_type, element, validation);
+        }
+        let value_constraint = self.components.element(declaration).value_constraint.as_ref();
+        self.validate_type(element_type, value_constraint, element, validation)
+    }
+
+    /// The type that an element is validated against and whether it is nilled
+    fn governing_type(&self, declaration: ElementId, element: &DomElement)
+                      -> Result<(TypeDefinition, bool), ValidationError> {
+        if self.components.element(declaration).is_abstract {
+            return Err(ValidationError::AbstractElement);
+        }
+        let declared_type = self.element_type(declaration)?;
+        let element_type = match self.find_xsi_type(element)? {
+            Some(xsi_type) => {
+                self.check_type_substitution(declaration, xsi_type, declared_type)?;
+                xsi_type
+            }
+            None => declared_type,
+        };
+        self.check_not_abstract(element_type)?;
+        Ok((element_type, is_nil(element)?))
+    }
+
+    fn validate_type<'d>(&self, element_type: TypeDefinition, value_constraint: Option<&ValueConstraint>,
+                         element: DomElement<'d>, validation: &mut Validation<'d>) -> Result<(), ValidationError> {
+        match element_type {
+            TypeDefinition::Simple(simple_type) => {
+                validation.recover(Some(Node::Element(element)), check_no_attributes(&element))?;
+                if !child_elements(&element).is_empty() {
+                    return Err(ValidationError::ElementInSimpleContent);
+                }
+                self.validate_text(element, value_constraint, validation,
+                                   |lexical| self.validate_simple(simple_type, lexical))
+            }
+            TypeDefinition::Complex(complex_type) =>
+                self.validate_complex(complex_type, value_constraint, element, validation),
+            TypeDefinition::Any => {
+                let result = self.check_mixed_value(element, value_constraint, validation);
+                validation.recover(Some(Node::Element(element)), result)?;
+                self.validate_lax(element, validation)
+            }
+        }
+    }
+
+    fn validate_complex<'d>(&self, complex_type: ComplexTypeId, value_constraint: Option<&ValueConstraint>,
+                            element: DomElement<'d>, validation: &mut Validation<'d>) -> Result<(), ValidationError> {
+        self.validate_attributes(complex_type, element, validation)?;
+        match self.components.complex_type(complex_type).content {
+            ContentType::Simple { .. } => {
+                if !child_elements(&element).is_empty() {
+                    return Err(ValidationError::ElementInSimpleContent);
+                }
+                self.validate_text(element, value_constraint, validation,
+                                   |lexical| self.validate_simple_content(complex_type, lexical))
+            }
+            ContentType::Complex(_) => {
+                let result = self.check_mixed_value(element, value_constraint, validation);
+                validation.recover(Some(Node::Element(element)), result)?;
+                self.validate_content(complex_type, element, validation)
+            }
+        }
+    }
+
+    /// Validates an element without a declaration against its xsi:type, its validity is not
+    /// known if it has none
+    fn validate_undeclared<'d>(&self, element: DomElement<'d>, validation: &mut Validation<'d>)
+                               -> Result<(), ValidationError> {
+        match self.find_xsi_type(&element)? {
+            Some(xsi_type) => {
+                self.check_not_abstract(xsi_type)?;
+                let info = validation.node_info_mut(Node::Element(element));
+                info.type_definition = Some(self.type_info(xsi_type));
+                info.validity = Validity::Valid;
+                self.validate_type(xsi_type, None, element, validation)
+            }
+            None => {
+                validation.node_info_mut(Node::Element(element));
+                self.validate_lax(element, validation)
+            }
+        }
+    }
+
+    /// Validates the attributes and children of an element for which no type is known, only
+    /// elements and attributes with a top level declaration are validated
+    fn validate_lax<'d>(&self, element: DomElement<'d>, validation: &mut Validation<'d>)
+                        -> Result<(), ValidationError> {
+        for attribute in element.attributes() {
+            let name = to_qname(attribute.name());
+            match self.components.find_attribute(&name) {
+                Some(declaration) => {
+                    let value_constraint = self.components.attribute(declaration).value_constraint.as_ref();
+                    let result = self.validate_attribute(declaration, value_constraint, attribute, validation);
+                    validation.recover(Some(Node::Attribute(attribute)), result)?;
+                }
+                None if name.namespace_uri != Some(XSI_NS_URI) => {
+                    validation.node_info_mut(Node::Attribute(attribute));
+                }
+                None => {}
+            }
+        }
+        self.validate_lax_children(element, validation)
+    }
+
+    /// Validates the children of an element against their global declarations
+    fn validate_lax_children<'d>(&self, element: DomElement<'d>, validation: &mut Validation<'d>)
+                                 -> Result<(), ValidationError> {
+        for child in child_elements(&element) {
+            let result = match self.components.find_element(&to_qname(child.name())) {
+                Some(declaration) => self.validate_element(declaration, child, validation),
+                None => self.validate_undeclared(child, validation),
+            };
+            validation.recover(Some(Node::Element(child)), result)?;
+        }
+        Ok(())
+    }
+
+    fn validate_attribute<'d>(&self, declaration: AttributeId, value_constraint: Option<&ValueConstraint>,
+                              attribute: DomAttribute<'d>, validation: &mut Validation<'d>)
+                              -> Result<(), ValidationError> {
+        let simple_type = resolve(&self.components.attribute(declaration).type_definition)?;
+        let (normalized, value) = self.validate_simple(simple_type, attribute.value())?;
+        if let Some(ValueConstraint::Fixed(fixed)) = value_constraint {
+            self.check_fixed(simple_type, fixed, &value)?;
+        }
+        if let Some(element) = attribute.parent() {
+            self.check_references(&value, element)?;
+        }
+        validation.insert_value(Node::Attribute(attribute), normalized, value)?;
+        let info = validation.node_info_mut(Node::Attribute(attribute));
+        info.declaration = Some(self.attribute_info(declaration));
+        info.type_definition = Some(self.type_info(TypeDefinition::Simple(simple_type)));
+        info.validity = Validity::Valid;
+        Ok(())
+    }
+
+    fn validate_attributes<'d>(&self, complex_type: ComplexTypeId, element: DomElement<'d>,
+                               validation: &mut Validation<'d>) -> Result<(), ValidationError> {
+        let uses = self.collect_attribute_uses(complex_type)?;
+        let attributes = element.attributes();
+        for &attribute in &attributes {
+            let name = to_qname(attribute.name());
+            if name.namespace_uri == Some(XSI_NS_URI) {
+                continue;
+            }
+            let declaration = uses.declarations.iter()
+                .find(|declaration| declaration.name.matches(&name));
+            let result = match (declaration, uses.wildcard) {
+                (Some(declaration), _) =>
+                    self.validate_attribute(declaration.declaration, declaration.value_constraint, attribute, validation),
+                (None, Some(wildcard)) if wildcard.namespace.allows(name.namespace_uri) => {
+                    let global = self.components.find_attribute(&name);
+                    match (wildcard.process_contents, global) {
+                        (ProcessContents::Skip, _) => Ok(()),
+                        (_, Some(global)) => {
+                            let value_constraint = self.components.attribute(global).value_constraint.as_ref();
+                            self.validate_attribute(global, value_constraint, attribute, validation)
+                        }
+                        (ProcessContents::Lax, None) => {
+                            validation.node_info_mut(Node::Attribute(attribute));
+                            Ok(())
+                        }
+                        (ProcessContents::Strict, None) => Err(ValidationError::UndeclaredAttribute),
+                    }
+                }
+                _ => Err(ValidationError::UndeclaredAttribute),
+            };
+            validation.recover(Some(Node::Attribute(attribute)), result)?;
+        }
+        let is_present = |declaration: &ResolvedAttributeUse| attributes.iter()
+            .any(|attribute| declaration.name.matches(&to_qname(attribute.name())));
+        for declaration in &uses.declarations {
+            if !is_present(declaration) && declaration.usage == Usage::Required {
+                validation.recover(Some(Node::Element(element)), Err(ValidationError::MissingAttribute))?;
+            }
+        }
+        for declaration in &uses.declarations {
+            if let (false, Some(value_constraint)) = (is_present(declaration), declaration.value_constraint) {
+                self.supply_attribute(element, declaration.declaration, value_constraint, validation)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the attribute uses of a complex type, attribute uses of the base type are
+    /// inherited unless they are redeclared or prohibited
+    fn collect_attribute_uses(&self, id: ComplexTypeId) -> Result<AttributeUses<'_>, ValidationError> {
+        let complex_type = self.components.complex_type(id);
+        let mut uses = match resolve(&complex_type.base)? {
+            TypeDefinition::Complex(base) => self.collect_attribute_uses(base)?,
+            _ => AttributeUses { declarations: Vec::new(), wildcard: None },
+        };
+        let own = self.resolve_attribute_uses(&complex_type.attribute_uses, &complex_type.attribute_groups,
+                                              complex_type.attribute_wildcard.as_ref())?;
+        for declaration in own.declarations {
+            uses.declarations.retain(|inherited| inherited.name != declaration.name);
+            if declaration.usage != Usage::Prohibited {
+                uses.declarations.push(declaration);
+            }
+        }
+        uses.wildcard = match complex_type.method {
+            DerivationMethod::Extension => own.wildcard.or(uses.wildcard),
+            DerivationMethod::Restriction => own.wildcard,
+        };
+        Ok(uses)
+    }
+
+    fn resolve_attribute_uses<'c>(&'c self, attribute_uses: &'c [AttributeUse], groups: &'c [Reference<AttributeGroupId>],
+                                  wildcard: Option<&'c Wildcard>) -> Result<AttributeUses<'c>, ValidationError> {
+        let mut uses = AttributeUses { declarations: Vec::new(), wildcard };
+        for attribute_use in attribute_uses {
+            let declaration = resolve(&attribute_use.declaration)?;
+            uses.declarations.push(ResolvedAttributeUse {
+                declaration,
+                name: &self.components.attribute(declaration).name,
+                usage: attribute_use.usage,
+                value_constraint: attribute_use.value_constraint.as_ref(),
+            });
+        }
+        for group in groups {
+            let group = self.components.attribute_group(resolve(group)?);
+            let group_uses = self.resolve_attribute_uses(&group.attribute_uses, &group.attribute_groups,
+                                                         group.attribute_wildcard.as_ref())?;
+            uses.declarations.extend(group_uses.declarations);
+            uses.wildcard = uses.wildcard.or(group_uses.wildcard);
+        }
+        Ok(uses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use testing::compile;
+    use super::*;
+
+    #[test]
+    fn normalized_values() {
+        let xml = include_str!("../../tests/validator/mod/purchase.xml");
+        let schema = compile(include_str!("../../tests/parser/mod/purchase.xsd"));
+        let package = DomParser::parse(xml).expect("Failed to parse");
+        let document = package.as_document();
+
+        let validation = schema.validate(&document).expect("Document is invalid");
+
+        let order = document.root().children()[0].element().unwrap();
+        let ship_to = child_elements(&order)[0];
+        let zip = child_elements(&ship_to)[4];
+        let country = ship_to.attribute("country").unwrap();
+        let comment = child_elements(&order)[2];
+        let order_date = order.attribute("orderDate").unwrap();
+        assert_eq!(Some("90952"), validation.normalized_value(Node::Element(zip)));
+        assert_eq!(Some("US"), validation.normalized_value(Node::Attribute(country)));
+        assert_eq!(Some("1999-10-20"), validation.normalized_value(Node::Attribute(order_date)));
+        // xsd:string preserves whitespace
+        assert_eq!(Some("\n        Hurry, my lawn is going wild\n    "),
+                   validation.normalized_value(Node::Element(comment)));
+    }
+
+    #[test]
+    fn invalid_values() {
+        let schema = compile(include_str!("../../tests/parser/mod/purchase.xsd"));
+
+        let xml = include_str!("../../tests/validator/mod/purchase.xml");
+        let validate = |from: &str, to: &str| {
+            let package = DomParser::parse(&xml.replace(from, to)).expect("Failed to parse");
+            schema.validate(&package.as_document()).map(|_| ())
+        };
+        assert_eq!(Ok(()), validate("<quantity>1</quantity>", "<quantity>\n 1 </quantity>"));
+        assert_eq!(Err(ValidationError::Facet(FacetError::MaxExclusive)),
+                   validate("<quantity>1</quantity>", "<quantity>100</quantity>"));
+        assert_eq!(Err(ValidationError::Pattern), validate("872-AA", " 872-AA"));
+        assert_eq!(Err(ValidationError::InvalidValue(ValueError::InvalidDecimal)),
+                   validate("<USPrice>39.98</USPrice>", "<USPrice>39,98</USPrice>"));
+        assert_eq!(Err(ValidationError::MissingAttribute), validate("partNum=\"926-AA\"", ""));
+        assert_eq!(Err(ValidationError::UndeclaredAttribute), validate("<zip>", "<zip unit=\"1\">"));
+        assert_eq!(Err(ValidationError::UnexpectedContent), validate("<city>Mill Valley</city>", ""));
+        assert_eq!(Err(ValidationError::FixedValueMismatch), validate("<billTo country=\"US\">", "<billTo country=\"UK\">"));
+    }
+
+    #[test]
+    fn forms() {
+        let schema = compile(include_str!("../../tests/validator/forms/forms.xsd"));
+        let validate = |attributes: &str, content: &str| {
+            let xml = format!("<f:order xmlns:f=\"urn:forms\" {}>{}</f:order>", attributes, content);
+            let package = DomParser::parse(&xml).expect("Failed to parse");
+            schema.validate(&package.as_document()).map(|_| ())
+        };
+
+        assert_eq!(Ok(()), validate("id=\"1\" f:priority=\"2\"", "<f:item>Lawnmower</f:item><note>Urgent</note>"));
+        // elements are qualified by the elementFormDefault of the schema, attributes are not
+        assert_eq!(Err(ValidationError::UnexpectedContent), validate("", "<item>Lawnmower</item>"));
+        assert_eq!(Err(ValidationError::UnexpectedContent),
+                   validate("", "<f:item>Lawnmower</f:item><f:note>Urgent</f:note>"));
+        assert_eq!(Err(ValidationError::UndeclaredAttribute), validate("f:id=\"1\"", "<f:item>Lawnmower</f:item>"));
+        assert_eq!(Err(ValidationError::UndeclaredAttribute), validate("priority=\"2\"", "<f:item>Lawnmower</f:item>"));
+    }
+}
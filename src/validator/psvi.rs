@@ -0,0 +1,206 @@
+use components::{AttributeId, ComponentName, ElementId, SimpleTypeDefinition, TypeDefinition};
+use datatypes::Value;
+use parser::positions::Position;
+use parser::types::BuiltIn;
+
+use validator::{Validator, Validation, Node};
+
+/// The declaration that governs a validated element or attribute
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#e-declaration
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct DeclarationInfo {
+    pub name: ComponentName,
+    // false for declarations nested inside of a complex type, group or attribute group
+    pub is_global: bool,
+    // only known if the schema was created by Schema::from_source
+    pub position: Option<Position>,
+}
+
+/// The type definition that an element or attribute was validated against
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#e-type_definition
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum TypeInfo {
+    // xsd:anyType
+    Any,
+    BuiltIn(BuiltIn),
+    // types defined in a schema, anonymous types have no name
+    Simple(Option<ComponentName>),
+    Complex(Option<ComponentName>),
+}
+
+/// The outcome of the validation of an element or attribute
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#e-validity
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Validity {
+    Valid,
+    // the node or one of its descendants violates a validation rule
+    Invalid,
+    // neither a declaration nor an xsi:type was found for a node that a lax wildcard allows
+    NotKnown,
+}
+
+/// The schema information that validation adds to an element or attribute
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#sic-e-outcome
+#[derive(PartialEq, Clone, Debug)]
+pub struct NodeInfo {
+    pub declaration: Option<DeclarationInfo>,
+    pub type_definition: Option<TypeInfo>,
+    pub validity: Validity,
+    // the element has xsi:nil="true"
+    pub is_nil: bool,
+    // only attributes and elements with simple content have a value
+    pub normalized_value: Option<String>,
+    pub value: Option<Value>,
+}
+
+impl NodeInfo {
+    fn new() -> NodeInfo {
+        NodeInfo {
+            declaration: None,
+            type_definition: None,
+            validity: Validity::NotKnown,
+            is_nil: false,
+            normalized_value: None,
+            value: None,
+        }
+    }
+}
+
+impl<'d> Validation<'d> {
+    /// The schema information of a validated element or attribute, nodes that were skipped by
+    /// a wildcard and attributes from the XMLSchema-instance namespace have none
+    pub fn node_info(&self, node: Node<'d>) -> Option<&NodeInfo> {
+        self.nodes.get(&node)
+    }
+
+    pub(super) fn node_info_mut(&mut self, node: Node<'d>) -> &mut NodeInfo {
+        self.nodes.entry(node).or_insert_with(NodeInfo::new)
+    }
+}
+
+impl Validator {
+    pub(super) fn element_info(&self, declaration: ElementId) -> DeclarationInfo {
+        let element = self.components.element(declaration);
+        DeclarationInfo {
+            name: element.name.clone(),
+            is_global: element.is_global,
+            position: element.position,
+        }
+    }
+
+    pub(super) fn attribute_info(&self, declaration: AttributeId) -> DeclarationInfo {
+        let attribute = self.components.attribute(declaration);
+        DeclarationInfo {
+            name: attribute.name.clone(),
+            is_global: attribute.is_global,
+            position: attribute.position,
+        }
+    }
+
+    pub(super) fn type_info(&self, type_definition: TypeDefinition) -> TypeInfo {
+        match type_definition {
+            TypeDefinition::Any => TypeInfo::Any,
+            TypeDefinition::Simple(SimpleTypeDefinition::BuiltIn(builtin)) => TypeInfo::BuiltIn(builtin),
+            TypeDefinition::Simple(SimpleTypeDefinition::Defined(id)) =>
+                TypeInfo::Simple(self.components.simple_type(id).name.clone()),
+            TypeDefinition::Complex(id) => TypeInfo::Complex(self.components.complex_type(id).name.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use datatypes::Value;
+    use datatypes::decimal::Decimal;
+    use testing::compile;
+    use parser::types::BuiltIn;
+    use validator::{Node, child_elements};
+    use super::*;
+
+    fn name(local_name: &str) -> ComponentName {
+        ComponentName { namespace_uri: None, local_name: local_name.to_string() }
+    }
+
+    #[test]
+    fn node_infos() {
+        let xml = include_str!("../../tests/validator/mod/purchase.xml");
+        let schema = compile(include_str!("../../tests/parser/mod/purchase.xsd"));
+        let package = DomParser::parse(xml).expect("Failed to parse");
+        let document = package.as_document();
+
+        let validation = schema.validate(&document).expect("Document is invalid");
+
+        let order = document.root().children()[0].element().unwrap();
+        let info = validation.node_info(Node::Element(order)).unwrap();
+        let position = Some(Position { line: 11, column: 5 });
+        assert_eq!(Some(DeclarationInfo { name: name("purchaseOrder"), is_global: true, position }), info.declaration);
+        assert_eq!(Some(TypeInfo::Complex(Some(name("PurchaseOrderType")))), info.type_definition);
+        assert_eq!(Validity::Valid, info.validity);
+        assert_eq!(None, info.value);
+
+        let zip = child_elements(&child_elements(&order)[0])[4];
+        let info = validation.node_info(Node::Element(zip)).unwrap();
+        let position = Some(Position { line: 31, column: 13 });
+        assert_eq!(Some(DeclarationInfo { name: name("zip"), is_global: false, position }), info.declaration);
+        assert_eq!(Some(TypeInfo::BuiltIn(BuiltIn::Decimal)), info.type_definition);
+        assert_eq!(Some(Value::Decimal(Decimal::parse("90952").unwrap())), info.value);
+
+        let order_date = order.attribute("orderDate").unwrap();
+        let info = validation.node_info(Node::Attribute(order_date)).unwrap();
+        assert_eq!(Some("1999-10-20"), info.normalized_value.as_deref());
+        assert_eq!(Some(TypeInfo::BuiltIn(BuiltIn::Date)), info.type_definition);
+
+        let item = child_elements(&child_elements(&order)[3])[0];
+        let info = validation.node_info(Node::Element(item)).unwrap();
+        assert_eq!(Some(TypeInfo::Complex(None)), info.type_definition);
+        let part_number = item.attribute("partNum").unwrap();
+        let info = validation.node_info(Node::Attribute(part_number)).unwrap();
+        assert_eq!(Some(TypeInfo::Simple(Some(name("SKU")))), info.type_definition);
+        assert_eq!(Some(Value::String("872-AA".to_string())), info.value);
+    }
+
+    #[test]
+    fn declaration_positions() {
+        let xml = include_str!("../../tests/validator/mod/purchase.xml");
+        let schema = compile(include_str!("../../tests/parser/mod/purchase.xsd"));
+        let package = DomParser::parse(xml).expect("Failed to parse");
+        let document = package.as_document();
+
+        let validation = schema.validate(&document).expect("Document is invalid");
+
+        let position = |element| validation.node_info(Node::Element(element))
+            .and_then(|info| info.declaration.as_ref())
+            .and_then(|declaration| declaration.position);
+        let order = document.root().children()[0].element().unwrap();
+        assert_eq!(Some(Position { line: 11, column: 5 }), position(order));
+        let zip = child_elements(&child_elements(&order)[0])[4];
+        assert_eq!(Some(Position { line: 31, column: 13 }), position(zip));
+    }
+
+    #[test]
+    fn lax_and_nilled_nodes() {
+        let schema = compile("<xsd:schema xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\
+                             <xsd:element name=\"root\"><xsd:complexType><xsd:sequence>\
+                             <xsd:element name=\"count\" type=\"xsd:int\" nillable=\"true\"/>\
+                             <xsd:any processContents=\"lax\"/><xsd:any processContents=\"skip\"/>\
+                             </xsd:sequence></xsd:complexType></xsd:element></xsd:schema>");
+        let package = DomParser::parse("<root xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\
+                                        <count xsi:nil=\"true\"/><unknown/><skipped/></root>")
+            .expect("Failed to parse");
+        let document = package.as_document();
+
+        let validation = schema.validate(&document).expect("Document is invalid");
+
+        let children = child_elements(&document.root().children()[0].element().unwrap());
+        let info = validation.node_info(Node::Element(children[0])).unwrap();
+        assert!(info.is_nil);
+        assert_eq!(None, info.value);
+        let info = validation.node_info(Node::Element(children[1])).unwrap();
+        assert_eq!((None, Validity::NotKnown), (info.declaration.clone(), info.validity));
+        assert_eq!(None, validation.node_info(Node::Element(children[2])));
+    }
+}
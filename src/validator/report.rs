@@ -0,0 +1,261 @@
+use std::fmt;
+
+use sxd_document::dom::{Element as DomElement, ParentOfChild};
+
+use datatypes::facets::FacetError;
+
+use validator::{
+    Validator,
+    Validation,
+    ValidationError,
+    Node,
+    DeclarationInfo,
+    TypeInfo,
+    Validity,
+};
+
+/// A violation of a validation rule found in a document
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#outcomes
+#[derive(PartialEq, Clone, Debug)]
+pub struct Violation<'d> {
+    // None if the document has no root element
+    pub node: Option<Node<'d>>,
+    // the path of the node, e.g. /purchaseOrder[1]/items[1]/item[2]/@partNum
+    pub location: String,
+    // the W3C constraint code, e.g. cvc-complex-type.2.4
+    pub code: &'static str,
+    pub message: String,
+    pub error: ValidationError,
+    // the declaration and type that governed the node when the violation was found
+    pub declaration: Option<DeclarationInfo>,
+    pub type_definition: Option<TypeInfo>,
+}
+
+impl ValidationError {
+    /// The validation rule of the spec that is violated, some errors are raised by more than one
+    /// clause of a rule and are reported with the rule that applies most often
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#outcome-cos
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ValidationError::NoRootElement | ValidationError::UndeclaredElement => "cvc-elt.1",
+            ValidationError::UnexpectedContent => "cvc-complex-type.2.4.a",
+            ValidationError::IncompleteContent => "cvc-complex-type.2.4.b",
+            ValidationError::NoElementExpected => "cvc-complex-type.2.4.d",
+            ValidationError::ElementInSimpleContent => "cvc-type.3.1.2",
+            ValidationError::UnexpectedText => "cvc-complex-type.2.3",
+            ValidationError::UndeclaredAttribute => "cvc-complex-type.3.2.2",
+            ValidationError::MissingAttribute => "cvc-complex-type.4",
+            ValidationError::InvalidValue(_) |
+            ValidationError::UndeclaredEntity |
+            ValidationError::UndeclaredNotation => "cvc-datatype-valid.1.2.1",
+            ValidationError::Facet(ref error) => match *error {
+                FacetError::MinExclusive => "cvc-minExclusive-valid",
+                FacetError::MinInclusive => "cvc-minInclusive-valid",
+                FacetError::MaxExclusive => "cvc-maxExclusive-valid",
+                FacetError::MaxInclusive => "cvc-maxInclusive-valid",
+                FacetError::TotalDigits => "cvc-totalDigits-valid",
+                FacetError::FractionDigits => "cvc-fractionDigits-valid",
+                FacetError::Length => "cvc-length-valid",
+                FacetError::MinLength => "cvc-minLength-valid",
+                FacetError::MaxLength => "cvc-maxLength-valid",
+                FacetError::Enumeration => "cvc-enumeration-valid",
+                FacetError::InvalidFacetValue => "cvc-facet-valid",
+            },
+            ValidationError::Pattern | ValidationError::InvalidPattern(_) => "cvc-pattern-valid",
+            ValidationError::UnresolvedReference => "src-resolve",
+            ValidationError::UnknownType => "cvc-elt.4.2",
+            ValidationError::NotDerived | ValidationError::Blocked => "cvc-elt.4.3",
+            ValidationError::AbstractElement => "cvc-elt.2",
+            ValidationError::AbstractType => "cvc-type.2",
+            ValidationError::NotNillable => "cvc-elt.3.1",
+            ValidationError::NilledWithContent => "cvc-elt.3.2.1",
+            ValidationError::InvalidSchemaDocument => "schema_reference.4",
+            ValidationError::DuplicateId => "cvc-id.2",
+            ValidationError::UnresolvedIdReference => "cvc-id.1",
+            ValidationError::FixedValueMismatch => "cvc-elt.5.2.2.2",
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match *self {
+            ValidationError::NoRootElement => "The document has no root element",
+            ValidationError::UndeclaredElement => "No declaration is found for the element",
+            ValidationError::UnexpectedContent => "The element is not allowed at this point of the content model",
+            ValidationError::IncompleteContent => "The content ends before the content model is complete",
+            ValidationError::NoElementExpected => "No more child elements are allowed after the complete content",
+            ValidationError::ElementInSimpleContent => "Child elements are not allowed in simple content",
+            ValidationError::UnexpectedText => "Text is not allowed in element only or empty content",
+            ValidationError::UndeclaredAttribute => "The attribute is not allowed by the type of its element",
+            ValidationError::MissingAttribute => "A required attribute is missing",
+            ValidationError::InvalidValue(_) => "The value is not in the lexical space of its type",
+            ValidationError::Facet(_) => "The value does not satisfy a facet of its type",
+            ValidationError::Pattern => "The value does not match the pattern of its type",
+            ValidationError::InvalidPattern(_) => "The pattern of the type is not a valid regular expression",
+            ValidationError::UnresolvedReference => "A reference to a schema component can not be resolved",
+            ValidationError::UnknownType => "xsi:type names a type which is not defined",
+            ValidationError::NotDerived => "xsi:type names a type which is not derived from the declared type",
+            ValidationError::Blocked => "The type derivation used by xsi:type is blocked",
+            ValidationError::AbstractElement => "The element declaration is abstract",
+            ValidationError::AbstractType => "The type of the element is abstract",
+            ValidationError::NotNillable => "xsi:nil is used on an element which is not nillable",
+            ValidationError::NilledWithContent => "An element with xsi:nil=\"true\" has content",
+            ValidationError::InvalidSchemaDocument => "A schema referenced by the document can not be loaded",
+            ValidationError::DuplicateId => "The ID is used more than once in the document",
+            ValidationError::UnresolvedIdReference => "The IDREF does not match any ID of the document",
+            ValidationError::UndeclaredEntity => "The ENTITY is not an unparsed entity of the document",
+            ValidationError::UndeclaredNotation => "The NOTATION is not declared in the schema",
+            ValidationError::FixedValueMismatch => "The value is not equal to the fixed value of its declaration",
+        };
+        f.write_str(message)
+    }
+}
+
+fn node_name(prefix: Option<&str>, local_name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}:{}", prefix, local_name),
+        None => local_name.to_string(),
+    }
+}
+
+/// The path of an element with the position among its siblings of the same name
+fn element_location(element: DomElement) -> String {
+    let position = 1 + element.preceding_siblings().iter()
+        .filter_map(|sibling| sibling.element())
+        .filter(|sibling| sibling.name() == element.name())
+        .count();
+    let step = format!("{}[{}]", node_name(element.preferred_prefix(), element.name().local_part()), position);
+    match element.parent() {
+        Some(ParentOfChild::Element(parent)) => format!("{}/{}", element_location(parent), step),
+        _ => format!("/{}", step),
+    }
+}
+
+fn location(node: Option<Node>) -> String {
+    match node {
+        Some(Node::Element(element)) => element_location(element),
+        Some(Node::Attribute(attribute)) => {
+            let step = format!("@{}", node_name(attribute.preferred_prefix(), attribute.name().local_part()));
+            match attribute.parent() {
+                Some(element) => format!("{}/{}", element_location(element), step),
+                None => step,
+            }
+        }
+        None => "/".to_string(),
+    }
+}
+
+impl<'d> Validation<'d> {
+    /// The violations found in the document in document order of the nodes they were found at,
+    /// except for IDREFs without a matching ID which are only known at the end and come last
+    pub fn violations(&self) -> &[Violation<'d>] {
+        &self.violations
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Records the error of a node and marks it and its ancestors as invalid. Validation goes on
+    /// with the next node unless the maximum number of errors is reached, then the error is
+    /// recorded and returned to stop it
+    pub(super) fn recover(&mut self, node: Option<Node<'d>>, result: Result<(), ValidationError>)
+                          -> Result<(), ValidationError> {
+        let error = match result {
+            Err(error) if !self.is_stopped => error,
+            result => return result,
+        };
+        let (declaration, type_definition) = match node {
+            Some(node) => {
+                let info = self.node_info_mut(node);
+                info.validity = Validity::Invalid;
+                (info.declaration.clone(), info.type_definition.clone())
+            }
+            None => (None, None),
+        };
+        let mut ancestor = match node {
+            Some(Node::Element(element)) => element.parent().and_then(|parent| parent.element()),
+            Some(Node::Attribute(attribute)) => attribute.parent(),
+            None => None,
+        };
+        while let Some(element) = ancestor {
+            if let Some(info) = self.nodes.get_mut(&Node::Element(element)) {
+                info.validity = Validity::Invalid;
+            }
+            ancestor = element.parent().and_then(|parent| parent.element());
+        }
+        let code = match (&error, node, &type_definition) {
+            (&ValidationError::FixedValueMismatch, Some(Node::Attribute(_)), _) => "cvc-au",
+            (&ValidationError::ElementInSimpleContent, _, &Some(TypeInfo::Complex(_))) => "cvc-complex-type.2.2",
+            _ => error.code(),
+        };
+        self.violations.push(Violation {
+            node,
+            location: location(node),
+            code,
+            message: error.to_string(),
+            error: error.clone(),
+            declaration,
+            type_definition,
+        });
+        if self.max_errors.is_some_and(|max_errors| self.violations.len() >= max_errors) {
+            self.is_stopped = true;
+            Err(error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Validator {
+    /// Limits the number of violations that validate_all collects before it stops, the violation
+    /// that reaches the maximum is always recorded so a maximum of 0 stops at the first one
+    pub fn with_max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = Some(max_errors);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use testing::compile;
+    use validator::{Node, Validity};
+
+    #[test]
+    fn violations() {
+        let xml = include_str!("../../tests/validator/report/purchase.xml");
+        let schema = compile(include_str!("../../tests/parser/mod/purchase.xsd"));
+        let package = DomParser::parse(xml).expect("Failed to parse");
+        let document = package.as_document();
+
+        let validation = schema.validate_all(&document);
+        let violations: Vec<(&str, &str)> = validation.violations().iter()
+            .map(|violation| (violation.location.as_str(), violation.code))
+            .collect();
+        assert_eq!(vec![
+            ("/purchaseOrder[1]/shipTo[1]/@country", "cvc-au"),
+            ("/purchaseOrder[1]/shipTo[1]/zip[1]", "cvc-datatype-valid.1.2.1"),
+            ("/purchaseOrder[1]/billTo[1]", "cvc-complex-type.2.4.a"),
+            ("/purchaseOrder[1]/items[1]/item[1]", "cvc-complex-type.4"),
+            ("/purchaseOrder[1]/items[1]/item[2]/quantity[1]", "cvc-maxExclusive-valid"),
+        ], violations);
+        let zip = &validation.violations()[1];
+        assert_eq!(Some("zip"), zip.declaration.as_ref().map(|declaration| declaration.name.local_name.as_str()));
+        assert_eq!("The value is not in the lexical space of its type", zip.message);
+
+        let order = document.root().children()[0].element().unwrap();
+        assert_eq!(Some(Validity::Invalid), validation.node_info(Node::Element(order)).map(|info| info.validity));
+        assert!(!validation.is_valid());
+
+        let validation = schema.validator().with_max_errors(2).validate_all(&document);
+        assert_eq!(2, validation.violations().len());
+        let validation = schema.validator().with_max_errors(0).validate_all(&document);
+        assert_eq!(1, validation.violations().len());
+        assert!(!validation.is_valid());
+    }
+}
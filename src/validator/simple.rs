@@ -0,0 +1,120 @@
+use components::{ComplexTypeId, ContentType, Facet, SimpleDerivation, SimpleTypeDefinition, TypeDefinition};
+use datatypes::{Value, ValueError};
+use datatypes::facets::check_facets;
+use datatypes::whitespace::{normalize, builtin_white_space};
+use parser::types::{BuiltIn, DerivationMethod, WhiteSpaceValue};
+
+use validator::{Validator, ValidationError, resolve};
+
+impl Validator {
+    /// Validates a text or attribute value against a simple type and returns its whitespace
+    /// normalized lexical representation and its value. Every derivation step normalizes the
+    /// value with its own whiteSpace facet, which is idempotent, before passing it on to its base
+    /// see https://www.w3.org/TR/xmlschema11-2/#rf-whiteSpace
+    pub(super) fn validate_simple(&self, simple_type: SimpleTypeDefinition, lexical: &str)
+                                  -> Result<(String, Value), ValidationError> {
+        let simple_type = match simple_type {
+            SimpleTypeDefinition::BuiltIn(builtin) => {
+                let normalized = normalize(lexical, builtin_white_space(builtin));
+                let value = Value::parse(builtin, &normalized).map_err(ValidationError::InvalidValue)?;
+                return Ok((normalized, value));
+            }
+            SimpleTypeDefinition::Defined(id) => self.components.simple_type(id),
+        };
+        match simple_type.derivation {
+            SimpleDerivation::Restriction { ref base, ref facets } => {
+                let base = resolve(base)?;
+                self.validate_restriction(facets, lexical, |lexical| self.validate_simple(base, lexical))
+            }
+            SimpleDerivation::List(ref item_type) => {
+                let item_type = resolve(item_type)?;
+                let normalized = normalize(lexical, WhiteSpaceValue::Collapse);
+                let items = normalized.split_whitespace()
+                    .map(|item| self.validate_simple(item_type, item).map(|(_, value)| value))
+                    .collect::<Result<Vec<Value>, ValidationError>>()?;
+                Ok((normalized, Value::List(items)))
+            }
+            // unions have no whiteSpace facet, each member normalizes the value on its own
+            SimpleDerivation::Union(ref member_types) => {
+                let mut result = Err(ValidationError::InvalidValue(ValueError::InvalidString));
+                for member_type in member_types {
+                    result = self.validate_simple(resolve(member_type)?, lexical);
+                    if result.is_ok() {
+                        break;
+                    }
+                }
+                result
+            }
+        }
+    }
+
+    /// Validates the text of an element whose complex type has simple content, the content type
+    /// is the simple type at the end of the derivation chain restricted by all facets on the way
+    pub(super) fn validate_simple_content(&self, id: ComplexTypeId, lexical: &str)
+                                          -> Result<(String, Value), ValidationError> {
+        let complex_type = self.components.complex_type(id);
+        let (simple_type, facets) = match complex_type.content {
+            ContentType::Simple { simple_type, ref facets } => (simple_type, facets),
+            ContentType::Complex(_) => return Err(ValidationError::UnexpectedContent),
+        };
+        let base = resolve(&complex_type.base)?;
+        let validate_base = |lexical: &str| match base {
+            TypeDefinition::Simple(simple_type) => self.validate_simple(simple_type, lexical),
+            TypeDefinition::Complex(complex_type) => self.validate_simple_content(complex_type, lexical),
+            TypeDefinition::Any => self.validate_simple(SimpleTypeDefinition::BuiltIn(BuiltIn::AnySimpleType), lexical),
+        };
+        match complex_type.method {
+            DerivationMethod::Extension => validate_base(lexical),
+            DerivationMethod::Restriction => {
+                if let Some(simple_type) = simple_type {
+                    self.validate_simple(SimpleTypeDefinition::Defined(simple_type), lexical)?;
+                }
+                self.validate_restriction(facets, lexical, validate_base)
+            }
+        }
+    }
+
+    /// Applies the facets of a single restriction step: the value is normalized if the step has
+    /// its own whiteSpace facet, validated against the base type, matched against the patterns
+    /// and finally checked against the value based facets
+    fn validate_restriction<F>(&self, facets: &[Facet], lexical: &str, validate_base: F)
+                               -> Result<(String, Value), ValidationError>
+        where F: Fn(&str) -> Result<(String, Value), ValidationError> {
+        let white_space = facets.iter()
+            .filter_map(|facet| match *facet {
+                Facet::WhiteSpace(white_space) => Some(white_space),
+                _ => None,
+            })
+            .next_back();
+        let lexical = match white_space {
+            Some(white_space) => normalize(lexical, white_space),
+            None => lexical.to_string(),
+        };
+        let (normalized, value) = validate_base(&lexical)?;
+        check_patterns(facets, &normalized)?;
+        check_facets(&value, facets, |facet| validate_base(facet).map(|(_, value)| value))
+            .map_err(ValidationError::Facet)?;
+        Ok((normalized, value))
+    }
+}
+
+/// Multiple patterns of the same derivation step are alternatives, patterns of different
+/// steps all have to match
+/// see https://www.w3.org/TR/xmlschema11-2/#rf-pattern
+fn check_patterns(facets: &[Facet], normalized: &str) -> Result<(), ValidationError> {
+    let mut has_patterns = false;
+    for facet in facets {
+        if let Facet::Pattern(ref pattern) = *facet {
+            has_patterns = true;
+            let regex = pattern.regex.as_ref().map_err(|&error| ValidationError::InvalidPattern(error))?;
+            if regex.is_match(normalized) {
+                return Ok(());
+            }
+        }
+    }
+    if has_patterns {
+        Err(ValidationError::Pattern)
+    } else {
+        Ok(())
+    }
+}
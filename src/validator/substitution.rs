@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use components::{Components, ElementId, TypeDefinition};
+use parser::{ComponentError, ComponentKind, SchemaError};
+use parser::elements::{ElementBlock, ElementFinal};
+use parser::types::DerivationMethod;
+
+use validator::{Validator, ValidationError, resolve};
+
+impl Validator {
+    /// The type of an element declaration, members of a substitution group which do not declare
+    /// a type already took the type of their first head when the components were built
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#element-element
+    pub(super) fn element_type(&self, declaration: ElementId) -> Result<TypeDefinition, ValidationError> {
+        resolve(&self.components.element(declaration).type_definition)
+    }
+
+    /// The elements which may appear in place of a head element: the members of its substitution
+    /// group and their members, without the ones whose type derivation is blocked by the head
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#cos-equiv-class
+    pub(super) fn substitutes(&self, head: ElementId) -> Result<Vec<ElementId>, ValidationError> {
+        let head_declaration = self.components.element(head);
+        if head_declaration.block_modes.contains(&ElementBlock::Substitution) {
+            return Ok(Vec::new());
+        }
+        let members = self.components.substitution_members(head);
+        let head_type = self.element_type(head)?;
+        let mut substitutes = Vec::with_capacity(members.len());
+        for &member in members {
+            let methods = self.derivation_methods(self.element_type(member)?, head_type)?;
+            if methods.is_some_and(|methods| !self.is_blocked(head_declaration, head_type, &methods)) {
+                substitutes.push(member);
+            }
+        }
+        Ok(substitutes)
+    }
+
+    /// The type of a member has to be derived from the type of its head without using a method
+    /// that is excluded by the final attribute of the head
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#e-props-correct
+    fn is_valid_member(&self, member: ElementId, head: ElementId) -> Result<bool, ValidationError> {
+        let methods = self.derivation_methods(self.element_type(member)?, self.element_type(head)?)?;
+        let final_modes = &self.components.element(head).final_modes;
+        Ok(methods.is_some_and(|methods| methods.iter()
+            .all(|&method| !final_modes.contains(&match method {
+                DerivationMethod::Extension => ElementFinal::Extension,
+                DerivationMethod::Restriction => ElementFinal::Restriction,
+            }))))
+    }
+}
+
+/// Checks the substitution group heads of all top level elements, heads and types which can not
+/// be resolved are left to the validation of instances
+pub(crate) fn check_substitution_groups(components: &Arc<Components>) -> Result<(), SchemaError> {
+    let validator = Validator::from_components(components.clone());
+    let invalid_member = components.global_elements()
+        .find(|&(member, element)| !element.substitution_groups.iter()
+            .filter_map(|head| head.as_ref().ok())
+            .all(|&head| validator.is_valid_member(member, head).unwrap_or(true)));
+    match invalid_member {
+        Some((_, element)) => Err(SchemaError::InvalidSubstitutionGroup(ComponentError {
+            code: "e-props-correct.4",
+            kind: ComponentKind::Element,
+            name: Some(element.name.local_name.clone()),
+            attribute: "substitutionGroup",
+            position: element.position,
+        })),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use testing::{check_schema, compile};
+    use validator::ValidationError;
+
+    #[test]
+    fn substitution_groups() {
+        let schema = compile(include_str!("../../tests/validator/substitution/substitution.xsd"));
+        let validate = |content: &str| {
+            let package = DomParser::parse(&format!("<order>{}</order>", content)).expect("Failed to parse");
+            schema.validate(&package.as_document()).map(|_| ())
+        };
+
+        assert_eq!(Ok(()), validate("<Buyer><name>A</name><account>1</account></Buyer>\
+                                     <Seller><name>B</name></Seller>"));
+        assert_eq!(Err(ValidationError::AbstractElement), validate("<Party><name>A</name></Party>"));
+        assert_eq!(Err(ValidationError::IncompleteContent), validate("<Buyer><name>A</name></Buyer>"));
+
+        // members of members and members of the second head of an XSD 1.1 substitution group
+        assert_eq!(Ok(()), validate("<Agent><name>A</name><account>1</account></Agent>"));
+        assert_eq!(Ok(()), validate("<Seller><name>A</name></Seller><Seller><name>B</name></Seller>\
+                                     <Contact><name>C</name></Contact>"));
+
+        assert_eq!(Ok(()), validate("<Seller><name>A</name></Seller><Fixed>x</Fixed>"));
+        assert_eq!(Err(ValidationError::UnexpectedContent), validate("<Seller><name>A</name></Seller><Variant>x</Variant>"));
+    }
+
+    #[test]
+    fn invalid_members() {
+        let parse = |content: &str| check_schema(content, "").map_err(|error| error.code());
+
+        assert_eq!(Ok(()), parse("<xsd:element name=\"head\" type=\"xsd:decimal\"/>\
+                                  <xsd:element name=\"member\" type=\"xsd:int\" substitutionGroup=\"head\"/>"));
+        assert_eq!(Err(Some("e-props-correct.4")),
+                   parse("<xsd:element name=\"head\" type=\"xsd:int\"/>\
+                          <xsd:element name=\"member\" type=\"xsd:string\" substitutionGroup=\"head\"/>"));
+        assert_eq!(Err(Some("e-props-correct.4")),
+                   parse("<xsd:element name=\"head\" type=\"xsd:decimal\" final=\"restriction\"/>\
+                          <xsd:element name=\"member\" type=\"xsd:int\" substitutionGroup=\"head\"/>"));
+    }
+}
@@ -0,0 +1,196 @@
+use std::sync::Arc;
+
+use sxd_document::{parser as DomParser, Package};
+use sxd_document::dom::{Document, Element as DomElement};
+
+use components::{Components, ElementId, SimpleTypeDefinition, TypeDefinition};
+use datatypes::Value;
+use datatypes::whitespace::normalize;
+use parser::{SchemaError, XSD_NS_URI, XSI_NS_URI, parse_qname};
+use parser::schema::{Schema, builtin_packages, parse_builtin};
+use parser::types::{BuiltIn, WhiteSpaceValue};
+use resolver::SchemaResolver;
+
+use validator::{
+    Validator,
+    Validation,
+    ValidationError,
+    check_no_attributes,
+    child_elements,
+    has_content,
+};
+
+/// Reads the boolean xsi:nil attribute of an element
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#xsi_nil
+pub(super) fn is_nil(element: &DomElement) -> Result<bool, ValidationError> {
+    match element.attribute_value((XSI_NS_URI, "nil")) {
+        Some(value) => match Value::parse(BuiltIn::Boolean, &normalize(value, WhiteSpaceValue::Collapse)) {
+            Ok(Value::Boolean(is_nil)) => Ok(is_nil),
+            Ok(_) => Ok(false),
+            Err(error) => Err(ValidationError::InvalidValue(error)),
+        },
+        None => Ok(false),
+    }
+}
+
+/// Collects the namespaces and locations of all schemaLocation and noNamespaceSchemaLocation
+/// hints of an element and its descendants
+fn find_schema_locations<'d>(element: DomElement<'d>, locations: &mut Vec<(Option<&'d str>, &'d str)>) {
+    if let Some(hints) = element.attribute_value((XSI_NS_URI, "schemaLocation")) {
+        let hints: Vec<&str> = hints.split_whitespace().collect();
+        locations.extend(hints.chunks(2)
+            .filter(|pair| pair.len() == 2)
+            .map(|pair| (Some(pair[0]), pair[1])));
+    }
+    if let Some(location) = element.attribute_value((XSI_NS_URI, "noNamespaceSchemaLocation")) {
+        locations.push((None, location.trim()));
+    }
+    for child in child_elements(&element) {
+        find_schema_locations(child, locations);
+    }
+}
+
+/// Validates a document against the components of a schema and the schemas which are referenced
+/// by its schemaLocation hints. Hints are only followed for namespaces other than the target
+/// namespace of the schema that no schema was loaded for yet, hints that the resolver can not
+/// resolve are ignored
+/// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#schema-loc
+pub fn validate_with_resolver<'d, R>(components: &Arc<Components>, target_namespace: Option<&str>,
+                                     document: &Document<'d>, resolver: &R)
+                                     -> Result<Validation<'d>, ValidationError>
+    where R: SchemaResolver + ?Sized {
+    let mut locations = Vec::new();
+    for root in document.root().children().into_iter().filter_map(|child| child.element()) {
+        find_schema_locations(root, &mut locations);
+    }
+    let mut namespaces = vec![target_namespace];
+    let mut packages = Vec::new();
+    for (namespace, location) in locations {
+        if namespaces.contains(&namespace) {
+            continue;
+        }
+        if let Some(content) = resolver.resolve(namespace, location) {
+            packages.push(DomParser::parse(&content).map_err(|_| ValidationError::InvalidSchemaDocument)?);
+            namespaces.push(namespace);
+        }
+    }
+    if packages.is_empty() {
+        return Validator::from_components(components.clone()).validate(document);
+    }
+    let documents: Vec<Document> = packages.iter().map(Package::as_document).collect();
+    let schemas = documents.iter()
+        .map(|document| Schema::from_document(document))
+        .collect::<Result<Vec<Schema>, SchemaError>>()
+        .map_err(|_| ValidationError::InvalidSchemaDocument)?;
+    let mut resolved: Vec<&Schema> = schemas.iter().collect();
+    let builtin_packages = builtin_packages(&resolved);
+    let builtin_documents: Vec<Document> = builtin_packages.iter().map(Package::as_document).collect();
+    let builtins: Vec<Schema> = builtin_documents.iter().map(parse_builtin).collect();
+    resolved.extend(&builtins);
+    let merged = components.merge(&Components::new(&resolved));
+    Validator::from_components(Arc::new(merged)).validate(document)
+}
+
+impl Validator {
+    /// Resolves the type named by the xsi:type attribute of an element
+    /// see https://www.w3.org/TR/2004/REC-xmlschema-1-20041028/structures.html#xsi_type
+    pub(super) fn find_xsi_type(&self, element: &DomElement) -> Result<Option<TypeDefinition>, ValidationError> {
+        let name = match element.attribute_value((XSI_NS_URI, "type")) {
+            Some(name) => parse_qname(element, name).ok_or(ValidationError::UnknownType)?,
+            None => return Ok(None),
+        };
+        if name.namespace_uri == Some(XSD_NS_URI) {
+            if name.local_name == "anyType" {
+                return Ok(Some(TypeDefinition::Any));
+            }
+            return BuiltIn::from_name(name.local_name)
+                .map(|builtin| Some(TypeDefinition::Simple(SimpleTypeDefinition::BuiltIn(builtin))))
+                .ok_or(ValidationError::UnknownType);
+        }
+        self.components.find_type(&name)
+            .map(Some)
+            .ok_or(ValidationError::UnknownType)
+    }
+
+    /// A nilled element must not have any text or child elements, its attributes are still
+    /// validated against its type
+    pub(super) fn validate_nil<'d>(&self, declaration: ElementId, element_type: TypeDefinition,
+                                   element: DomElement<'d>, validation: &mut Validation<'d>)
+                                   -> Result<(), ValidationError> {
+        if !self.components.element(declaration).is_nillable {
+            return Err(ValidationError::NotNillable);
+        }
+        if has_content(&element) {
+            return Err(ValidationError::NilledWithContent);
+        }
+        match element_type {
+            TypeDefinition::Simple(_) => check_no_attributes(&element),
+            TypeDefinition::Complex(complex_type) => self.validate_attributes(complex_type, element, validation),
+            TypeDefinition::Any => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate sxd_document;
+
+    use sxd_document::parser as DomParser;
+
+    use testing::compile;
+    use validator::ValidationError;
+
+    #[test]
+    fn xsi_type_and_nil() {
+        let schema = compile(include_str!("../../tests/validator/xsi/xsi.xsd"));
+        let validate = |content: &str| {
+            let xml = format!("<root xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+                               xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">{}</root>", content);
+            let package = DomParser::parse(&xml).expect("Failed to parse");
+            schema.validate(&package.as_document()).map(|_| ())
+        };
+
+        assert_eq!(Ok(()), validate("<address><name>A</name></address>"));
+        assert_eq!(Ok(()), validate("<address xsi:type=\"USAddress\"><name>A</name><zip>1</zip></address>"));
+        assert_eq!(Err(ValidationError::NoElementExpected), validate("<address><name>A</name><zip>1</zip></address>"));
+        assert_eq!(Err(ValidationError::NotDerived), validate("<address xsi:type=\"Other\"/>"));
+        assert_eq!(Err(ValidationError::UnknownType), validate("<address xsi:type=\"Unknown\"/>"));
+        assert_eq!(Err(ValidationError::Blocked),
+                   validate("<blocked xsi:type=\"USAddress\"><name>A</name><zip>1</zip></blocked>"));
+
+        assert_eq!(Ok(()), validate("<number xsi:type=\"xsd:byte\">12</number>"));
+        assert_eq!(Ok(()), validate("<number xsi:type=\"SmallNumber\">12</number>"));
+        assert_eq!(Err(ValidationError::NotDerived), validate("<number xsi:type=\"xsd:string\">12</number>"));
+        assert_eq!(Err(ValidationError::InvalidValue(::datatypes::ValueError::OutOfRange)),
+                   validate("<number xsi:type=\"xsd:byte\">1200</number>"));
+
+        assert_eq!(Ok(()), validate("<note xsi:nil=\"true\"/>"));
+        assert_eq!(Ok(()), validate("<note xsi:nil=\"false\"/>"));
+        assert_eq!(Err(ValidationError::NilledWithContent), validate("<note xsi:nil=\"true\">text</note>"));
+        assert_eq!(Err(ValidationError::NotNillable), validate("<number xsi:nil=\"1\"/>"));
+    }
+
+    #[test]
+    fn schema_location() {
+        let schema = compile(include_str!("../../tests/validator/xsi/xsi.xsd"));
+        let resolver = |namespace: Option<&str>, location: &str| match (namespace, location) {
+            (Some("urn:other"), "other.xsd") => Some(include_str!("../../tests/validator/xsi/other.xsd").to_string()),
+            _ => None,
+        };
+        let validate = |item: &str| {
+            let xml = format!("<root xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+                               xsi:schemaLocation=\"urn:other other.xsd urn:unknown unknown.xsd\">\
+                               <other xmlns=\"urn:other\">{}</other></root>", item);
+            let package = DomParser::parse(&xml).expect("Failed to parse");
+            let document = package.as_document();
+            let without_resolver = schema.validate(&document).map(|_| ());
+            let with_resolver = schema.validate_with_resolver(&document, &resolver).map(|_| ());
+            (without_resolver, with_resolver)
+        };
+
+        assert_eq!((Err(ValidationError::UndeclaredElement), Ok(())), validate("12"));
+        assert_eq!((Err(ValidationError::UndeclaredElement),
+                    Err(ValidationError::InvalidValue(::datatypes::ValueError::InvalidDecimal))),
+                   validate("twelve"));
+    }
+}